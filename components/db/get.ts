@@ -6,7 +6,8 @@ type EmptyParam = Record<string, never>;
 type FetcherParam =
   | { endpoint: "get_all_recipes"; param: EmptyParam }
   | { endpoint: "get_recipe_by_id"; param: { id: number } }
-  | { endpoint: "does_recipe_exist_by_url"; param: { url: string } };
+  | { endpoint: "does_recipe_exist_by_url"; param: { url: string } }
+  | { endpoint: "get_import_jobs"; param: EmptyParam };
 
 const fetcher = async <T>([{ endpoint, param }]: FetcherParam[]): Promise<T> => {
   try {
@@ -1,4 +1,9 @@
+import { DuplicatePolicy } from "@/src-tauri/bindings/DuplicatePolicy";
+import { ImportJob } from "@/src-tauri/bindings/ImportJob";
+import { Images } from "@/src-tauri/bindings/Images";
 import { RecipeDetails } from "@/src-tauri/bindings/RecipeDetails";
+import { RecipeLabels } from "@/src-tauri/bindings/RecipeLabels";
+import { UrlImportReport } from "@/src-tauri/bindings/UrlImportReport";
 import { invoke } from "@/components/lib/tauri";
 import { addRecipeToast, deleteRecipeToast, updateRecipeToast } from "@/components/lib/toasts";
 import { createStandaloneToast } from "@chakra-ui/react";
@@ -17,8 +22,18 @@ export const updateRecipe = (recipe: RecipeDetails) => {
     });
 };
 
-export const addRecipe = (recipe: RecipeDetails) => {
-  invoke("add_recipe", { recipe: recipe })
+export const addRecipe = (
+  recipe: RecipeDetails,
+  labels?: RecipeLabels,
+  onDuplicate?: DuplicatePolicy,
+  images?: Images
+) => {
+  invoke("add_recipe", {
+    recipe: recipe,
+    labels: labels ?? null,
+    onDuplicate: onDuplicate ?? null,
+    images: images ?? null,
+  })
     .then(() => {
       toast(addRecipeToast("success"));
       console.log("Request successfully made");
@@ -29,6 +44,31 @@ export const addRecipe = (recipe: RecipeDetails) => {
     });
 };
 
+export const createRecipe = (recipe: RecipeDetails, imagePath?: string, labels?: RecipeLabels) =>
+  invoke<number>("create_recipe", { recipe: recipe, imagePath: imagePath ?? null, labels: labels ?? null })
+    .then((id) => {
+      toast(addRecipeToast("success"));
+      return id;
+    })
+    .catch((e) => {
+      toast(addRecipeToast("error"));
+      console.error(e);
+      return null;
+    });
+
+export const importRecipeFromUrl = (url: string, onDuplicate?: DuplicatePolicy) =>
+  invoke<UrlImportReport>("import_recipe_from_url", { url: url, onDuplicate: onDuplicate ?? null }).then((report) => {
+    toast(addRecipeToast(report.status === "imported" ? "success" : "error"));
+    return report;
+  });
+
+export const enqueueUrlImports = (urls: string[]) =>
+  invoke<ImportJob[]>("enqueue_url_imports", { urls: urls }).catch((e) => {
+    toast(addRecipeToast("error"));
+    console.error(e);
+    return [] as ImportJob[];
+  });
+
 export const deleteRecipe = (id: number) => {
   invoke("delete_recipe_by_id", { id: id })
     .then(() => {
@@ -1,9 +1,23 @@
-import { convertFileSrc } from "@tauri-apps/api/tauri";
+// Saved images are named after the hash of their content, variants get a suffix
+const SAVED_IMAGE_NAME = /^([0-9a-f]{64})(?:-(thumb|medium))?\.\w+$/;
+
+// Custom protocols are served over https on Windows, as `https://<protocol>.<path>`
+const imageProtocolUrl = (path: string): string =>
+  navigator.userAgent.includes("Windows") ? `https://foodie.${path}` : `foodie://${path}`;
+
+const isLocalPath = (url: string): boolean => url.startsWith("/") || /^[A-Za-z]:[\\/]/.test(url);
 
 export const getRightImage = (url: string): string => {
-  if (url.startsWith("/")) {
-    return convertFileSrc(url);
-  } else {
+  if (!isLocalPath(url)) {
     return url;
   }
+  const name = url.split(/[\\/]/).pop() ?? "";
+  const match = name.match(SAVED_IMAGE_NAME);
+  if (match) {
+    const [, id, variant] = match;
+    return imageProtocolUrl(variant ? `image/${id}/${variant}` : `image/${id}`);
+  } else {
+    // Images saved before they were stored by content are loaded by name until they're indexed
+    return imageProtocolUrl(`image/file/${name}`);
+  }
 };
@@ -15,6 +15,12 @@ export function processRecipeData(edamamHit: Hit, recipeScrapersData: RecipeData
     title: edamamHit.recipe.label,
     id: null,
     date_added: null,
+    deleted_at: null,
+    version: null,
+    updated_at: null,
+    canonical_url: recipeScrapersData.canonical_url,
+    image_thumbnail: null,
+    image_medium: null,
   };
 }
 
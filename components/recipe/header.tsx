@@ -1,5 +1,7 @@
 import { useRouter } from "next/router";
 import { RecipeDetails } from "@/src-tauri/bindings/RecipeDetails";
+import { RecipeLabels } from "@/src-tauri/bindings/RecipeLabels";
+import { Images } from "@/src-tauri/bindings/Images";
 import { ChatIcon, EditIcon, ExternalLinkIcon } from "@chakra-ui/icons";
 import {
   Button,
@@ -26,9 +28,11 @@ interface HeaderProps {
   servings: number;
   time: number;
   calories: number;
-  url: string;
+  url: string | null;
   host: string | null;
   recipe: RecipeDetails;
+  labels?: RecipeLabels;
+  images?: Images;
   action: "search" | "saved";
   openModal: () => void;
 }
@@ -41,12 +45,14 @@ const RecipeHeader = ({
   url,
   host,
   recipe,
+  labels,
+  images,
   action,
   openModal,
 }: HeaderProps) => {
   const [saved, setSaved] = useState(false);
   const handleSave = () => {
-    addRecipe(recipe);
+    addRecipe(recipe, labels, undefined, images);
     setSaved(true);
   };
 
@@ -62,7 +68,7 @@ const RecipeHeader = ({
 
   const { data: savedInDB } = useFetchData<boolean>({
     endpoint: "does_recipe_exist_by_url",
-    param: { url: url },
+    param: { url: url ?? "" },
   });
 
   useEffect(() => {
@@ -199,13 +205,15 @@ const RecipeHeader = ({
               </ButtonGroup>
             </Flex>
             {/* Link to original URL */}
-            <Link href={url} isExternal textAlign={{ base: "center", md: "left" }}>
-              <Button size="sm" variant="link" rightIcon={<ExternalLinkIcon />}>
-                <Text fontFamily="mono" as="u">
-                  {host ? `View original recipe on ${host}` : "View original recipe"}
-                </Text>
-              </Button>
-            </Link>
+            {url && (
+              <Link href={url} isExternal textAlign={{ base: "center", md: "left" }}>
+                <Button size="sm" variant="link" rightIcon={<ExternalLinkIcon />}>
+                  <Text fontFamily="mono" as="u">
+                    {host ? `View original recipe on ${host}` : "View original recipe"}
+                  </Text>
+                </Button>
+              </Link>
+            )}
           </Stack>
         </Flex>
       </Flex>
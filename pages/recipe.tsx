@@ -8,6 +8,8 @@ import RecipeHeader from "@/components/recipe/header";
 import RecipeAccordian from "@/components/recipe/accordian";
 import Spinner from "@/components/spinner";
 import { RecipeDetails } from "@/src-tauri/bindings/RecipeDetails";
+import { RecipeLabels } from "@/src-tauri/bindings/RecipeLabels";
+import { Images } from "@/src-tauri/bindings/Images";
 import { processRecipeData } from "@/components/lib/recipe";
 import { getRightImage } from "@/components/lib/image";
 import { ParsedUrlQuery } from "querystring";
@@ -33,6 +35,8 @@ const Recipe = () => {
   const [errorStatus, setErrorStatus] = useState(false);
   const [recipeDetails, setRecipeDetails] = useState<RecipeDetails | null>(null);
   const [image, setImage] = useState<string>("");
+  const [labels, setLabels] = useState<RecipeLabels | undefined>(undefined);
+  const [images, setImages] = useState<Images | undefined>(undefined);
   const { isOpen, onOpen, onClose } = useDisclosure();
 
   // Processes data if required and sets state used in UI
@@ -41,6 +45,8 @@ const Recipe = () => {
       const decodedData = decodeURIComponent(hitData);
       const edamamData: Hit = JSON.parse(decodedData) as Hit;
       setImage(edamamData.recipe.image);
+      setLabels(edamamData.recipe);
+      setImages(edamamData.recipe.images);
       invoke("get_recipe_details", { url: url })
         .then((value: unknown) => {
           const scraped_data = value as RecipeData;
@@ -57,7 +63,7 @@ const Recipe = () => {
       const decodedData = decodeURIComponent(savedRecipe);
       const parsedData: RecipeDetails = JSON.parse(decodedData) as RecipeDetails;
       setRecipeDetails(parsedData);
-      const image = getRightImage(parsedData.image);
+      const image = getRightImage(parsedData.image_medium ?? parsedData.image);
       setImage(image);
     }
   }, [hitData, savedRecipe, pageAction, url]);
@@ -78,6 +84,8 @@ const Recipe = () => {
             url={recipeDetails.url}
             host={recipeDetails.source}
             recipe={recipeDetails}
+            labels={labels}
+            images={images}
             action={pageAction}
             openModal={onOpen}
           />
@@ -0,0 +1,3 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+export interface ArchiveEntry { path: string, sha256: string, size: bigint, source_path: string | null, }
\ No newline at end of file
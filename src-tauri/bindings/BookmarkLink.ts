@@ -0,0 +1,3 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+export interface BookmarkLink { url: string, title: string, folders: Array<string>, is_scrapable: boolean, recipe_id: number | null, }
\ No newline at end of file
@@ -0,0 +1,4 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { BookmarkLink } from "./BookmarkLink";
+
+export interface BookmarkReport { links: Array<BookmarkLink>, skipped: number, }
\ No newline at end of file
@@ -0,0 +1,3 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+export interface Collection { id: number, name: string, cover_image: string | null, position: number, recipe_count: number, date_added: string | null, }
\ No newline at end of file
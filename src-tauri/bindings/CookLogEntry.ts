@@ -0,0 +1,3 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+export interface CookLogEntry { id: number | null, recipe_id: number, cooked_on: string | null, rating: number | null, cook: string | null, servings_made: number | null, notes: string | null, date_added: string | null, }
\ No newline at end of file
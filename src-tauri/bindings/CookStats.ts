@@ -0,0 +1,3 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+export interface CookStats { recipe_id: number, times_cooked: number, last_cooked: string | null, average_rating: number | null, }
\ No newline at end of file
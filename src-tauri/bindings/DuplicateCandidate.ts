@@ -0,0 +1,3 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+export interface DuplicateCandidate { recipe_id: number, title: string, duplicate_id: number, duplicate_title: string, title_similarity: number, ingredient_overlap: number | null, score: number, }
\ No newline at end of file
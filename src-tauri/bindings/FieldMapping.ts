@@ -0,0 +1,5 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { ListSplit } from "./ListSplit";
+import type { RecipeField } from "./RecipeField";
+
+export interface FieldMapping { column: string, field: RecipeField, split: ListSplit | null, }
\ No newline at end of file
@@ -0,0 +1,4 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { ImageReference } from "./ImageReference";
+
+export interface ImageGcReport { files_removed: number, bytes_reclaimed: bigint, images_indexed: number, missing: Array<ImageReference>, }
\ No newline at end of file
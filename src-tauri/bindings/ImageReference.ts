@@ -0,0 +1,3 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+export interface ImageReference { path: string, recipe_ids: Array<number>, collection_ids: Array<number>, }
\ No newline at end of file
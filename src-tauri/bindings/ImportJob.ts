@@ -0,0 +1,4 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { ImportJobStatus } from "./ImportJobStatus";
+
+export interface ImportJob { id: number, url: string, status: ImportJobStatus, attempts: number, recipe_id: number | null, error: string | null, created_at: string, updated_at: string, }
\ No newline at end of file
@@ -0,0 +1,4 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { ImportJob } from "./ImportJob";
+
+export interface ImportJobEvent { job: ImportJob, remaining: number, }
\ No newline at end of file
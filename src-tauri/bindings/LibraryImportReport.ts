@@ -0,0 +1,3 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+export interface LibraryImportReport { recipes_added: number, recipes_skipped: number, images_restored: number, }
\ No newline at end of file
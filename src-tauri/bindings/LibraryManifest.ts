@@ -0,0 +1,4 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { ArchiveEntry } from "./ArchiveEntry";
+
+export interface LibraryManifest { format_version: number, app_version: string, schema_version: number, created_at: string, recipe_count: number, files: Array<ArchiveEntry>, }
\ No newline at end of file
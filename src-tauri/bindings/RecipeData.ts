@@ -1,3 +1,3 @@
 // This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
 
-export interface RecipeData { canonical_url: string | null, category: string | null, host: string | null, image: string | null, ingredients: Array<string> | null, instructions: string | null, instructions_list: Array<string> | null, language: string | null, ratings: number | null, site_name: string | null, title: string | null, yields: string | null, }
\ No newline at end of file
+export interface RecipeData { canonical_url: string | null, category: string | null, cook_time: number | null, host: string | null, image: string | null, ingredients: Array<string> | null, instructions: string | null, instructions_list: Array<string> | null, language: string | null, nutrients: Record<string, string> | null, prep_time: number | null, ratings: number | null, site_name: string | null, title: string | null, total_time: number | null, yields: string | null, }
\ No newline at end of file
@@ -1,3 +1,3 @@
 // This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
 
-export interface RecipeDetails { image: string, url: string, servings: number, time: number, calories: number, source: string | null, ingredients: Array<string> | null, instructions: Array<string> | null, title: string, id: number | null, date_added: string | null, }
\ No newline at end of file
+export interface RecipeDetails { image: string, url: string | null, servings: number, time: number, calories: number, source: string | null, ingredients: Array<string> | null, instructions: Array<string> | null, title: string, id: number | null, date_added: string | null, deleted_at: string | null, version: number | null, updated_at: string | null, canonical_url: string | null, image_thumbnail: string | null, image_medium: string | null, }
\ No newline at end of file
@@ -0,0 +1,3 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+export type RecipeField = "title" | "url" | "image" | "source" | "servings" | "time" | "calories" | "ingredients" | "instructions" | "tags" | "date_added";
\ No newline at end of file
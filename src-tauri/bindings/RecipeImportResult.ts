@@ -0,0 +1,4 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { ImportStatus } from "./ImportStatus";
+
+export interface RecipeImportResult { title: string, status: ImportStatus, recipe_id: number | null, messages: Array<string>, }
\ No newline at end of file
@@ -0,0 +1,3 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+export interface RecipeLabels { dietLabels: Array<string>, healthLabels: Array<string>, cautions: Array<string>, cuisineType: Array<string>, mealType: Array<string>, dishType: Array<string>, }
\ No newline at end of file
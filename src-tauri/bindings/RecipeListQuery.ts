@@ -0,0 +1,5 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { RecipeSort } from "./RecipeSort";
+import type { SortDirection } from "./SortDirection";
+
+export interface RecipeListQuery { sort: RecipeSort, direction: SortDirection, cursor: string | null, limit: number | null, tag_ids: Array<number>, match_all_tags: boolean, }
\ No newline at end of file
@@ -0,0 +1,4 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { RecipeSummary } from "./RecipeSummary";
+
+export interface RecipePage { recipes: Array<RecipeSummary>, next_cursor: string | null, }
\ No newline at end of file
@@ -0,0 +1,4 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { RecipeDetails } from "./RecipeDetails";
+
+export interface RecipeRevision { id: number, recipe_id: number, recipe: RecipeDetails, created_at: string | null, }
\ No newline at end of file
@@ -0,0 +1,3 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+export interface RecipeSummary { id: number, title: string, image: string, image_thumbnail: string | null, source: string | null, servings: number, time: number, calories: number, date_added: string | null, last_cooked: string | null, times_cooked: number, average_rating: number | null, }
\ No newline at end of file
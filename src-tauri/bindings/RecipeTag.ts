@@ -0,0 +1,5 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { TagCategory } from "./TagCategory";
+import type { TagOrigin } from "./TagOrigin";
+
+export interface RecipeTag { id: number, name: string, category: TagCategory | null, origin: TagOrigin, }
\ No newline at end of file
@@ -0,0 +1,3 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+export interface RestorePoint { path: string, created_at: string, recipe_count: number, app_version: string, size: bigint, }
\ No newline at end of file
@@ -0,0 +1,3 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+export interface Settings { trash_retention_days: number | null, backup_dir: string | null, backup_interval_hours: number | null, backup_generations: number | null, }
\ No newline at end of file
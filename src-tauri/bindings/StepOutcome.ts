@@ -0,0 +1,5 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { StepStatus } from "./StepStatus";
+import type { UrlImportStep } from "./UrlImportStep";
+
+export interface StepOutcome { step: UrlImportStep, status: StepStatus, messages: Array<string>, retryable: boolean, }
\ No newline at end of file
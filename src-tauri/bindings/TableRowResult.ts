@@ -0,0 +1,5 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { ImportStatus } from "./ImportStatus";
+import type { RecipeDetails } from "./RecipeDetails";
+
+export interface TableRowResult { row: number, recipe: RecipeDetails, tags: Array<string>, status: ImportStatus, recipe_id: number | null, errors: Array<string>, }
\ No newline at end of file
@@ -0,0 +1,4 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { TagCategory } from "./TagCategory";
+
+export interface Tag { id: number, name: string, category: TagCategory | null, }
\ No newline at end of file
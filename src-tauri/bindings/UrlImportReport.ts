@@ -0,0 +1,6 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { ImportStatus } from "./ImportStatus";
+import type { RecipeDetails } from "./RecipeDetails";
+import type { StepOutcome } from "./StepOutcome";
+
+export interface UrlImportReport { status: ImportStatus, recipe_id: number | null, recipe: RecipeDetails | null, steps: Array<StepOutcome>, }
\ No newline at end of file
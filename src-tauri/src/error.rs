@@ -1,6 +1,9 @@
+use serde::ser::SerializeStruct;
 use std::io;
 use url::ParseError;
 
+use crate::types::recipe::RecipeDetails;
+
 #[derive(Debug, thiserror::Error)]
 pub enum RequestError {
     #[error("Failed to make a request: {0}")]
@@ -44,6 +47,22 @@ pub enum CheckScrapableError {
     NoDomain,
 }
 
+#[derive(Debug, thiserror::Error)]
+pub enum ImageDownloadError {
+    #[error("Failed to request the image: {0}")]
+    Request(#[from] RequestError),
+    #[error("Image request responded with {0}")]
+    Status(reqwest::StatusCode),
+    #[error("Response is {0}, not an image")]
+    ContentType(String),
+    #[error("Data isn't a JPEG, PNG, GIF, WebP or AVIF image")]
+    Unrecognized,
+    #[error("Image is larger than {} MB", .0 / 1_000_000)]
+    TooLarge(usize),
+    #[error("Image is corrupt or truncated: {0}")]
+    Decode(#[from] image::ImageError),
+}
+
 #[derive(Debug, thiserror::Error)]
 pub enum GetHitBodiesError {
     #[error("Middleware error: {0}")]
@@ -84,17 +103,49 @@ pub enum DBError {
     ConnectionPool,
     #[error("Record not found")]
     NoRecord,
+    #[error("Validation failed: {0}")]
+    Validation(String),
+    #[error("Missing version field in RecipeDetails, needed to detect conflicting updates")]
+    MissingVersion,
+    #[error("Recipe was changed by another update, now at version {}", .0.version.unwrap_or_default())]
+    Conflict(Box<RecipeDetails>),
+    #[error("Recipe is already saved with id {0}")]
+    Duplicate(i32),
     #[error("Error making a request: {0}")]
     Request(#[from] RequestError),
     #[error("IO Error: {0}")]
     IO(#[from] std::io::Error),
+    #[error("Failed to read or write the archive: {0}")]
+    Archive(#[from] zip::result::ZipError),
+    #[error("Archive is corrupt or unsupported: {0}")]
+    CorruptArchive(String),
+    #[error("Failed to read the CSV file: {0}")]
+    Csv(#[from] csv::Error),
+    #[error("Failed to process the image: {0}")]
+    Image(#[from] image::ImageError),
 }
 
+/// Errors reach the frontend as their message, except for errors it can act on, which are
+/// serialized as an object with the `message` and the data needed to recover.
 impl serde::Serialize for DBError {
     fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
     where
         S: serde::ser::Serializer,
     {
-        serializer.serialize_str(self.to_string().as_ref())
+        match self {
+            DBError::Conflict(current) => {
+                let mut state = serializer.serialize_struct("DBError", 2)?;
+                state.serialize_field("message", &self.to_string())?;
+                state.serialize_field("current", current)?;
+                state.end()
+            }
+            DBError::Duplicate(existing_id) => {
+                let mut state = serializer.serialize_struct("DBError", 2)?;
+                state.serialize_field("message", &self.to_string())?;
+                state.serialize_field("existing_id", existing_id)?;
+                state.end()
+            }
+            _ => serializer.serialize_str(self.to_string().as_ref()),
+        }
     }
 }
@@ -2,30 +2,71 @@
 #![cfg_attr(not(debug_assertions), windows_subsystem = "windows")]
 mod types {
     pub mod client;
+    pub mod collection;
+    pub mod cook_log;
     pub mod db;
+    pub mod duplicate;
+    pub mod image;
+    pub mod import;
+    pub mod import_job;
+    pub mod library;
     pub mod recipe;
     pub mod response;
+    pub mod revision;
+    pub mod settings;
+    pub mod tag;
 }
 mod utils {
+    pub mod backup;
+    pub mod bookmarks;
+    pub mod canonical_url;
+    pub mod cooklang;
     pub mod db;
+    pub mod export;
     pub mod image;
+    pub mod image_protocol;
+    pub mod import;
+    pub mod import_queue;
     pub mod json;
+    pub mod library;
+    pub mod mealmaster;
+    pub mod paprika;
+    pub mod parse;
     pub mod recipe_scrapers;
+    pub mod recipe_text;
     pub mod request;
+    pub mod table_import;
+    pub mod url_import;
 }
 mod config;
 mod error;
 
 use error::{DBError, RequestError};
+use std::path::{Path, PathBuf};
 use tauri::AppHandle;
 use tauri::Manager;
 use tauri::State;
 use types::client::ReqwestClient;
+use types::collection::Collection;
+use types::cook_log::{CookLogEntry, CookStats};
 use types::db::Database;
+use types::duplicate::DuplicateCandidate;
+use types::image::ImageGcReport;
+use types::import::{
+    BookmarkReport, FieldMapping, RecipeDraft, RecipeImportResult, TableImportReport,
+    UrlImportReport,
+};
+use types::import_job::ImportJob;
+use types::library::{LibraryImportReport, LibraryManifest, RestoreMode, RestorePoint};
 use types::recipe::RecipeData;
-use types::recipe::RecipeDetails;
-use types::response::EdamamResponse;
-use utils::image::download_image;
+use types::recipe::{DuplicatePolicy, RecipeDetails, RecipeListQuery, RecipePage};
+use types::response::{EdamamResponse, Images};
+use types::revision::{FieldChange, RecipeRevision};
+use types::settings::Settings;
+use types::tag::{RecipeLabels, RecipeTag, Tag, TagCategory};
+use utils::export::{recipe_html, recipe_json_ld, write_recipe_html};
+use utils::image::{delete_image, download_image, image_data_uri, import_local_image};
+use utils::image_protocol::{handle_image_request, IMAGE_PROTOCOL};
 use utils::recipe_scrapers::scrape_recipe_from_url;
 use utils::request::build_request;
 
@@ -35,11 +76,16 @@ fn main() {
     tauri::Builder::default()
         .setup(|app| {
             let app_handle = app.handle();
-            let db = Database::new(app_handle).unwrap();
+            let db = Database::new(app_handle.clone()).unwrap();
+            purge_expired_trash(&db, app_handle.clone());
             app.manage(db);
+            spawn_image_index(app_handle.clone());
+            utils::import_queue::spawn_import_queue(app_handle.clone());
+            utils::backup::spawn_backup_schedule(app_handle);
             Ok(())
         })
         .manage(client)
+        .register_uri_scheme_protocol(IMAGE_PROTOCOL, handle_image_request)
         // .manage(db)
         .invoke_handler(tauri::generate_handler![
             greet,
@@ -47,11 +93,71 @@ fn main() {
             get_next_recipes,
             get_recipe_details,
             get_all_recipes,
+            list_recipes,
             get_recipe_by_id,
             add_recipe,
+            create_recipe,
+            import_recipe_from_url,
+            read_bookmarks,
+            enqueue_url_imports,
+            get_import_jobs,
+            retry_import_job,
+            cancel_import_job,
+            clear_finished_import_jobs,
             update_recipe,
+            export_recipes_json_ld,
+            export_recipes_html,
+            backfill_image_variants,
+            gc_images,
+            import_paprika,
+            export_paprika,
+            import_cooklang,
+            export_cooklang,
+            read_mealmaster,
+            parse_recipe_text,
+            get_table_columns,
+            import_table,
             delete_recipe_by_id,
-            does_recipe_exist_by_url
+            get_trashed_recipes,
+            restore_recipe_by_id,
+            purge_recipe_by_id,
+            empty_trash,
+            get_settings,
+            update_settings,
+            export_library,
+            import_library,
+            list_restore_points,
+            does_recipe_exist_by_url,
+            find_similar_recipes,
+            merge_recipes,
+            get_all_tags,
+            create_tag,
+            rename_tag,
+            delete_tag,
+            get_tags_by_category,
+            get_tags_for_recipe,
+            add_tag_to_recipe,
+            remove_tag_from_recipe,
+            get_recipes_by_tags,
+            get_recipes_by_labels,
+            get_all_collections,
+            create_collection,
+            update_collection,
+            delete_collection,
+            reorder_collections,
+            get_collection_recipes,
+            add_recipe_to_collection,
+            remove_recipe_from_collection,
+            reorder_collection_recipes,
+            add_cook_log_entry,
+            update_cook_log_entry,
+            delete_cook_log_entry,
+            get_cook_log,
+            get_cook_stats,
+            get_all_cook_stats,
+            get_recipe_revisions,
+            diff_recipe_revisions,
+            restore_recipe_revision
         ])
         .run(tauri::generate_context!())
         .expect("error while running tauri application");
@@ -105,7 +211,7 @@ async fn get_next_recipes(
 
 #[tauri::command]
 async fn get_recipe_details(url: &str) -> Result<RecipeData, RequestError> {
-    let details = scrape_recipe_from_url(url)?;
+    let details = scrape_recipe_from_url(url, false)?;
     let json: RecipeData = serde_json::from_str(&details)?;
     Ok(json)
 }
@@ -115,6 +221,14 @@ async fn get_all_recipes(db: State<'_, Database>) -> Result<Vec<RecipeDetails>,
     db.get_all_recipes()
 }
 
+#[tauri::command]
+async fn list_recipes(
+    db: State<'_, Database>,
+    query: RecipeListQuery,
+) -> Result<RecipePage, DBError> {
+    db.list_recipes(&query)
+}
+
 #[tauri::command]
 async fn get_recipe_by_id(
     db: State<'_, Database>,
@@ -123,15 +237,129 @@ async fn get_recipe_by_id(
     db.get_recipe_by_id(&id)
 }
 
+/// Saves a recipe and returns its id. A recipe whose canonical URL is already saved is
+/// rejected with `DBError::Duplicate` or merged into the saved one, depending on
+/// `on_duplicate`. The canonical URL is the one scraped along with the recipe, the recipe's
+/// own URL standing in when the page has none. `images` are the Edamam variants of the
+/// recipe's image, downloaded instead when its own image can't be.
 #[tauri::command]
 async fn add_recipe(
     client: State<'_, ReqwestClient>,
     db: State<'_, Database>,
     recipe: RecipeDetails,
+    labels: Option<RecipeLabels>,
+    on_duplicate: Option<DuplicatePolicy>,
+    images: Option<Images>,
     app: AppHandle,
-) -> Result<(), DBError> {
-    let uploaded_image = download_image(client, &recipe.image, app).await?;
-    db.add_recipe(&recipe, uploaded_image)
+) -> Result<i32, DBError> {
+    if let Some(existing_id) = db.find_duplicate_recipe(&recipe)? {
+        return match on_duplicate.unwrap_or_default() {
+            DuplicatePolicy::Reject => Err(DBError::Duplicate(existing_id)),
+            DuplicatePolicy::Merge => db.merge_recipe(&existing_id, &recipe, labels.as_ref()),
+        };
+    }
+
+    let fallbacks = images
+        .map(|images| images.fallback_urls())
+        .unwrap_or_default();
+    let uploaded_image =
+        download_image(client, &db, &recipe.image, &fallbacks, app.clone()).await?;
+    db.add_recipe(&recipe, uploaded_image.clone(), labels.as_ref())
+        .map_err(|error| {
+            // The same recipe may have been saved while the image was downloading
+            if let Some(image) = &uploaded_image {
+                let _ = delete_image(&db, image, app);
+            }
+            error
+        })
+}
+
+/// Saves a recipe written from scratch, which may have no page URL, and returns its id.
+/// `image_path` is a local image file to copy into the library as the recipe's image.
+#[tauri::command]
+async fn create_recipe(
+    db: State<'_, Database>,
+    mut recipe: RecipeDetails,
+    image_path: Option<String>,
+    labels: Option<RecipeLabels>,
+    app: AppHandle,
+) -> Result<i32, DBError> {
+    recipe.title = recipe.title.trim().to_string();
+    if recipe.title.is_empty() {
+        return Err(DBError::Validation("title cannot be empty".to_owned()));
+    }
+    recipe.url = recipe
+        .url
+        .map(|url| url.trim().to_string())
+        .filter(|url| !url.is_empty());
+
+    let copied_image = match &image_path {
+        Some(image_path) => Some(import_local_image(&db, Path::new(image_path), app.clone())?),
+        None => None,
+    };
+    db.add_recipe(&recipe, copied_image.clone(), labels.as_ref())
+        .map_err(|error| {
+            if let Some(image) = &copied_image {
+                let _ = delete_image(&db, image, app);
+            }
+            error
+        })
+}
+
+/// Saves the recipe on the page at `url`, reporting how each step of the import went.
+#[tauri::command]
+async fn import_recipe_from_url(
+    client: State<'_, ReqwestClient>,
+    db: State<'_, Database>,
+    url: String,
+    on_duplicate: Option<DuplicatePolicy>,
+    app: AppHandle,
+) -> Result<UrlImportReport, DBError> {
+    Ok(utils::url_import::import_recipe_from_url(
+        client,
+        &db,
+        &url,
+        on_duplicate.unwrap_or_default(),
+        app,
+    )
+    .await)
+}
+
+/// Lists the likely recipe links of a browser's bookmarks export, to be picked from and queued
+/// with `enqueue_url_imports`.
+#[tauri::command]
+async fn read_bookmarks(db: State<'_, Database>, path: String) -> Result<BookmarkReport, DBError> {
+    utils::bookmarks::read_bookmarks(&db, Path::new(&path))
+}
+
+/// Queues recipe URLs to be imported in the background. Progress is sent to the frontend as
+/// `import-job` events.
+#[tauri::command]
+async fn enqueue_url_imports(
+    db: State<'_, Database>,
+    urls: Vec<String>,
+) -> Result<Vec<ImportJob>, DBError> {
+    utils::import_queue::enqueue_urls(&db, &urls)
+}
+
+#[tauri::command]
+async fn get_import_jobs(db: State<'_, Database>) -> Result<Vec<ImportJob>, DBError> {
+    db.get_import_jobs()
+}
+
+#[tauri::command]
+async fn retry_import_job(db: State<'_, Database>, id: i32) -> Result<ImportJob, DBError> {
+    db.retry_import_job(&id)
+}
+
+#[tauri::command]
+async fn cancel_import_job(db: State<'_, Database>, id: i32) -> Result<ImportJob, DBError> {
+    db.cancel_import_job(&id)
+}
+
+#[tauri::command]
+async fn clear_finished_import_jobs(db: State<'_, Database>) -> Result<(), DBError> {
+    db.clear_finished_import_jobs()
 }
 
 #[tauri::command]
@@ -139,13 +367,460 @@ async fn update_recipe(db: State<'_, Database>, recipe: RecipeDetails) -> Result
     db.update_recipe(&recipe)
 }
 
+/// Recipes to export, all of those in the library if `ids` is `None`.
+fn recipes_to_export(db: &Database, ids: Option<Vec<i32>>) -> Result<Vec<RecipeDetails>, DBError> {
+    match ids {
+        Some(ids) => db.get_recipes_by_ids(&ids),
+        None => db.get_all_recipes(),
+    }
+}
+
+/// Describes recipes as schema.org JSON-LD, with downloaded images inlined.
+#[tauri::command]
+async fn export_recipes_json_ld(
+    db: State<'_, Database>,
+    ids: Option<Vec<i32>>,
+    app: AppHandle,
+) -> Result<Vec<serde_json::Value>, DBError> {
+    recipes_to_export(&db, ids)?
+        .iter()
+        .map(|recipe| {
+            let tags = db.get_tags_for_recipe(&recipe.id.unwrap_or_default())?;
+            let image = image_data_uri(&recipe.image, app.clone())?;
+            Ok(recipe_json_ld(recipe, &tags, image.as_deref()))
+        })
+        .collect()
+}
+
+/// Writes each recipe to `dir` as a self-contained, printable HTML page and returns the paths
+/// of the pages.
+#[tauri::command]
+async fn export_recipes_html(
+    db: State<'_, Database>,
+    ids: Option<Vec<i32>>,
+    dir: String,
+    app: AppHandle,
+) -> Result<Vec<String>, DBError> {
+    let dir = Path::new(&dir);
+    std::fs::create_dir_all(dir)?;
+
+    recipes_to_export(&db, ids)?
+        .iter()
+        .map(|recipe| {
+            let tags = db.get_tags_for_recipe(&recipe.id.unwrap_or_default())?;
+            let image = image_data_uri(&recipe.image, app.clone())?;
+            let html = recipe_html(recipe, &tags, image.as_deref());
+            write_recipe_html(dir, recipe, &html)
+        })
+        .collect()
+}
+
+/// Makes the thumbnail and medium variants of images saved before they were made on saving,
+/// returning the number of images given variants.
+#[tauri::command]
+async fn backfill_image_variants(db: State<'_, Database>, app: AppHandle) -> Result<u32, DBError> {
+    utils::image::backfill_image_variants(&db, app)
+}
+
+/// Removes images nothing uses any more and reports the space reclaimed, along with the
+/// recipes and collections pointing to images that are gone.
+#[tauri::command]
+async fn gc_images(db: State<'_, Database>, app: AppHandle) -> Result<ImageGcReport, DBError> {
+    utils::image::gc_images(&db, app)
+}
+
+#[tauri::command]
+async fn import_paprika(
+    db: State<'_, Database>,
+    path: String,
+    app: AppHandle,
+) -> Result<Vec<RecipeImportResult>, DBError> {
+    utils::paprika::import_paprika(&db, Path::new(&path), app)
+}
+
+#[tauri::command]
+async fn export_paprika(
+    db: State<'_, Database>,
+    ids: Option<Vec<i32>>,
+    path: String,
+    app: AppHandle,
+) -> Result<(), DBError> {
+    let recipes = recipes_to_export(&db, ids)?;
+    utils::paprika::export_paprika(&db, &recipes, Path::new(&path), app)
+}
+
+#[tauri::command]
+async fn import_cooklang(
+    db: State<'_, Database>,
+    paths: Vec<String>,
+    app: AppHandle,
+) -> Result<Vec<RecipeImportResult>, DBError> {
+    let paths: Vec<PathBuf> = paths.iter().map(PathBuf::from).collect();
+    utils::cooklang::import_cooklang(&db, &paths, app)
+}
+
+#[tauri::command]
+async fn export_cooklang(
+    db: State<'_, Database>,
+    ids: Option<Vec<i32>>,
+    dir: String,
+    app: AppHandle,
+) -> Result<Vec<String>, DBError> {
+    let recipes = recipes_to_export(&db, ids)?;
+    utils::cooklang::export_cooklang(&db, &recipes, Path::new(&dir), app)
+}
+
+#[tauri::command]
+async fn read_mealmaster(path: String) -> Result<Vec<RecipeDraft>, DBError> {
+    utils::mealmaster::read_mealmaster(Path::new(&path))
+}
+
+#[tauri::command]
+async fn parse_recipe_text(text: String) -> RecipeDraft {
+    utils::recipe_text::parse_recipe_text(&text)
+}
+
+#[tauri::command]
+async fn get_table_columns(path: String) -> Result<Vec<String>, DBError> {
+    utils::table_import::read_table_columns(Path::new(&path))
+}
+
+#[tauri::command]
+async fn import_table(
+    db: State<'_, Database>,
+    path: String,
+    mappings: Vec<FieldMapping>,
+    dry_run: bool,
+    skip_invalid: Option<bool>,
+) -> Result<TableImportReport, DBError> {
+    utils::table_import::import_table(
+        &db,
+        Path::new(&path),
+        &mappings,
+        dry_run,
+        skip_invalid.unwrap_or(false),
+    )
+}
+
 #[tauri::command]
 async fn delete_recipe_by_id(db: State<'_, Database>, id: i32) -> Result<(), DBError> {
     db.delete_recipe_by_id(&id)
 }
 
+#[tauri::command]
+async fn get_trashed_recipes(db: State<'_, Database>) -> Result<Vec<RecipeDetails>, DBError> {
+    db.get_trashed_recipes()
+}
+
+#[tauri::command]
+async fn restore_recipe_by_id(db: State<'_, Database>, id: i32) -> Result<(), DBError> {
+    db.restore_recipe_by_id(&id)
+}
+
+#[tauri::command]
+async fn purge_recipe_by_id(
+    db: State<'_, Database>,
+    id: i32,
+    app: AppHandle,
+) -> Result<(), DBError> {
+    for image in db.purge_recipe_by_id(&id)? {
+        delete_image(&db, &image, app.clone())?;
+    }
+    Ok(())
+}
+
+#[tauri::command]
+async fn empty_trash(db: State<'_, Database>, app: AppHandle) -> Result<(), DBError> {
+    for image in db.empty_trash()? {
+        delete_image(&db, &image, app.clone())?;
+    }
+    Ok(())
+}
+
+/// Purges recipes that outlived the trash retention setting. Runs on launch, failures are
+/// logged rather than keeping the app from starting.
+fn purge_expired_trash(db: &Database, app: AppHandle) {
+    let result = db.get_settings().and_then(|settings| {
+        let images = match settings.trash_retention_days {
+            Some(days) => db.purge_expired_trash(&days)?,
+            None => Vec::new(),
+        };
+        for image in images {
+            delete_image(db, &image, app.clone())?;
+        }
+        Ok(())
+    });
+
+    if let Err(error) = result {
+        println!("Error purging expired recipes from the trash: {error}");
+    }
+}
+
+/// Indexes the images saved before they were stored by content. Runs on launch in the
+/// background, as each of them is read to be hashed.
+fn spawn_image_index(app: AppHandle) {
+    std::thread::spawn(move || {
+        if let Err(error) = utils::image::index_images(&app.state::<Database>(), app.clone()) {
+            println!("Error indexing images: {error}");
+        }
+    });
+}
+
+#[tauri::command]
+async fn get_settings(db: State<'_, Database>) -> Result<Settings, DBError> {
+    db.get_settings()
+}
+
+#[tauri::command]
+async fn update_settings(db: State<'_, Database>, settings: Settings) -> Result<(), DBError> {
+    db.update_settings(&settings)
+}
+
+/// Backs up the whole library, images included, to a single archive at `path`.
+#[tauri::command]
+async fn export_library(
+    db: State<'_, Database>,
+    path: String,
+    app: AppHandle,
+) -> Result<LibraryManifest, DBError> {
+    utils::library::export_library(&db, Path::new(&path), app)
+}
+
+/// Restores an archive written by `export_library`, either replacing the library or merging
+/// the archived recipes into it.
+#[tauri::command]
+async fn import_library(
+    db: State<'_, Database>,
+    path: String,
+    mode: RestoreMode,
+    app: AppHandle,
+) -> Result<LibraryImportReport, DBError> {
+    utils::library::import_library(&db, Path::new(&path), mode, app)
+}
+
+#[tauri::command]
+async fn list_restore_points(
+    db: State<'_, Database>,
+    app: AppHandle,
+) -> Result<Vec<RestorePoint>, DBError> {
+    utils::backup::list_restore_points(&db, app)
+}
+
 #[tauri::command]
 async fn does_recipe_exist_by_url(db: State<'_, Database>, url: &str) -> Result<bool, DBError> {
     let status = db.does_recipe_exist_by_url(url)?;
     Ok(status)
 }
+
+/// Lists pairs of saved recipes that are likely the same dish. Pairs scoring below
+/// `min_score`, 0.6 by default, are left out.
+#[tauri::command]
+async fn find_similar_recipes(
+    db: State<'_, Database>,
+    min_score: Option<f64>,
+) -> Result<Vec<DuplicateCandidate>, DBError> {
+    db.find_similar_recipes(min_score.unwrap_or(0.6))
+}
+
+#[tauri::command]
+async fn merge_recipes(
+    db: State<'_, Database>,
+    keep_id: i32,
+    merge_id: i32,
+) -> Result<(), DBError> {
+    db.merge_recipes(&keep_id, &merge_id)
+}
+
+#[tauri::command]
+async fn get_all_tags(db: State<'_, Database>) -> Result<Vec<Tag>, DBError> {
+    db.get_all_tags()
+}
+
+#[tauri::command]
+async fn create_tag(db: State<'_, Database>, name: &str) -> Result<Tag, DBError> {
+    db.create_tag(name)
+}
+
+#[tauri::command]
+async fn rename_tag(db: State<'_, Database>, id: i32, name: &str) -> Result<(), DBError> {
+    db.rename_tag(&id, name)
+}
+
+#[tauri::command]
+async fn delete_tag(db: State<'_, Database>, id: i32) -> Result<(), DBError> {
+    db.delete_tag(&id)
+}
+
+#[tauri::command]
+async fn get_tags_by_category(
+    db: State<'_, Database>,
+    category: TagCategory,
+) -> Result<Vec<Tag>, DBError> {
+    db.get_tags_by_category(&category)
+}
+
+#[tauri::command]
+async fn get_tags_for_recipe(
+    db: State<'_, Database>,
+    recipe_id: i32,
+) -> Result<Vec<RecipeTag>, DBError> {
+    db.get_tags_for_recipe(&recipe_id)
+}
+
+#[tauri::command]
+async fn add_tag_to_recipe(
+    db: State<'_, Database>,
+    recipe_id: i32,
+    name: &str,
+) -> Result<Tag, DBError> {
+    db.add_tag_to_recipe(&recipe_id, name)
+}
+
+#[tauri::command]
+async fn remove_tag_from_recipe(
+    db: State<'_, Database>,
+    recipe_id: i32,
+    tag_id: i32,
+) -> Result<(), DBError> {
+    db.remove_tag_from_recipe(&recipe_id, &tag_id)
+}
+
+#[tauri::command]
+async fn get_recipes_by_tags(
+    db: State<'_, Database>,
+    tag_ids: Vec<i32>,
+    match_all: bool,
+) -> Result<Vec<RecipeDetails>, DBError> {
+    db.get_recipes_by_tags(&tag_ids, match_all)
+}
+
+#[tauri::command]
+async fn get_recipes_by_labels(
+    db: State<'_, Database>,
+    cuisine_types: Vec<String>,
+    meal_types: Vec<String>,
+) -> Result<Vec<RecipeDetails>, DBError> {
+    db.get_recipes_by_labels(&cuisine_types, &meal_types)
+}
+
+#[tauri::command]
+async fn get_all_collections(db: State<'_, Database>) -> Result<Vec<Collection>, DBError> {
+    db.get_all_collections()
+}
+
+#[tauri::command]
+async fn create_collection(
+    db: State<'_, Database>,
+    name: &str,
+    cover_image: Option<String>,
+) -> Result<Collection, DBError> {
+    db.create_collection(name, cover_image)
+}
+
+#[tauri::command]
+async fn update_collection(db: State<'_, Database>, collection: Collection) -> Result<(), DBError> {
+    db.update_collection(&collection)
+}
+
+#[tauri::command]
+async fn delete_collection(db: State<'_, Database>, id: i32) -> Result<(), DBError> {
+    db.delete_collection(&id)
+}
+
+#[tauri::command]
+async fn reorder_collections(db: State<'_, Database>, ids: Vec<i32>) -> Result<(), DBError> {
+    db.reorder_collections(&ids)
+}
+
+#[tauri::command]
+async fn get_collection_recipes(
+    db: State<'_, Database>,
+    collection_id: i32,
+) -> Result<Vec<RecipeDetails>, DBError> {
+    db.get_collection_recipes(&collection_id)
+}
+
+#[tauri::command]
+async fn add_recipe_to_collection(
+    db: State<'_, Database>,
+    collection_id: i32,
+    recipe_id: i32,
+) -> Result<(), DBError> {
+    db.add_recipe_to_collection(&collection_id, &recipe_id)
+}
+
+#[tauri::command]
+async fn remove_recipe_from_collection(
+    db: State<'_, Database>,
+    collection_id: i32,
+    recipe_id: i32,
+) -> Result<(), DBError> {
+    db.remove_recipe_from_collection(&collection_id, &recipe_id)
+}
+
+#[tauri::command]
+async fn reorder_collection_recipes(
+    db: State<'_, Database>,
+    collection_id: i32,
+    recipe_ids: Vec<i32>,
+) -> Result<(), DBError> {
+    db.reorder_collection_recipes(&collection_id, &recipe_ids)
+}
+
+#[tauri::command]
+async fn add_cook_log_entry(db: State<'_, Database>, entry: CookLogEntry) -> Result<i32, DBError> {
+    db.add_cook_log_entry(&entry)
+}
+
+#[tauri::command]
+async fn update_cook_log_entry(
+    db: State<'_, Database>,
+    entry: CookLogEntry,
+) -> Result<(), DBError> {
+    db.update_cook_log_entry(&entry)
+}
+
+#[tauri::command]
+async fn delete_cook_log_entry(db: State<'_, Database>, id: i32) -> Result<(), DBError> {
+    db.delete_cook_log_entry(&id)
+}
+
+#[tauri::command]
+async fn get_cook_log(
+    db: State<'_, Database>,
+    recipe_id: i32,
+) -> Result<Vec<CookLogEntry>, DBError> {
+    db.get_cook_log(&recipe_id)
+}
+
+#[tauri::command]
+async fn get_cook_stats(db: State<'_, Database>, recipe_id: i32) -> Result<CookStats, DBError> {
+    db.get_cook_stats(&recipe_id)
+}
+
+#[tauri::command]
+async fn get_all_cook_stats(db: State<'_, Database>) -> Result<Vec<CookStats>, DBError> {
+    db.get_all_cook_stats()
+}
+
+#[tauri::command]
+async fn get_recipe_revisions(
+    db: State<'_, Database>,
+    recipe_id: i32,
+) -> Result<Vec<RecipeRevision>, DBError> {
+    db.get_recipe_revisions(&recipe_id)
+}
+
+#[tauri::command]
+async fn diff_recipe_revisions(
+    db: State<'_, Database>,
+    from_id: i32,
+    to_id: i32,
+) -> Result<Vec<FieldChange>, DBError> {
+    db.diff_recipe_revisions(&from_id, &to_id)
+}
+
+#[tauri::command]
+async fn restore_recipe_revision(db: State<'_, Database>, id: i32) -> Result<(), DBError> {
+    db.restore_recipe_revision(&id)
+}
@@ -0,0 +1,13 @@
+use serde::{Deserialize, Serialize};
+use ts_rs::TS;
+
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize, TS)]
+#[ts(export)]
+pub struct Collection {
+    pub id: i32,
+    pub name: String,
+    pub cover_image: Option<String>,
+    pub position: i32,
+    pub recipe_count: i32,
+    pub date_added: Option<String>,
+}
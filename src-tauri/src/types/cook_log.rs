@@ -0,0 +1,28 @@
+use serde::{Deserialize, Serialize};
+use ts_rs::TS;
+
+/// A single time a saved recipe was cooked.
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize, TS)]
+#[ts(export)]
+pub struct CookLogEntry {
+    pub id: Option<i32>,
+    pub recipe_id: i32,
+    /// `YYYY-MM-DD`, defaults to today when saving an entry without one
+    pub cooked_on: Option<String>,
+    /// 1 to 5
+    pub rating: Option<i32>,
+    pub cook: Option<String>,
+    pub servings_made: Option<i32>,
+    pub notes: Option<String>,
+    pub date_added: Option<String>,
+}
+
+/// Aggregates over the cook log of a recipe.
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize, TS)]
+#[ts(export)]
+pub struct CookStats {
+    pub recipe_id: i32,
+    pub times_cooked: i32,
+    pub last_cooked: Option<String>,
+    pub average_rating: Option<f64>,
+}
@@ -1,37 +1,129 @@
 use r2d2::Pool;
 use r2d2_sqlite::SqliteConnectionManager;
-use rusqlite::params;
+use rusqlite::{
+    backup::Progress, params, params_from_iter, types::Value, Connection, DatabaseName, OpenFlags,
+    OptionalExtension, Params, Row, ToSql,
+};
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::{BTreeMap, HashMap, HashSet},
+    path::Path,
+    time::Duration,
+};
 use tauri::AppHandle;
 
 use crate::{
     config::get_or_create_db_path,
     error::DBError,
-    types::recipe::RecipeDetails,
-    utils::db::{string_to_vec, vec_to_string},
+    types::{
+        collection::Collection,
+        cook_log::{CookLogEntry, CookStats},
+        duplicate::{fill_missing_fields, find_duplicate_candidates, DuplicateCandidate},
+        image::{ImageReference, StoredImage},
+        import::ImportStatus,
+        import_job::{ImportJob, ImportJobStatus},
+        library::LibraryImportReport,
+        recipe::{
+            RecipeDetails, RecipeListQuery, RecipePage, RecipeSort, RecipeSummary, SortDirection,
+        },
+        revision::{diff_recipes, FieldChange, RecipeRevision},
+        settings::Settings,
+        tag::{RecipeLabels, RecipeTag, Tag, TagCategory, TagOrigin},
+    },
+    utils::{
+        canonical_url::canonicalize_url,
+        db::{string_to_vec, vec_to_string},
+        image::{existing_variant, ImageVariant},
+    },
 };
 
 pub struct Database {
     pool: Pool<SqliteConnectionManager>,
 }
 
-impl Database {
-    pub fn new(app: AppHandle) -> Result<Self, DBError> {
-        let db_path = get_or_create_db_path(app)?;
-        let manager = SqliteConnectionManager::file(db_path);
-        let pool = Pool::new(manager).map_err(|_| DBError::ConnectionPool)?;
-        let db = Self { pool };
-        db.init_table()?;
-        Ok(db)
-    }
+/// Schema changes applied on top of the tables created in `init_table`, in order. The number
+/// of migrations already applied is tracked in SQLite's `user_version` pragma, so new
+/// migrations must only ever be appended.
+const MIGRATIONS: &[&str] = &[
+    // Record the Edamam label group of a tag and who applied a tag to a recipe
+    "
+ALTER TABLE Tag ADD COLUMN category TEXT;
 
-    fn init_table(&self) -> Result<(), DBError> {
-        let conn = self.pool.get().map_err(|_| DBError::ConnectionPool)?;
-        conn.execute(
-            "
-CREATE TABLE IF NOT EXISTS RecipeDetails (
+CREATE TABLE RecipeTag_new (
+  recipe_id     INTEGER NOT NULL REFERENCES RecipeDetails(id) ON DELETE CASCADE,
+  tag_id        INTEGER NOT NULL REFERENCES Tag(id) ON DELETE CASCADE,
+  origin        TEXT NOT NULL DEFAULT 'user',
+  PRIMARY KEY (recipe_id, tag_id, origin)
+);
+INSERT INTO RecipeTag_new (recipe_id, tag_id) SELECT recipe_id, tag_id FROM RecipeTag;
+DROP TABLE RecipeTag;
+ALTER TABLE RecipeTag_new RENAME TO RecipeTag;
+    ",
+    // Cook log
+    "
+CREATE TABLE CookLog (
+  id            INTEGER PRIMARY KEY AUTOINCREMENT,
+  recipe_id     INTEGER NOT NULL REFERENCES RecipeDetails(id) ON DELETE CASCADE,
+  cooked_on     DATE NOT NULL,
+  rating        INTEGER CHECK (rating BETWEEN 1 AND 5),
+  cook          TEXT,
+  servings_made INTEGER CHECK (servings_made > 0),
+  notes         TEXT,
+  date_added    DATETIME DEFAULT CURRENT_TIMESTAMP
+);
+CREATE INDEX CookLog_recipe_id ON CookLog (recipe_id, cooked_on);
+    ",
+    // Trash and app settings
+    "
+ALTER TABLE RecipeDetails ADD COLUMN deleted_at DATETIME;
+
+CREATE TABLE Setting (
+  key           TEXT PRIMARY KEY,
+  value         TEXT NOT NULL
+);
+    ",
+    // Recipe revisions
+    "
+CREATE TABLE RecipeRevision (
+  id            INTEGER PRIMARY KEY AUTOINCREMENT,
+  recipe_id     INTEGER NOT NULL REFERENCES RecipeDetails(id) ON DELETE CASCADE,
+  snapshot      TEXT NOT NULL,
+  created_at    DATETIME DEFAULT CURRENT_TIMESTAMP
+);
+CREATE INDEX RecipeRevision_recipe_id ON RecipeRevision (recipe_id);
+    ",
+    // Optimistic concurrency control on updates
+    "
+ALTER TABLE RecipeDetails ADD COLUMN version INTEGER NOT NULL DEFAULT 1;
+ALTER TABLE RecipeDetails ADD COLUMN updated_at DATETIME;
+    ",
+    // Normalized URL for detecting duplicates, filled in by `backfill_canonical_urls`
+    "
+ALTER TABLE RecipeDetails ADD COLUMN canonical_url TEXT;
+CREATE INDEX RecipeDetails_canonical_url ON RecipeDetails (canonical_url);
+    ",
+    // Queue of recipe URLs to import in the background
+    "
+CREATE TABLE ImportJob (
   id            INTEGER PRIMARY KEY AUTOINCREMENT,
-  image         TEXT NOT NULL,
   url           TEXT NOT NULL,
+  status        TEXT NOT NULL DEFAULT 'queued',
+  attempts      INTEGER NOT NULL DEFAULT 0,
+  recipe_id     INTEGER REFERENCES RecipeDetails(id) ON DELETE SET NULL,
+  error         TEXT,
+  run_after     DATETIME,
+  created_at    DATETIME DEFAULT CURRENT_TIMESTAMP,
+  updated_at    DATETIME DEFAULT CURRENT_TIMESTAMP
+);
+CREATE INDEX ImportJob_status ON ImportJob (status, run_after);
+    ",
+    // Recipes without a page URL, such as ones written from scratch. Imports used to save
+    // them with an empty URL.
+    "
+CREATE TABLE RecipeDetails_new (
+  id            INTEGER PRIMARY KEY AUTOINCREMENT,
+  image         TEXT NOT NULL,
+  url           TEXT,
   servings      INTEGER NOT NULL,
   time          INTEGER NOT NULL,
   calories      INTEGER NOT NULL,
@@ -39,95 +131,521 @@ CREATE TABLE IF NOT EXISTS RecipeDetails (
   title         TEXT NOT NULL,
   ingredients   TEXT,
   instructions  TEXT,
-  date_added    DATETIME DEFAULT CURRENT_TIMESTAMP
+  date_added    DATETIME DEFAULT CURRENT_TIMESTAMP,
+  deleted_at    DATETIME,
+  version       INTEGER NOT NULL DEFAULT 1,
+  updated_at    DATETIME,
+  canonical_url TEXT
 );
-        ",
-            (),
+INSERT INTO RecipeDetails_new
+  SELECT id, image, NULLIF(TRIM(url), ''), servings, time, calories, source, title, ingredients,
+    instructions, date_added, deleted_at, version, updated_at, canonical_url
+  FROM RecipeDetails;
+DROP TABLE RecipeDetails;
+ALTER TABLE RecipeDetails_new RENAME TO RecipeDetails;
+CREATE INDEX RecipeDetails_canonical_url ON RecipeDetails (canonical_url);
+    ",
+    // Scaled down copies of saved images, filled in for older images by
+    // `backfill_image_variants`
+    "
+ALTER TABLE RecipeDetails ADD COLUMN image_thumbnail TEXT;
+ALTER TABLE RecipeDetails ADD COLUMN image_medium TEXT;
+    ",
+    // Images stored by the SHA-256 of their content, with the number of recipes and collection
+    // covers using each. Images saved before are indexed by `index_images`.
+    "
+CREATE TABLE Image (
+  hash          TEXT PRIMARY KEY,
+  path          TEXT NOT NULL UNIQUE,
+  size          INTEGER NOT NULL,
+  ref_count     INTEGER NOT NULL DEFAULT 0,
+  created_at    DATETIME DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE TRIGGER RecipeDetails_image_insert AFTER INSERT ON RecipeDetails BEGIN
+  UPDATE Image SET ref_count = ref_count + 1 WHERE path = NEW.image;
+END;
+CREATE TRIGGER RecipeDetails_image_update AFTER UPDATE OF image ON RecipeDetails
+WHEN OLD.image IS NOT NEW.image BEGIN
+  UPDATE Image SET ref_count = ref_count - 1 WHERE path = OLD.image;
+  UPDATE Image SET ref_count = ref_count + 1 WHERE path = NEW.image;
+END;
+CREATE TRIGGER RecipeDetails_image_delete AFTER DELETE ON RecipeDetails BEGIN
+  UPDATE Image SET ref_count = ref_count - 1 WHERE path = OLD.image;
+END;
+
+CREATE TRIGGER Collection_cover_image_insert AFTER INSERT ON Collection BEGIN
+  UPDATE Image SET ref_count = ref_count + 1 WHERE path = NEW.cover_image;
+END;
+CREATE TRIGGER Collection_cover_image_update AFTER UPDATE OF cover_image ON Collection
+WHEN OLD.cover_image IS NOT NEW.cover_image BEGIN
+  UPDATE Image SET ref_count = ref_count - 1 WHERE path = OLD.cover_image;
+  UPDATE Image SET ref_count = ref_count + 1 WHERE path = NEW.cover_image;
+END;
+CREATE TRIGGER Collection_cover_image_delete AFTER DELETE ON Collection BEGIN
+  UPDATE Image SET ref_count = ref_count - 1 WHERE path = OLD.cover_image;
+END;
+    ",
+];
+
+/// The `user_version` of a database all migrations have been applied to
+pub const SCHEMA_VERSION: usize = MIGRATIONS.len();
+
+/// Tauri commands run concurrently, so a few connections are kept open. WAL lets them read
+/// while another one writes.
+const POOL_SIZE: u32 = 8;
+/// How long a connection waits on a lock held by another connection before giving up with
+/// `SQLITE_BUSY`
+const BUSY_TIMEOUT: Duration = Duration::from_secs(5);
+const STATEMENT_CACHE_CAPACITY: usize = 64;
+
+/// Settings describing the backups taken on this device, which a restored library doesn't
+/// bring along
+const DEVICE_SETTINGS: [&str; 4] = [
+    "backup_dir",
+    "backup_interval_hours",
+    "backup_generations",
+    "last_backup_at",
+];
+
+/// Sets up every connection of the pool. In-memory databases keep their own journal mode.
+fn configure_connection(conn: &mut Connection) -> rusqlite::Result<()> {
+    conn.pragma_update_and_check(None, "journal_mode", "WAL", |row| row.get::<_, String>(0))?;
+    conn.pragma_update(None, "synchronous", "NORMAL")?;
+    conn.pragma_update(None, "foreign_keys", true)?;
+    conn.busy_timeout(BUSY_TIMEOUT)?;
+    conn.set_prepared_statement_cache_capacity(STATEMENT_CACHE_CAPACITY);
+    Ok(())
+}
+
+impl Database {
+    pub fn new(app: AppHandle) -> Result<Self, DBError> {
+        let db_path = get_or_create_db_path(app)?;
+        let manager = SqliteConnectionManager::file(db_path).with_init(configure_connection);
+        let pool = Pool::builder()
+            .max_size(POOL_SIZE)
+            .build(manager)
+            .map_err(|_| DBError::ConnectionPool)?;
+        let mut conn = pool.get().map_err(|_| DBError::ConnectionPool)?;
+        prepare_schema(&mut conn)?;
+        drop(conn);
+
+        Ok(Self { pool })
+    }
+
+    /// A library of its own held in memory, for tests.
+    #[cfg(test)]
+    pub fn in_memory() -> Result<Self, DBError> {
+        let manager = SqliteConnectionManager::memory().with_init(configure_connection);
+        // Every in-memory connection is a database of its own, so the pool only holds one
+        let pool = Pool::builder()
+            .max_size(1)
+            .build(manager)
+            .map_err(|_| DBError::ConnectionPool)?;
+        let mut conn = pool.get().map_err(|_| DBError::ConnectionPool)?;
+        prepare_schema(&mut conn)?;
+        drop(conn);
+
+        Ok(Self { pool })
+    }
+
+    /// Writes a consistent copy of the whole database to `path`, while other connections keep
+    /// reading and writing.
+    pub fn backup_to(&self, path: &Path) -> Result<(), DBError> {
+        let conn = self.pool.get().map_err(|_| DBError::ConnectionPool)?;
+        conn.backup(DatabaseName::Main, path, None)?;
+        Ok(())
+    }
+
+    /// Replaces the whole database with the one at `path`, except for the settings of this
+    /// device's backups, see `DEVICE_SETTINGS`.
+    pub fn restore_from(&self, path: &Path) -> Result<(), DBError> {
+        let mut conn = self.pool.get().map_err(|_| DBError::ConnectionPool)?;
+        let device_settings: Vec<(String, String)> = query_all(
+            &conn,
+            "SELECT key, value FROM Setting WHERE key IN (?1, ?2, ?3, ?4)",
+            params_from_iter(DEVICE_SETTINGS),
+            |row| Ok((row.get(0)?, row.get(1)?)),
         )?;
+
+        conn.restore(DatabaseName::Main, path, None::<fn(Progress)>)?;
+        prepare_schema(&mut conn)?;
+
+        let tx = conn.transaction()?;
+        tx.execute(
+            "DELETE FROM Setting WHERE key IN (?1, ?2, ?3, ?4)",
+            params_from_iter(DEVICE_SETTINGS),
+        )?;
+        for (key, value) in device_settings {
+            tx.execute(
+                "INSERT INTO Setting (key, value) VALUES (?1, ?2)",
+                params![key, value],
+            )?;
+        }
+        tx.commit()?;
         Ok(())
     }
 
+    /// Adds the live recipes of the database at `path` that aren't in the library yet, with
+    /// their revisions and cook log. Tags and collections are matched by name and applied to
+    /// both added recipes and ones that were already in the library.
+    pub fn merge_from(&self, path: &Path) -> Result<LibraryImportReport, DBError> {
+        let source = Connection::open_with_flags(path, OpenFlags::SQLITE_OPEN_READ_ONLY)?;
+        let mut conn = self.pool.get().map_err(|_| DBError::ConnectionPool)?;
+        let tx = conn.transaction()?;
+        let mut report = LibraryImportReport::default();
+
+        // Library ids of the archived recipes, keyed by their id in the archive
+        let mut recipe_ids = HashMap::new();
+        let mut added = HashSet::new();
+
+        let recipes = query_all(
+            &source,
+            "SELECT * FROM RecipeDetails WHERE deleted_at IS NULL ORDER BY id",
+            [],
+            recipe_from_row,
+        )?;
+        for recipe in recipes {
+            let source_id = recipe.id.unwrap_or_default();
+            let existing_id = match find_duplicate(&tx, &recipe, None)? {
+                Some(existing_id) => Some(existing_id),
+                None => find_same_recipe_without_url(&tx, &recipe)?,
+            };
+            if let Some(existing_id) = existing_id {
+                recipe_ids.insert(source_id, existing_id);
+                report.recipes_skipped += 1;
+                continue;
+            }
+
+            let id = insert_recipe(&tx, &recipe)?;
+            recipe_ids.insert(source_id, id);
+            added.insert(source_id);
+            report.recipes_added += 1;
+
+            let revisions = query_all(
+                &source,
+                "SELECT snapshot, created_at FROM RecipeRevision WHERE recipe_id = ?1 ORDER BY id",
+                params![source_id],
+                |row| Ok((row.get::<_, String>(0)?, row.get::<_, Option<String>>(1)?)),
+            )?;
+            if revisions.is_empty() {
+                record_revision(&tx, &id)?;
+            }
+            for (snapshot, created_at) in revisions {
+                tx.execute(
+                    "INSERT INTO RecipeRevision (recipe_id, snapshot, created_at) VALUES (?1, ?2, ?3)",
+                    params![id, snapshot, created_at],
+                )?;
+            }
+        }
+
+        let recipe_tags = query_all(
+            &source,
+            "SELECT RecipeTag.recipe_id, Tag.name, Tag.category, RecipeTag.origin
+             FROM RecipeTag JOIN Tag ON Tag.id = RecipeTag.tag_id",
+            [],
+            |row| {
+                Ok((
+                    row.get::<_, i32>(0)?,
+                    row.get::<_, String>(1)?,
+                    row.get::<_, Option<TagCategory>>(2)?,
+                    row.get::<_, TagOrigin>(3)?,
+                ))
+            },
+        )?;
+        for (source_id, name, category, origin) in recipe_tags {
+            if let Some(recipe_id) = recipe_ids.get(&source_id) {
+                let tag_id = upsert_tag(&tx, &name, category)?;
+                tx.execute(
+                    "INSERT OR IGNORE INTO RecipeTag (recipe_id, tag_id, origin) VALUES (?1, ?2, ?3)",
+                    params![recipe_id, tag_id, origin],
+                )?;
+            }
+        }
+
+        let collections = query_all(
+            &source,
+            "SELECT id, name, cover_image FROM Collection ORDER BY position",
+            [],
+            |row| {
+                Ok((
+                    row.get::<_, i32>(0)?,
+                    row.get::<_, String>(1)?,
+                    row.get::<_, Option<String>>(2)?,
+                ))
+            },
+        )?;
+        for (source_collection_id, name, cover_image) in collections {
+            let existing_id: Option<i32> = tx
+                .query_row(
+                    "SELECT id FROM Collection WHERE name = ?1 COLLATE NOCASE ORDER BY id LIMIT 1",
+                    params![name],
+                    |row| row.get(0),
+                )
+                .optional()?;
+            let collection_id = match existing_id {
+                Some(id) => id,
+                None => {
+                    tx.execute(
+                        "INSERT INTO Collection (name, cover_image, position)
+                         VALUES (?1, ?2, (SELECT COALESCE(MAX(position) + 1, 0) FROM Collection))",
+                        params![name, cover_image],
+                    )?;
+                    tx.last_insert_rowid() as i32
+                }
+            };
+
+            let members = query_all(
+                &source,
+                "SELECT recipe_id FROM CollectionRecipe WHERE collection_id = ?1 ORDER BY position",
+                params![source_collection_id],
+                |row| row.get::<_, i32>(0),
+            )?;
+            for recipe_id in members.iter().filter_map(|id| recipe_ids.get(id)) {
+                tx.execute(
+                    "INSERT OR IGNORE INTO CollectionRecipe (collection_id, recipe_id, position)
+                     VALUES (?1, ?2, (SELECT COALESCE(MAX(position) + 1, 0) FROM CollectionRecipe WHERE collection_id = ?1))",
+                    params![collection_id, recipe_id],
+                )?;
+            }
+        }
+
+        // Recipes already in the library keep their own cook log, so importing the same
+        // archive twice doesn't log every meal twice
+        let cook_log = query_all(
+            &source,
+            "SELECT recipe_id, cooked_on, rating, cook, servings_made, notes, date_added FROM CookLog ORDER BY id",
+            [],
+            |row| {
+                Ok((
+                    row.get::<_, i32>(0)?,
+                    row.get::<_, String>(1)?,
+                    row.get::<_, Option<i32>>(2)?,
+                    row.get::<_, Option<String>>(3)?,
+                    row.get::<_, Option<i32>>(4)?,
+                    row.get::<_, Option<String>>(5)?,
+                    row.get::<_, Option<String>>(6)?,
+                ))
+            },
+        )?;
+        for (source_id, cooked_on, rating, cook, servings_made, notes, date_added) in cook_log {
+            if !added.contains(&source_id) {
+                continue;
+            }
+            tx.execute(
+                "INSERT INTO CookLog (recipe_id, cooked_on, rating, cook, servings_made, notes, date_added) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+                params![recipe_ids[&source_id], cooked_on, rating, cook, servings_made, notes, date_added],
+            )?;
+        }
+
+        tx.commit()?;
+        Ok(report)
+    }
+
+    /// Saves a recipe and returns its id. Any Edamam `labels` are stored as tags with an
+    /// `edamam` origin in the same transaction.
     pub fn add_recipe(
         &self,
         recipe: &RecipeDetails,
         uploaded_image: Option<String>,
-    ) -> Result<(), DBError> {
-        let ingredients_str = vec_to_string(&recipe.ingredients)?;
-        let instructions_str = vec_to_string(&recipe.instructions)?;
+        labels: Option<&RecipeLabels>,
+    ) -> Result<i32, DBError> {
+        let mut conn = self.pool.get().map_err(|_| DBError::ConnectionPool)?;
+        let tx = conn.transaction()?;
 
-        let conn = self.pool.get().map_err(|_| DBError::ConnectionPool)?;
+        if let Some(existing_id) = find_duplicate(&tx, recipe, None)? {
+            return Err(DBError::Duplicate(existing_id));
+        }
 
-        let recipe_image_to_upload = match &uploaded_image {
-            Some(image_path) => image_path.as_str(),
-            None => &recipe.image,
+        let recipe = RecipeDetails {
+            image: uploaded_image.unwrap_or_else(|| recipe.image.clone()),
+            date_added: None,
+            ..recipe.clone()
         };
+        let id = insert_recipe(&tx, &recipe)?;
+        record_revision(&tx, &id)?;
 
-        conn.execute(
-        "INSERT INTO RecipeDetails (image, url, servings, time, calories, source, title, ingredients, instructions) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)",
-        params![recipe_image_to_upload, &recipe.url, recipe.servings, recipe.time, recipe.calories, &recipe.source, &recipe.title, ingredients_str, instructions_str],
-    )?;
+        if let Some(labels) = labels {
+            add_label_tags(&tx, &id, labels)?;
+        }
 
-        Ok(())
+        tx.commit()?;
+        Ok(id)
     }
 
-    pub fn get_recipe_by_id(&self, id: &i32) -> Result<Option<RecipeDetails>, DBError> {
+    /// Saves a recipe brought over from another app or file along with its `tags`, keeping its
+    /// `date_added` if it has one. Fails with `DBError::Duplicate` if it is already saved.
+    pub fn import_recipe(&self, recipe: &RecipeDetails, tags: &[String]) -> Result<i32, DBError> {
+        let mut conn = self.pool.get().map_err(|_| DBError::ConnectionPool)?;
+        let tx = conn.transaction()?;
+        let id = import_into(&tx, recipe, tags)?;
+        tx.commit()?;
+        Ok(id)
+    }
+
+    /// Saves a batch of imported recipes, each with its tags, in a single transaction. Recipes
+    /// that are already saved, including earlier in the batch, are skipped. Returns for each
+    /// recipe its status and either its new id or the id of the saved copy.
+    pub fn import_recipes(
+        &self,
+        recipes: &[(RecipeDetails, Vec<String>)],
+    ) -> Result<Vec<(ImportStatus, i32)>, DBError> {
+        let mut conn = self.pool.get().map_err(|_| DBError::ConnectionPool)?;
+        let tx = conn.transaction()?;
+
+        let mut outcomes = Vec::with_capacity(recipes.len());
+        for (recipe, tags) in recipes {
+            outcomes.push(match import_into(&tx, recipe, tags) {
+                Ok(id) => (ImportStatus::Imported, id),
+                Err(DBError::Duplicate(existing_id)) => (ImportStatus::Duplicate, existing_id),
+                Err(error) => return Err(error),
+            });
+        }
+
+        tx.commit()?;
+        Ok(outcomes)
+    }
+
+    /// Returns the id of the saved recipe with the same canonical URL as `url`, if any.
+    pub fn find_recipe_by_url(&self, url: &str) -> Result<Option<i32>, DBError> {
         let conn = self.pool.get().map_err(|_| DBError::ConnectionPool)?;
+        let existing_id = conn
+            .query_row(
+                "SELECT id FROM RecipeDetails WHERE canonical_url = ?1 AND canonical_url != '' AND deleted_at IS NULL",
+                params![canonicalize_url(url)],
+                |row| row.get(0),
+            )
+            .optional()?;
+        Ok(existing_id)
+    }
 
-        let mut stmt = conn.prepare("SELECT * FROM RecipeDetails WHERE id = ?1")?;
-        let mut rows = stmt.query_map(params![id], |row| {
-            let ingredients: Option<String> = row.get(8)?;
-            let ingredients =
-                string_to_vec(&ingredients).map_err(|_| rusqlite::Error::InvalidQuery)?;
-            let instructions: Option<String> = row.get(9)?;
-            let instructions =
-                string_to_vec(&instructions).map_err(|_| rusqlite::Error::InvalidQuery)?;
-            Ok(RecipeDetails {
-                id: row.get(0)?,
-                image: row.get(1)?,
-                url: row.get(2)?,
-                servings: row.get(3)?,
-                time: row.get(4)?,
-                calories: row.get(5)?,
-                source: row.get(6)?,
-                title: row.get(7)?,
-                ingredients,
-                instructions,
-                date_added: row.get(10)?,
-            })
-        })?;
+    /// Returns the id of the saved recipe `recipe` duplicates, if any.
+    pub fn find_duplicate_recipe(&self, recipe: &RecipeDetails) -> Result<Option<i32>, DBError> {
+        let conn = self.pool.get().map_err(|_| DBError::ConnectionPool)?;
+        Ok(find_duplicate(&conn, recipe, None)?)
+    }
 
-        match rows.next() {
-            Some(Ok(recipe)) => Ok(Some(recipe)),
-            Some(Err(e)) => Err(DBError::Connection(e)),
-            None => Ok(None),
+    /// Merges a duplicate of a saved recipe into it, filling in the fields the saved recipe is
+    /// missing and adding the duplicate's `labels`. Fields the saved recipe already has are
+    /// kept. Returns the id of the saved recipe.
+    pub fn merge_recipe(
+        &self,
+        id: &i32,
+        duplicate: &RecipeDetails,
+        labels: Option<&RecipeLabels>,
+    ) -> Result<i32, DBError> {
+        let mut conn = self.pool.get().map_err(|_| DBError::ConnectionPool)?;
+        let tx = conn.transaction()?;
+
+        let current = live_recipe_by_id(&tx, id)?.ok_or(DBError::NoRecord)?;
+        let merged = fill_missing_fields(&current, duplicate);
+        if merged != current {
+            update_recipe(&tx, &merged)?;
+        }
+
+        if let Some(labels) = labels {
+            add_label_tags(&tx, id, labels)?;
+        }
+
+        tx.commit()?;
+        Ok(*id)
+    }
+
+    /// Scans the library for pairs of recipes that are likely the same dish, see
+    /// `find_duplicate_candidates`.
+    pub fn find_similar_recipes(&self, min_score: f64) -> Result<Vec<DuplicateCandidate>, DBError> {
+        if !(0.0..=1.0).contains(&min_score) {
+            return Err(DBError::Validation(
+                "minimum score must be between 0 and 1".to_string(),
+            ));
+        }
+
+        let recipes = self.get_all_recipes()?;
+        Ok(find_duplicate_candidates(&recipes, min_score))
+    }
+
+    /// Merges the recipe `merge_id` into `keep_id`. The kept recipe's content wins, with the
+    /// fields it is missing filled in from the merged one. Tags, collections and cook log
+    /// entries are moved over, and the merged recipe is moved to the trash along with its
+    /// revisions.
+    pub fn merge_recipes(&self, keep_id: &i32, merge_id: &i32) -> Result<(), DBError> {
+        if keep_id == merge_id {
+            return Err(DBError::Validation(
+                "a recipe can't be merged into itself".to_string(),
+            ));
+        }
+
+        let mut conn = self.pool.get().map_err(|_| DBError::ConnectionPool)?;
+        let tx = conn.transaction()?;
+
+        let kept = live_recipe_by_id(&tx, keep_id)?.ok_or(DBError::NoRecord)?;
+        let merged = live_recipe_by_id(&tx, merge_id)?.ok_or(DBError::NoRecord)?;
+
+        // The merged recipe is trashed first, as taking over its URL would otherwise make
+        // it a duplicate of the kept one
+        tx.execute(
+            "UPDATE RecipeDetails SET deleted_at = CURRENT_TIMESTAMP WHERE id = ?1",
+            params![merge_id],
+        )?;
+        let filled = fill_missing_fields(&kept, &merged);
+        if filled != kept {
+            update_recipe(&tx, &filled)?;
+            if filled.url != kept.url {
+                tx.execute(
+                    "UPDATE RecipeDetails SET canonical_url = ?1 WHERE id = ?2",
+                    params![canonical_url_of(&merged), keep_id],
+                )?;
+            }
         }
+
+        tx.execute(
+            "INSERT OR IGNORE INTO RecipeTag (recipe_id, tag_id, origin)
+             SELECT ?1, tag_id, origin FROM RecipeTag WHERE recipe_id = ?2",
+            params![keep_id, merge_id],
+        )?;
+        tx.execute(
+            "DELETE FROM RecipeTag WHERE recipe_id = ?1",
+            params![merge_id],
+        )?;
+        tx.execute(
+            "INSERT OR IGNORE INTO CollectionRecipe (collection_id, recipe_id, position)
+             SELECT collection_id, ?1, position FROM CollectionRecipe WHERE recipe_id = ?2",
+            params![keep_id, merge_id],
+        )?;
+        tx.execute(
+            "DELETE FROM CollectionRecipe WHERE recipe_id = ?1",
+            params![merge_id],
+        )?;
+        renumber_collections_of(&tx, keep_id)?;
+        tx.execute(
+            "UPDATE CookLog SET recipe_id = ?1 WHERE recipe_id = ?2",
+            params![keep_id, merge_id],
+        )?;
+
+        tx.commit()?;
+        Ok(())
+    }
+
+    pub fn get_recipe_by_id(&self, id: &i32) -> Result<Option<RecipeDetails>, DBError> {
+        let conn = self.pool.get().map_err(|_| DBError::ConnectionPool)?;
+        Ok(live_recipe_by_id(&conn, id)?)
+    }
+
+    /// Returns the recipes with the given ids, in that order. Fails with `NoRecord` if any of
+    /// them isn't in the library.
+    pub fn get_recipes_by_ids(&self, ids: &[i32]) -> Result<Vec<RecipeDetails>, DBError> {
+        let conn = self.pool.get().map_err(|_| DBError::ConnectionPool)?;
+        ids.iter()
+            .map(|id| live_recipe_by_id(&conn, id)?.ok_or(DBError::NoRecord))
+            .collect()
     }
 
     pub fn get_all_recipes(&self) -> Result<Vec<RecipeDetails>, DBError> {
         let conn = self.pool.get().map_err(|_| DBError::ConnectionPool)?;
 
-        let mut stmt = conn.prepare("SELECT * FROM RecipeDetails")?;
-        let recipes_iter = stmt.query_map([], |row| {
-            let ingredients: Option<String> = row.get(8)?;
-            let ingredients =
-                string_to_vec(&ingredients).map_err(|_| rusqlite::Error::InvalidQuery)?;
-            let instructions: Option<String> = row.get(9)?;
-            let instructions =
-                string_to_vec(&instructions).map_err(|_| rusqlite::Error::InvalidQuery)?;
-            Ok(RecipeDetails {
-                id: row.get(0)?,
-                image: row.get(1)?,
-                url: row.get(2)?,
-                servings: row.get(3)?,
-                time: row.get(4)?,
-                calories: row.get(5)?,
-                source: row.get(6)?,
-                title: row.get(7)?,
-                ingredients,
-                instructions,
-                date_added: row.get(10)?,
-            })
-        })?;
+        let mut stmt =
+            conn.prepare_cached("SELECT * FROM RecipeDetails WHERE deleted_at IS NULL")?;
+        let recipes_iter = stmt.query_map([], recipe_from_row)?;
 
         let mut recipes = Vec::new();
 
@@ -139,64 +657,2299 @@ CREATE TABLE IF NOT EXISTS RecipeDetails (
         Ok(recipes)
     }
 
-    pub fn update_recipe(&self, recipe: &RecipeDetails) -> Result<(), DBError> {
-        let conn = self.pool.get().map_err(|_| DBError::ConnectionPool)?;
+    /// Lists one page of recipe summaries using keyset pagination, so that pages stay
+    /// consistent while recipes are added or removed and deep pages stay cheap.
+    pub fn list_recipes(&self, query: &RecipeListQuery) -> Result<RecipePage, DBError> {
+        const DEFAULT_LIMIT: i32 = 50;
+        const MAX_LIMIT: i32 = 500;
 
-        let id = match &recipe.id {
-            Some(id) => id,
-            None => return Err(DBError::MissingID),
+        let limit = query.limit.unwrap_or(DEFAULT_LIMIT).clamp(1, MAX_LIMIT);
+        // Sort keys are made non-null so that they can be compared against the cursor
+        let sort_key = match query.sort {
+            RecipeSort::DateAdded => "COALESCE(RecipeDetails.date_added, '')",
+            RecipeSort::Title => "lower(RecipeDetails.title)",
+            RecipeSort::Time => "RecipeDetails.time",
+            RecipeSort::Calories => "RecipeDetails.calories",
+            RecipeSort::LastCooked => "COALESCE(CookStats.last_cooked, '')",
+            RecipeSort::Rating => "COALESCE(CookStats.average_rating, 0)",
+        };
+        let (direction, comparison) = match query.direction {
+            SortDirection::Asc => ("ASC", ">"),
+            SortDirection::Desc => ("DESC", "<"),
         };
 
-        let recipe_exists = self.does_recipe_exist_by_id(id)?;
+        let mut conditions = vec!["RecipeDetails.deleted_at IS NULL".to_owned()];
+        let mut values: Vec<Value> = Vec::new();
 
-        match recipe_exists {
+        if !query.tag_ids.is_empty() {
+            let placeholders = vec!["?"; query.tag_ids.len()].join(", ");
+            let required_matches = match query.match_all_tags {
+                true => query.tag_ids.len(),
+                false => 1,
+            };
+            conditions.push(format!(
+                "RecipeDetails.id IN (
+                   SELECT recipe_id FROM RecipeTag
+                   WHERE tag_id IN ({placeholders})
+                   GROUP BY recipe_id
+                   HAVING COUNT(DISTINCT tag_id) >= {required_matches}
+                 )"
+            ));
+            values.extend(query.tag_ids.iter().map(|id| Value::Integer(*id as i64)));
+        }
+
+        if let Some(cursor) = &query.cursor {
+            let cursor = RecipeCursor::decode(cursor)?;
+            if cursor.sort != query.sort {
+                return Err(DBError::Validation(
+                    "cursor was created for a different sort".to_owned(),
+                ));
+            }
+            conditions.push(format!(
+                "({sort_key} {comparison} ? OR ({sort_key} = ? AND RecipeDetails.id {comparison} ?))"
+            ));
+            values.push(cursor.sql_value());
+            values.push(cursor.sql_value());
+            values.push(Value::Integer(cursor.id as i64));
+        }
+
+        let conn = self.pool.get().map_err(|_| DBError::ConnectionPool)?;
+        let mut stmt = conn.prepare(&format!(
+            "SELECT RecipeDetails.id, RecipeDetails.title, RecipeDetails.image, RecipeDetails.source,
+                    RecipeDetails.servings, RecipeDetails.time, RecipeDetails.calories,
+                    RecipeDetails.date_added, CookStats.last_cooked,
+                    COALESCE(CookStats.times_cooked, 0), CookStats.average_rating,
+                    RecipeDetails.image_thumbnail, {sort_key}
+             FROM RecipeDetails
+             LEFT JOIN (
+               SELECT recipe_id, MAX(cooked_on) AS last_cooked, COUNT(*) AS times_cooked,
+                      AVG(rating) AS average_rating
+               FROM CookLog GROUP BY recipe_id
+             ) AS CookStats ON CookStats.recipe_id = RecipeDetails.id
+             WHERE {}
+             ORDER BY {sort_key} {direction}, RecipeDetails.id {direction}
+             LIMIT {}",
+            conditions.join(" AND "),
+            // One extra row tells whether there is a next page
+            limit + 1
+        ))?;
+        let mut rows = stmt
+            .query_map(params_from_iter(values), |row| {
+                let summary = RecipeSummary {
+                    id: row.get(0)?,
+                    title: row.get(1)?,
+                    image: row.get(2)?,
+                    source: row.get(3)?,
+                    servings: row.get(4)?,
+                    time: row.get(5)?,
+                    calories: row.get(6)?,
+                    date_added: row.get(7)?,
+                    last_cooked: row.get(8)?,
+                    times_cooked: row.get(9)?,
+                    average_rating: row.get(10)?,
+                    image_thumbnail: row.get(11)?,
+                };
+                Ok((summary, row.get::<_, Value>(12)?))
+            })?
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let next_cursor = match rows.len() > limit as usize {
             true => {
-                let ingredients_str = vec_to_string(&recipe.ingredients)?;
-                let instructions_str = vec_to_string(&recipe.instructions)?;
+                rows.truncate(limit as usize);
+                match rows.last() {
+                    Some((summary, sort_value)) => {
+                        Some(RecipeCursor::new(query.sort, sort_value, summary.id).encode()?)
+                    }
+                    None => None,
+                }
+            }
+            false => None,
+        };
 
-                conn.execute(
-        "UPDATE RecipeDetails SET image = ?1, url = ?2, servings = ?3, time = ?4, calories = ?5, source = ?6, title = ?7, ingredients = ?8, instructions = ?9, date_added = ?10 WHERE id = ?11",
-        params![&recipe.image, &recipe.url, &recipe.servings, &recipe.time, &recipe.calories, &recipe.source, &recipe.title, &ingredients_str, &instructions_str, &recipe.date_added, &id],
-    )?;
+        Ok(RecipePage {
+            recipes: rows.into_iter().map(|(summary, _)| summary).collect(),
+            next_cursor,
+        })
+    }
 
-                Ok(())
+    /// Overwrites a saved recipe and appends the result to its revision history.
+    ///
+    /// `recipe.version` must be the version that was read before editing. If the recipe has
+    /// been saved by someone else since, nothing is written and `DBError::Conflict` is
+    /// returned with the current copy so that the changes can be merged and saved again.
+    pub fn update_recipe(&self, recipe: &RecipeDetails) -> Result<(), DBError> {
+        let mut conn = self.pool.get().map_err(|_| DBError::ConnectionPool)?;
+        let tx = conn.transaction()?;
+
+        update_recipe(&tx, recipe)?;
+
+        tx.commit()?;
+        Ok(())
+    }
+
+    /// Moves a recipe to the trash. Its tags, collections and cook log are kept so that it can
+    /// be restored as it was.
+    pub fn delete_recipe_by_id(&self, id: &i32) -> Result<(), DBError> {
+        let conn = self.pool.get().map_err(|_| DBError::ConnectionPool)?;
+
+        // Only trashes the recipe if it exists and is not in the trash already
+        match conn.execute(
+            "UPDATE RecipeDetails SET deleted_at = CURRENT_TIMESTAMP WHERE id = ?1 AND deleted_at IS NULL",
+            params![&id],
+        )? {
+            0 => Err(DBError::NoRecord),
+            _ => Ok(()),
+        }
+    }
+
+    /// Returns the recipes in the trash, most recently deleted first.
+    pub fn get_trashed_recipes(&self) -> Result<Vec<RecipeDetails>, DBError> {
+        let conn = self.pool.get().map_err(|_| DBError::ConnectionPool)?;
+
+        let mut stmt = conn.prepare_cached(
+            "SELECT * FROM RecipeDetails WHERE deleted_at IS NOT NULL ORDER BY deleted_at DESC",
+        )?;
+        let recipes = stmt
+            .query_map([], recipe_from_row)?
+            .collect::<Result<Vec<_>, _>>()?;
+
+        Ok(recipes)
+    }
+
+    pub fn restore_recipe_by_id(&self, id: &i32) -> Result<(), DBError> {
+        let conn = self.pool.get().map_err(|_| DBError::ConnectionPool)?;
+
+        match conn.execute(
+            "UPDATE RecipeDetails SET deleted_at = NULL WHERE id = ?1 AND deleted_at IS NOT NULL",
+            params![id],
+        )? {
+            0 => Err(DBError::NoRecord),
+            _ => Ok(()),
+        }
+    }
+
+    /// Permanently deletes a recipe from the trash, returning the image files that no recipe,
+    /// revision or collection cover uses any more and that should be removed from disk.
+    pub fn purge_recipe_by_id(&self, id: &i32) -> Result<Vec<String>, DBError> {
+        let mut conn = self.pool.get().map_err(|_| DBError::ConnectionPool)?;
+        let tx = conn.transaction()?;
+
+        let recipe_trashed: bool = tx.query_row(
+            "SELECT EXISTS(SELECT 1 FROM RecipeDetails WHERE id = ?1 AND deleted_at IS NOT NULL)",
+            params![id],
+            |row| row.get(0),
+        )?;
+        if !recipe_trashed {
+            return Err(DBError::NoRecord);
+        }
+        let unused_images = purge_recipes(&tx, "id = ?1", params![id])?;
+
+        tx.commit()?;
+        Ok(unused_images)
+    }
+
+    /// Permanently deletes every recipe in the trash. See `purge_recipe_by_id`.
+    pub fn empty_trash(&self) -> Result<Vec<String>, DBError> {
+        let mut conn = self.pool.get().map_err(|_| DBError::ConnectionPool)?;
+        let tx = conn.transaction()?;
+
+        let unused_images = purge_recipes(&tx, "1", [])?;
+
+        tx.commit()?;
+        Ok(unused_images)
+    }
+
+    /// Permanently deletes recipes that have been in the trash for more than `days` days.
+    /// See `purge_recipe_by_id`.
+    pub fn purge_expired_trash(&self, days: &i32) -> Result<Vec<String>, DBError> {
+        let mut conn = self.pool.get().map_err(|_| DBError::ConnectionPool)?;
+        let tx = conn.transaction()?;
+
+        let unused_images = purge_recipes(
+            &tx,
+            "deleted_at <= datetime('now', '-' || ?1 || ' days')",
+            params![days],
+        )?;
+
+        tx.commit()?;
+        Ok(unused_images)
+    }
+
+    pub fn get_settings(&self) -> Result<Settings, DBError> {
+        let conn = self.pool.get().map_err(|_| DBError::ConnectionPool)?;
+
+        let mut stmt = conn.prepare_cached("SELECT value FROM Setting WHERE key = ?1")?;
+        let mut get = |key: &str| -> Result<Option<String>, DBError> {
+            let mut rows = stmt.query_map(params![key], |row| row.get::<_, String>(0))?;
+            Ok(rows.next().transpose()?)
+        };
+
+        let defaults = Settings::default();
+        let settings = Settings {
+            trash_retention_days: match get("trash_retention_days")? {
+                Some(value) => serde_json::from_str(&value)?,
+                None => defaults.trash_retention_days,
+            },
+            backup_dir: match get("backup_dir")? {
+                Some(value) => serde_json::from_str(&value)?,
+                None => defaults.backup_dir,
+            },
+            backup_interval_hours: match get("backup_interval_hours")? {
+                Some(value) => serde_json::from_str(&value)?,
+                None => defaults.backup_interval_hours,
+            },
+            backup_generations: match get("backup_generations")? {
+                Some(value) => serde_json::from_str(&value)?,
+                None => defaults.backup_generations,
+            },
+        };
+
+        Ok(settings)
+    }
+
+    pub fn update_settings(&self, settings: &Settings) -> Result<(), DBError> {
+        if let Some(days) = settings.trash_retention_days {
+            if days < 1 {
+                return Err(DBError::Validation(format!(
+                    "trash retention must be at least 1 day, got {days}"
+                )));
+            }
+        }
+        if let Some(hours) = settings.backup_interval_hours {
+            if hours < 1 {
+                return Err(DBError::Validation(format!(
+                    "backup interval must be at least 1 hour, got {hours}"
+                )));
+            }
+        }
+        if let Some(generations) = settings.backup_generations {
+            if generations < 1 {
+                return Err(DBError::Validation(format!(
+                    "at least 1 backup must be kept, got {generations}"
+                )));
+            }
+        }
+        if let Some(dir) = &settings.backup_dir {
+            if !Path::new(dir).is_absolute() {
+                return Err(DBError::Validation(format!(
+                    "backup directory must be an absolute path, got {dir}"
+                )));
             }
-            false => Err(DBError::NoRecord),
         }
+
+        let mut conn = self.pool.get().map_err(|_| DBError::ConnectionPool)?;
+        let tx = conn.transaction()?;
+
+        let values = [
+            (
+                "trash_retention_days",
+                serde_json::to_string(&settings.trash_retention_days)?,
+            ),
+            ("backup_dir", serde_json::to_string(&settings.backup_dir)?),
+            (
+                "backup_interval_hours",
+                serde_json::to_string(&settings.backup_interval_hours)?,
+            ),
+            (
+                "backup_generations",
+                serde_json::to_string(&settings.backup_generations)?,
+            ),
+        ];
+        for (key, value) in values {
+            tx.execute(
+                "INSERT INTO Setting (key, value) VALUES (?1, ?2)
+                 ON CONFLICT (key) DO UPDATE SET value = excluded.value",
+                params![key, value],
+            )?;
+        }
+
+        tx.commit()?;
+        Ok(())
     }
 
-    pub fn delete_recipe_by_id(&self, id: &i32) -> Result<(), DBError> {
+    /// Whether the last automatic backup is more than `interval_hours` old, or there was none.
+    pub fn is_backup_due(&self, interval_hours: &i32) -> Result<bool, DBError> {
         let conn = self.pool.get().map_err(|_| DBError::ConnectionPool)?;
+        let is_due = conn.query_row(
+            "SELECT NOT EXISTS(
+               SELECT 1 FROM Setting WHERE key = 'last_backup_at'
+               AND json_extract(value, '$') > datetime('now', '-' || ?1 || ' hours')
+             )",
+            params![interval_hours],
+            |row| row.get(0),
+        )?;
+        Ok(is_due)
+    }
 
-        // Check if the recipe exists
-        let recipe_exists = self.does_recipe_exist_by_id(id)?;
-        // If it does, delete it
-        match recipe_exists {
-            true => {
-                conn.execute("DELETE FROM RecipeDetails WHERE id = ?1", params![&id])?;
+    /// Remembers that an automatic backup was just taken, see `is_backup_due`.
+    pub fn record_backup(&self) -> Result<(), DBError> {
+        let conn = self.pool.get().map_err(|_| DBError::ConnectionPool)?;
+        conn.execute(
+            "INSERT INTO Setting (key, value) VALUES ('last_backup_at', json_quote(datetime('now')))
+             ON CONFLICT (key) DO UPDATE SET value = excluded.value",
+            [],
+        )?;
+        Ok(())
+    }
+
+    pub fn does_recipe_exist_by_url(&self, url: &str) -> Result<bool, DBError> {
+        Ok(self.find_recipe_by_url(url)?.is_some())
+    }
+
+    pub fn get_all_tags(&self) -> Result<Vec<Tag>, DBError> {
+        let conn = self.pool.get().map_err(|_| DBError::ConnectionPool)?;
+
+        let mut stmt = conn.prepare_cached("SELECT id, name, category FROM Tag ORDER BY name")?;
+        let tags = stmt
+            .query_map([], tag_from_row)?
+            .collect::<Result<Vec<_>, _>>()?;
 
+        Ok(tags)
+    }
+
+    pub fn create_tag(&self, name: &str) -> Result<Tag, DBError> {
+        let name = validate_name(name)?;
+        let conn = self.pool.get().map_err(|_| DBError::ConnectionPool)?;
+
+        let id = upsert_tag(&conn, name, None)?;
+        let tag = conn.query_row(
+            "SELECT id, name, category FROM Tag WHERE id = ?1",
+            params![id],
+            tag_from_row,
+        )?;
+
+        Ok(tag)
+    }
+
+    pub fn rename_tag(&self, id: &i32, name: &str) -> Result<(), DBError> {
+        let name = validate_name(name)?;
+        let conn = self.pool.get().map_err(|_| DBError::ConnectionPool)?;
+
+        match conn.execute("UPDATE Tag SET name = ?1 WHERE id = ?2", params![name, id])? {
+            0 => Err(DBError::NoRecord),
+            _ => Ok(()),
+        }
+    }
+
+    pub fn delete_tag(&self, id: &i32) -> Result<(), DBError> {
+        let mut conn = self.pool.get().map_err(|_| DBError::ConnectionPool)?;
+        let tx = conn.transaction()?;
+
+        tx.execute("DELETE FROM RecipeTag WHERE tag_id = ?1", params![id])?;
+        match tx.execute("DELETE FROM Tag WHERE id = ?1", params![id])? {
+            0 => Err(DBError::NoRecord),
+            _ => {
+                tx.commit()?;
                 Ok(())
             }
-            false => Err(DBError::NoRecord),
         }
     }
-    pub fn does_recipe_exist_by_url(&self, url: &str) -> Result<bool, DBError> {
+
+    pub fn get_tags_by_category(&self, category: &TagCategory) -> Result<Vec<Tag>, DBError> {
         let conn = self.pool.get().map_err(|_| DBError::ConnectionPool)?;
-        let recipe_exists: bool = conn.query_row(
-            "SELECT EXISTS(SELECT 1 FROM RecipeDetails WHERE url = ?1)",
-            params![&url],
-            |row| row.get(0),
+
+        let mut stmt = conn.prepare_cached(
+            "SELECT id, name, category FROM Tag WHERE category = ?1 ORDER BY name",
         )?;
-        Ok(recipe_exists)
+        let tags = stmt
+            .query_map(params![category], tag_from_row)?
+            .collect::<Result<Vec<_>, _>>()?;
+
+        Ok(tags)
     }
 
-    fn does_recipe_exist_by_id(&self, id: &i32) -> Result<bool, DBError> {
+    pub fn get_tags_for_recipe(&self, recipe_id: &i32) -> Result<Vec<RecipeTag>, DBError> {
         let conn = self.pool.get().map_err(|_| DBError::ConnectionPool)?;
-        let recipe_exists: bool = conn.query_row(
-            "SELECT EXISTS(SELECT 1 FROM RecipeDetails WHERE id = ?1)",
-            params![&id],
-            |row| row.get(0),
+
+        let mut stmt = conn.prepare_cached(
+            "SELECT Tag.id, Tag.name, Tag.category, RecipeTag.origin FROM Tag
+             JOIN RecipeTag ON RecipeTag.tag_id = Tag.id
+             WHERE RecipeTag.recipe_id = ?1
+             ORDER BY Tag.name, RecipeTag.origin",
+        )?;
+        let tags = stmt
+            .query_map(params![recipe_id], |row| {
+                Ok(RecipeTag {
+                    id: row.get(0)?,
+                    name: row.get(1)?,
+                    category: row.get(2)?,
+                    origin: row.get(3)?,
+                })
+            })?
+            .collect::<Result<Vec<_>, _>>()?;
+
+        Ok(tags)
+    }
+
+    /// Tags a recipe by name, creating the tag first if it does not exist yet.
+    pub fn add_tag_to_recipe(&self, recipe_id: &i32, name: &str) -> Result<Tag, DBError> {
+        let name = validate_name(name)?;
+        let mut conn = self.pool.get().map_err(|_| DBError::ConnectionPool)?;
+        let tx = conn.transaction()?;
+
+        if !does_recipe_exist_by_id(&tx, recipe_id)? {
+            return Err(DBError::NoRecord);
+        }
+
+        let tag_id = upsert_tag(&tx, name, None)?;
+        tx.execute(
+            "INSERT OR IGNORE INTO RecipeTag (recipe_id, tag_id, origin) VALUES (?1, ?2, ?3)",
+            params![recipe_id, tag_id, TagOrigin::User],
+        )?;
+        let tag = tx.query_row(
+            "SELECT id, name, category FROM Tag WHERE id = ?1",
+            params![tag_id],
+            tag_from_row,
         )?;
-        Ok(recipe_exists)
+
+        tx.commit()?;
+        Ok(tag)
+    }
+
+    /// Removes a tag from a recipe, whoever applied it.
+    pub fn remove_tag_from_recipe(&self, recipe_id: &i32, tag_id: &i32) -> Result<(), DBError> {
+        let conn = self.pool.get().map_err(|_| DBError::ConnectionPool)?;
+
+        conn.execute(
+            "DELETE FROM RecipeTag WHERE recipe_id = ?1 AND tag_id = ?2",
+            params![recipe_id, tag_id],
+        )?;
+
+        Ok(())
+    }
+
+    /// Returns the recipes carrying the given tags.
+    ///
+    /// With `match_all` set, a recipe must carry every tag in `tag_ids`, otherwise carrying
+    /// any one of them is enough. An empty `tag_ids` returns the whole library.
+    pub fn get_recipes_by_tags(
+        &self,
+        tag_ids: &[i32],
+        match_all: bool,
+    ) -> Result<Vec<RecipeDetails>, DBError> {
+        if tag_ids.is_empty() {
+            return self.get_all_recipes();
+        }
+
+        let conn = self.pool.get().map_err(|_| DBError::ConnectionPool)?;
+
+        let placeholders = vec!["?"; tag_ids.len()].join(", ");
+        let required_matches = match match_all {
+            true => tag_ids.len(),
+            false => 1,
+        };
+        let mut stmt = conn.prepare(&format!(
+            "SELECT * FROM RecipeDetails WHERE deleted_at IS NULL AND id IN (
+               SELECT recipe_id FROM RecipeTag
+               WHERE tag_id IN ({placeholders})
+               GROUP BY recipe_id
+               HAVING COUNT(DISTINCT tag_id) >= {required_matches}
+             )"
+        ))?;
+        let recipes = stmt
+            .query_map(params_from_iter(tag_ids), recipe_from_row)?
+            .collect::<Result<Vec<_>, _>>()?;
+
+        Ok(recipes)
+    }
+
+    /// Returns the recipes matching any of `cuisine_types` and any of `meal_types`, as tagged
+    /// from Edamam labels. An empty list does not filter on that label group.
+    pub fn get_recipes_by_labels(
+        &self,
+        cuisine_types: &[String],
+        meal_types: &[String],
+    ) -> Result<Vec<RecipeDetails>, DBError> {
+        let conn = self.pool.get().map_err(|_| DBError::ConnectionPool)?;
+
+        let mut conditions = Vec::new();
+        let mut values: Vec<&dyn ToSql> = Vec::new();
+        for (category, names) in [
+            (&TagCategory::Cuisine, cuisine_types),
+            (&TagCategory::Meal, meal_types),
+        ] {
+            if names.is_empty() {
+                continue;
+            }
+            let placeholders = vec!["?"; names.len()].join(", ");
+            conditions.push(format!(
+                "id IN (
+                   SELECT RecipeTag.recipe_id FROM RecipeTag
+                   JOIN Tag ON Tag.id = RecipeTag.tag_id
+                   WHERE Tag.category = ? AND Tag.name IN ({placeholders})
+                 )"
+            ));
+            values.push(category);
+            values.extend(names.iter().map(|name| name as &dyn ToSql));
+        }
+
+        conditions.push("deleted_at IS NULL".to_owned());
+        let mut stmt = conn.prepare(&format!(
+            "SELECT * FROM RecipeDetails WHERE {}",
+            conditions.join(" AND ")
+        ))?;
+        let recipes = stmt
+            .query_map(values.as_slice(), recipe_from_row)?
+            .collect::<Result<Vec<_>, _>>()?;
+
+        Ok(recipes)
+    }
+
+    pub fn get_all_collections(&self) -> Result<Vec<Collection>, DBError> {
+        let conn = self.pool.get().map_err(|_| DBError::ConnectionPool)?;
+
+        let mut stmt = conn.prepare_cached(
+            "SELECT Collection.id, Collection.name, Collection.cover_image, Collection.position,
+                    COUNT(RecipeDetails.id), Collection.date_added
+             FROM Collection
+             LEFT JOIN CollectionRecipe ON CollectionRecipe.collection_id = Collection.id
+             LEFT JOIN RecipeDetails ON RecipeDetails.id = CollectionRecipe.recipe_id
+               AND RecipeDetails.deleted_at IS NULL
+             GROUP BY Collection.id
+             ORDER BY Collection.position",
+        )?;
+        let collections = stmt
+            .query_map([], collection_from_row)?
+            .collect::<Result<Vec<_>, _>>()?;
+
+        Ok(collections)
+    }
+
+    pub fn create_collection(
+        &self,
+        name: &str,
+        cover_image: Option<String>,
+    ) -> Result<Collection, DBError> {
+        let name = validate_name(name)?;
+        let conn = self.pool.get().map_err(|_| DBError::ConnectionPool)?;
+
+        conn.execute(
+            "INSERT INTO Collection (name, cover_image, position)
+             VALUES (?1, ?2, (SELECT COALESCE(MAX(position) + 1, 0) FROM Collection))",
+            params![name, cover_image],
+        )?;
+        let collection = conn.query_row(
+            "SELECT id, name, cover_image, position, 0, date_added FROM Collection WHERE id = ?1",
+            params![conn.last_insert_rowid()],
+            collection_from_row,
+        )?;
+
+        Ok(collection)
+    }
+
+    /// Updates the name and cover image of a collection. Ordering is changed through
+    /// `reorder_collections` instead.
+    pub fn update_collection(&self, collection: &Collection) -> Result<(), DBError> {
+        let name = validate_name(&collection.name)?;
+        let conn = self.pool.get().map_err(|_| DBError::ConnectionPool)?;
+
+        match conn.execute(
+            "UPDATE Collection SET name = ?1, cover_image = ?2 WHERE id = ?3",
+            params![name, &collection.cover_image, &collection.id],
+        )? {
+            0 => Err(DBError::NoRecord),
+            _ => Ok(()),
+        }
+    }
+
+    pub fn delete_collection(&self, id: &i32) -> Result<(), DBError> {
+        let mut conn = self.pool.get().map_err(|_| DBError::ConnectionPool)?;
+        let tx = conn.transaction()?;
+
+        tx.execute(
+            "DELETE FROM CollectionRecipe WHERE collection_id = ?1",
+            params![id],
+        )?;
+        match tx.execute("DELETE FROM Collection WHERE id = ?1", params![id])? {
+            0 => Err(DBError::NoRecord),
+            _ => {
+                tx.commit()?;
+                Ok(())
+            }
+        }
+    }
+
+    /// Sets the display order of collections to the order of `ids`.
+    pub fn reorder_collections(&self, ids: &[i32]) -> Result<(), DBError> {
+        let mut conn = self.pool.get().map_err(|_| DBError::ConnectionPool)?;
+        let tx = conn.transaction()?;
+
+        for (position, id) in ids.iter().enumerate() {
+            tx.execute(
+                "UPDATE Collection SET position = ?1 WHERE id = ?2",
+                params![position, id],
+            )?;
+        }
+
+        tx.commit()?;
+        Ok(())
+    }
+
+    pub fn get_collection_recipes(
+        &self,
+        collection_id: &i32,
+    ) -> Result<Vec<RecipeDetails>, DBError> {
+        let conn = self.pool.get().map_err(|_| DBError::ConnectionPool)?;
+
+        let mut stmt = conn.prepare_cached(
+            "SELECT RecipeDetails.* FROM RecipeDetails
+             JOIN CollectionRecipe ON CollectionRecipe.recipe_id = RecipeDetails.id
+             WHERE CollectionRecipe.collection_id = ?1 AND RecipeDetails.deleted_at IS NULL
+             ORDER BY CollectionRecipe.position",
+        )?;
+        let recipes = stmt
+            .query_map(params![collection_id], recipe_from_row)?
+            .collect::<Result<Vec<_>, _>>()?;
+
+        Ok(recipes)
+    }
+
+    /// Appends a recipe to the end of a collection. Adding a recipe that is already in the
+    /// collection keeps its current position.
+    pub fn add_recipe_to_collection(
+        &self,
+        collection_id: &i32,
+        recipe_id: &i32,
+    ) -> Result<(), DBError> {
+        let mut conn = self.pool.get().map_err(|_| DBError::ConnectionPool)?;
+        let tx = conn.transaction()?;
+
+        let collection_exists: bool = tx.query_row(
+            "SELECT EXISTS(SELECT 1 FROM Collection WHERE id = ?1)",
+            params![collection_id],
+            |row| row.get(0),
+        )?;
+        if !collection_exists || !does_recipe_exist_by_id(&tx, recipe_id)? {
+            return Err(DBError::NoRecord);
+        }
+
+        tx.execute(
+            "INSERT OR IGNORE INTO CollectionRecipe (collection_id, recipe_id, position)
+             VALUES (?1, ?2, (SELECT COALESCE(MAX(position) + 1, 0) FROM CollectionRecipe WHERE collection_id = ?1))",
+            params![collection_id, recipe_id],
+        )?;
+
+        tx.commit()?;
+        Ok(())
+    }
+
+    pub fn remove_recipe_from_collection(
+        &self,
+        collection_id: &i32,
+        recipe_id: &i32,
+    ) -> Result<(), DBError> {
+        let conn = self.pool.get().map_err(|_| DBError::ConnectionPool)?;
+
+        conn.execute(
+            "DELETE FROM CollectionRecipe WHERE collection_id = ?1 AND recipe_id = ?2",
+            params![collection_id, recipe_id],
+        )?;
+
+        Ok(())
+    }
+
+    /// Sets the order of recipes within a collection to the order of `recipe_ids`.
+    pub fn reorder_collection_recipes(
+        &self,
+        collection_id: &i32,
+        recipe_ids: &[i32],
+    ) -> Result<(), DBError> {
+        let mut conn = self.pool.get().map_err(|_| DBError::ConnectionPool)?;
+        let tx = conn.transaction()?;
+
+        for (position, recipe_id) in recipe_ids.iter().enumerate() {
+            tx.execute(
+                "UPDATE CollectionRecipe SET position = ?1 WHERE collection_id = ?2 AND recipe_id = ?3",
+                params![position, collection_id, recipe_id],
+            )?;
+        }
+
+        tx.commit()?;
+        Ok(())
+    }
+
+    /// Records a cook of a recipe and returns the id of the new entry.
+    pub fn add_cook_log_entry(&self, entry: &CookLogEntry) -> Result<i32, DBError> {
+        let mut conn = self.pool.get().map_err(|_| DBError::ConnectionPool)?;
+        let tx = conn.transaction()?;
+
+        if !does_recipe_exist_by_id(&tx, &entry.recipe_id)? {
+            return Err(DBError::NoRecord);
+        }
+        let cooked_on = validate_cook_log_entry(&tx, entry)?;
+
+        tx.execute(
+            "INSERT INTO CookLog (recipe_id, cooked_on, rating, cook, servings_made, notes)
+             VALUES (?1, COALESCE(?2, date('now', 'localtime')), ?3, ?4, ?5, ?6)",
+            params![
+                &entry.recipe_id,
+                cooked_on,
+                &entry.rating,
+                &entry.cook,
+                &entry.servings_made,
+                &entry.notes
+            ],
+        )?;
+        let id = tx.last_insert_rowid() as i32;
+
+        tx.commit()?;
+        Ok(id)
+    }
+
+    pub fn update_cook_log_entry(&self, entry: &CookLogEntry) -> Result<(), DBError> {
+        let id = match &entry.id {
+            Some(id) => id,
+            None => return Err(DBError::MissingID),
+        };
+
+        let conn = self.pool.get().map_err(|_| DBError::ConnectionPool)?;
+        let cooked_on = validate_cook_log_entry(&conn, entry)?;
+
+        match conn.execute(
+            "UPDATE CookLog SET cooked_on = COALESCE(?1, cooked_on), rating = ?2, cook = ?3, servings_made = ?4, notes = ?5 WHERE id = ?6",
+            params![
+                cooked_on,
+                &entry.rating,
+                &entry.cook,
+                &entry.servings_made,
+                &entry.notes,
+                id
+            ],
+        )? {
+            0 => Err(DBError::NoRecord),
+            _ => Ok(()),
+        }
+    }
+
+    pub fn delete_cook_log_entry(&self, id: &i32) -> Result<(), DBError> {
+        let conn = self.pool.get().map_err(|_| DBError::ConnectionPool)?;
+
+        match conn.execute("DELETE FROM CookLog WHERE id = ?1", params![id])? {
+            0 => Err(DBError::NoRecord),
+            _ => Ok(()),
+        }
+    }
+
+    /// Returns every cook of a recipe, most recent first.
+    pub fn get_cook_log(&self, recipe_id: &i32) -> Result<Vec<CookLogEntry>, DBError> {
+        let conn = self.pool.get().map_err(|_| DBError::ConnectionPool)?;
+
+        let mut stmt = conn.prepare_cached(
+            "SELECT id, recipe_id, cooked_on, rating, cook, servings_made, notes, date_added
+             FROM CookLog WHERE recipe_id = ?1
+             ORDER BY cooked_on DESC, id DESC",
+        )?;
+        let entries = stmt
+            .query_map(params![recipe_id], |row| {
+                Ok(CookLogEntry {
+                    id: row.get(0)?,
+                    recipe_id: row.get(1)?,
+                    cooked_on: row.get(2)?,
+                    rating: row.get(3)?,
+                    cook: row.get(4)?,
+                    servings_made: row.get(5)?,
+                    notes: row.get(6)?,
+                    date_added: row.get(7)?,
+                })
+            })?
+            .collect::<Result<Vec<_>, _>>()?;
+
+        Ok(entries)
+    }
+
+    pub fn get_cook_stats(&self, recipe_id: &i32) -> Result<CookStats, DBError> {
+        let conn = self.pool.get().map_err(|_| DBError::ConnectionPool)?;
+
+        let stats = conn.query_row(
+            "SELECT ?1, COUNT(*), MAX(cooked_on), AVG(rating) FROM CookLog WHERE recipe_id = ?1",
+            params![recipe_id],
+            cook_stats_from_row,
+        )?;
+
+        Ok(stats)
+    }
+
+    /// Returns cook stats for every recipe that has been cooked at least once.
+    pub fn get_all_cook_stats(&self) -> Result<Vec<CookStats>, DBError> {
+        let conn = self.pool.get().map_err(|_| DBError::ConnectionPool)?;
+
+        let mut stmt = conn.prepare_cached(
+            "SELECT recipe_id, COUNT(*), MAX(cooked_on), AVG(rating) FROM CookLog GROUP BY recipe_id",
+        )?;
+        let stats = stmt
+            .query_map([], cook_stats_from_row)?
+            .collect::<Result<Vec<_>, _>>()?;
+
+        Ok(stats)
+    }
+
+    /// Returns the revision history of a recipe, newest first.
+    pub fn get_recipe_revisions(&self, recipe_id: &i32) -> Result<Vec<RecipeRevision>, DBError> {
+        let conn = self.pool.get().map_err(|_| DBError::ConnectionPool)?;
+
+        let mut stmt = conn.prepare_cached(
+            "SELECT id, recipe_id, snapshot, created_at FROM RecipeRevision
+             WHERE recipe_id = ?1 ORDER BY id DESC",
+        )?;
+        let revisions = stmt
+            .query_map(params![recipe_id], revision_from_row)?
+            .collect::<Result<Vec<_>, _>>()?;
+
+        Ok(revisions)
+    }
+
+    pub fn get_recipe_revision(&self, id: &i32) -> Result<RecipeRevision, DBError> {
+        let conn = self.pool.get().map_err(|_| DBError::ConnectionPool)?;
+
+        let mut stmt = conn.prepare_cached(
+            "SELECT id, recipe_id, snapshot, created_at FROM RecipeRevision WHERE id = ?1",
+        )?;
+        let mut rows = stmt.query_map(params![id], revision_from_row)?;
+
+        match rows.next() {
+            Some(Ok(revision)) => Ok(revision),
+            Some(Err(e)) => Err(DBError::Connection(e)),
+            None => Err(DBError::NoRecord),
+        }
+    }
+
+    /// Lists the fields that changed going from revision `from_id` to revision `to_id`.
+    pub fn diff_recipe_revisions(
+        &self,
+        from_id: &i32,
+        to_id: &i32,
+    ) -> Result<Vec<FieldChange>, DBError> {
+        let from = self.get_recipe_revision(from_id)?;
+        let to = self.get_recipe_revision(to_id)?;
+
+        if from.recipe_id != to.recipe_id {
+            return Err(DBError::Validation(
+                "revisions belong to different recipes".to_owned(),
+            ));
+        }
+
+        Ok(diff_recipes(&from.recipe, &to.recipe))
+    }
+
+    /// Saves the content of an older revision as the current version of its recipe. The
+    /// restore is itself recorded as a new revision, so it can be undone the same way.
+    pub fn restore_recipe_revision(&self, id: &i32) -> Result<(), DBError> {
+        let mut conn = self.pool.get().map_err(|_| DBError::ConnectionPool)?;
+        let tx = conn.transaction()?;
+
+        let revision = tx
+            .query_row(
+                "SELECT id, recipe_id, snapshot, created_at FROM RecipeRevision WHERE id = ?1",
+                params![id],
+                revision_from_row,
+            )
+            .optional()?
+            .ok_or(DBError::NoRecord)?;
+        let current = live_recipe_by_id(&tx, &revision.recipe_id)?.ok_or(DBError::NoRecord)?;
+        let recipe = RecipeDetails {
+            id: Some(revision.recipe_id),
+            version: current.version,
+            ..revision.recipe
+        };
+        update_recipe(&tx, &recipe)?;
+
+        tx.commit()?;
+        Ok(())
+    }
+
+    /// Returns the images of recipes missing one of their variants, remote ones included.
+    pub fn get_images_without_variants(&self) -> Result<Vec<String>, DBError> {
+        let conn = self.pool.get().map_err(|_| DBError::ConnectionPool)?;
+        Ok(query_all(
+            &conn,
+            "SELECT DISTINCT image FROM RecipeDetails
+             WHERE image != '' AND (image_thumbnail IS NULL OR image_medium IS NULL)",
+            [],
+            |row| row.get(0),
+        )?)
+    }
+
+    /// Records the variants made of an image on every recipe using it.
+    pub fn set_image_variants(
+        &self,
+        image: &str,
+        thumbnail: Option<&str>,
+        medium: Option<&str>,
+    ) -> Result<(), DBError> {
+        let conn = self.pool.get().map_err(|_| DBError::ConnectionPool)?;
+        conn.execute(
+            "UPDATE RecipeDetails SET image_thumbnail = ?2, image_medium = ?3 WHERE image = ?1",
+            params![image, thumbnail, medium],
+        )?;
+        Ok(())
+    }
+
+    /// Returns the stored image with the given content hash.
+    pub fn get_stored_image(&self, hash: &str) -> Result<Option<StoredImage>, DBError> {
+        let conn = self.pool.get().map_err(|_| DBError::ConnectionPool)?;
+        Ok(conn
+            .query_row(
+                "SELECT hash, path, size, ref_count FROM Image WHERE hash = ?1",
+                params![hash],
+                stored_image_from_row,
+            )
+            .optional()?)
+    }
+
+    pub fn get_stored_images(&self) -> Result<Vec<StoredImage>, DBError> {
+        let conn = self.pool.get().map_err(|_| DBError::ConnectionPool)?;
+        Ok(query_all(
+            &conn,
+            "SELECT hash, path, size, ref_count FROM Image ORDER BY path",
+            [],
+            stored_image_from_row,
+        )?)
+    }
+
+    /// Records an image saved to the images directory, counting the recipes and collection
+    /// covers already pointing to it. An image already stored under the same hash is kept.
+    pub fn add_stored_image(&self, hash: &str, path: &str, size: &i64) -> Result<(), DBError> {
+        let conn = self.pool.get().map_err(|_| DBError::ConnectionPool)?;
+        conn.execute(
+            "INSERT OR IGNORE INTO Image (hash, path, size, ref_count)
+             VALUES (?1, ?2, ?3, (SELECT COUNT(*) FROM RecipeDetails WHERE image = ?2)
+               + (SELECT COUNT(*) FROM Collection WHERE cover_image = ?2))",
+            params![hash, path, size],
+        )?;
+        Ok(())
+    }
+
+    /// Forgets the stored image at `path`, once its file is gone.
+    pub fn remove_stored_image(&self, path: &str) -> Result<(), DBError> {
+        let conn = self.pool.get().map_err(|_| DBError::ConnectionPool)?;
+        conn.execute("DELETE FROM Image WHERE path = ?1", params![path])?;
+        Ok(())
+    }
+
+    /// Counts the users of every stored image again. The triggers keep the counts up to date,
+    /// but a restored snapshot may point to images under paths it didn't know about.
+    pub fn recount_image_refs(&self) -> Result<(), DBError> {
+        let conn = self.pool.get().map_err(|_| DBError::ConnectionPool)?;
+        conn.execute(
+            "UPDATE Image SET ref_count = (SELECT COUNT(*) FROM RecipeDetails WHERE image = Image.path)
+               + (SELECT COUNT(*) FROM Collection WHERE cover_image = Image.path)",
+            [],
+        )?;
+        Ok(())
+    }
+
+    /// Whether a recipe, trashed ones included, a revision of one or a collection cover uses
+    /// the image at `path`.
+    pub fn is_image_used(&self, path: &str) -> Result<bool, DBError> {
+        let conn = self.pool.get().map_err(|_| DBError::ConnectionPool)?;
+        Ok(is_image_used(&conn, path)?)
+    }
+
+    /// Returns the images recipe revisions were saved with. They aren't counted in the
+    /// `ref_count` of stored images, but restoring a revision brings its image back.
+    pub fn get_revision_images(&self) -> Result<Vec<String>, DBError> {
+        let conn = self.pool.get().map_err(|_| DBError::ConnectionPool)?;
+        Ok(query_all(
+            &conn,
+            "SELECT DISTINCT json_extract(snapshot, '$.image') FROM RecipeRevision
+             WHERE json_extract(snapshot, '$.image') != ''",
+            [],
+            |row| row.get(0),
+        )?)
+    }
+
+    /// Points recipes and collection covers using the image at `from` to the copy of it at
+    /// `to`, see `move_image_path`.
+    pub fn repoint_image(&self, from: &str, to: &str) -> Result<(), DBError> {
+        let conn = self.pool.get().map_err(|_| DBError::ConnectionPool)?;
+        move_image_path(&conn, from, to)
+    }
+
+    /// Returns every image recipes and collection covers point to, with the ones using it.
+    pub fn get_image_references(&self) -> Result<Vec<ImageReference>, DBError> {
+        let conn = self.pool.get().map_err(|_| DBError::ConnectionPool)?;
+        let mut references: BTreeMap<String, ImageReference> = BTreeMap::new();
+
+        let recipe_images = query_all(
+            &conn,
+            "SELECT image, id FROM RecipeDetails WHERE image != '' ORDER BY id",
+            [],
+            |row| Ok((row.get::<_, String>(0)?, row.get::<_, i32>(1)?)),
+        )?;
+        for (path, id) in recipe_images {
+            let reference = references.entry(path.clone()).or_default();
+            reference.path = path;
+            reference.recipe_ids.push(id);
+        }
+
+        let covers = query_all(
+            &conn,
+            "SELECT cover_image, id FROM Collection WHERE cover_image IS NOT NULL ORDER BY id",
+            [],
+            |row| Ok((row.get::<_, String>(0)?, row.get::<_, i32>(1)?)),
+        )?;
+        for (path, id) in covers {
+            let reference = references.entry(path.clone()).or_default();
+            reference.path = path;
+            reference.collection_ids.push(id);
+        }
+
+        Ok(references.into_values().collect())
+    }
+
+    /// Queues each URL to be imported, skipping those already queued or being imported.
+    /// Returns the new jobs.
+    pub fn enqueue_import_jobs(&self, urls: &[String]) -> Result<Vec<ImportJob>, DBError> {
+        let mut conn = self.pool.get().map_err(|_| DBError::ConnectionPool)?;
+        let tx = conn.transaction()?;
+
+        let mut jobs = Vec::new();
+        for url in urls {
+            let is_pending: bool = tx.query_row(
+                "SELECT EXISTS(SELECT 1 FROM ImportJob WHERE url = ?1 AND status IN ('queued', 'running'))",
+                params![url],
+                |row| row.get(0),
+            )?;
+            if is_pending {
+                continue;
+            }
+            tx.execute("INSERT INTO ImportJob (url) VALUES (?1)", params![url])?;
+            jobs.push(import_job_by_id(&tx, &(tx.last_insert_rowid() as i32))?);
+        }
+
+        tx.commit()?;
+        Ok(jobs)
+    }
+
+    /// Returns every job in the queue, oldest first.
+    pub fn get_import_jobs(&self) -> Result<Vec<ImportJob>, DBError> {
+        let conn = self.pool.get().map_err(|_| DBError::ConnectionPool)?;
+        Ok(query_all(
+            &conn,
+            &format!("SELECT {IMPORT_JOB_COLUMNS} FROM ImportJob ORDER BY id"),
+            [],
+            import_job_from_row,
+        )?)
+    }
+
+    /// Number of jobs still queued or running.
+    pub fn count_pending_import_jobs(&self) -> Result<i32, DBError> {
+        let conn = self.pool.get().map_err(|_| DBError::ConnectionPool)?;
+        Ok(conn.query_row(
+            "SELECT COUNT(*) FROM ImportJob WHERE status IN ('queued', 'running')",
+            [],
+            |row| row.get(0),
+        )?)
+    }
+
+    /// Marks the oldest queued job that is due as running and returns it. A single statement
+    /// claims the job, so two workers never run the same one.
+    pub fn claim_import_job(&self) -> Result<Option<ImportJob>, DBError> {
+        let conn = self.pool.get().map_err(|_| DBError::ConnectionPool)?;
+        Ok(conn
+            .query_row(
+                &format!(
+                    "UPDATE ImportJob SET status = ?1, attempts = attempts + 1, updated_at = CURRENT_TIMESTAMP
+                     WHERE id = (
+                       SELECT id FROM ImportJob
+                       WHERE status = ?2 AND (run_after IS NULL OR run_after <= CURRENT_TIMESTAMP)
+                       ORDER BY id LIMIT 1
+                     )
+                     RETURNING {IMPORT_JOB_COLUMNS}"
+                ),
+                params![ImportJobStatus::Running, ImportJobStatus::Queued],
+                import_job_from_row,
+            )
+            .optional()?)
+    }
+
+    /// Records how a running job ended.
+    pub fn finish_import_job(
+        &self,
+        id: &i32,
+        status: ImportJobStatus,
+        recipe_id: Option<i32>,
+        error: Option<&str>,
+    ) -> Result<ImportJob, DBError> {
+        let conn = self.pool.get().map_err(|_| DBError::ConnectionPool)?;
+        conn.execute(
+            "UPDATE ImportJob SET status = ?1, recipe_id = ?2, error = ?3, run_after = NULL, updated_at = CURRENT_TIMESTAMP
+             WHERE id = ?4",
+            params![status, recipe_id, error, id],
+        )?;
+        Ok(import_job_by_id(&conn, id)?)
+    }
+
+    /// Puts a job that failed back in the queue, to be run again once `delay_seconds` have
+    /// passed.
+    pub fn requeue_import_job(
+        &self,
+        id: &i32,
+        error: &str,
+        delay_seconds: &i32,
+    ) -> Result<ImportJob, DBError> {
+        let conn = self.pool.get().map_err(|_| DBError::ConnectionPool)?;
+        conn.execute(
+            "UPDATE ImportJob SET status = ?1, error = ?2, run_after = datetime('now', ?3 || ' seconds'), updated_at = CURRENT_TIMESTAMP
+             WHERE id = ?4",
+            params![ImportJobStatus::Queued, error, delay_seconds, id],
+        )?;
+        Ok(import_job_by_id(&conn, id)?)
+    }
+
+    /// Queues jobs left running when the app was last closed, so they start over.
+    pub fn requeue_interrupted_import_jobs(&self) -> Result<(), DBError> {
+        let conn = self.pool.get().map_err(|_| DBError::ConnectionPool)?;
+        conn.execute(
+            "UPDATE ImportJob SET status = ?1, updated_at = CURRENT_TIMESTAMP WHERE status = ?2",
+            params![ImportJobStatus::Queued, ImportJobStatus::Running],
+        )?;
+        Ok(())
+    }
+
+    /// Queues a failed or cancelled job again, with its attempts reset.
+    pub fn retry_import_job(&self, id: &i32) -> Result<ImportJob, DBError> {
+        let conn = self.pool.get().map_err(|_| DBError::ConnectionPool)?;
+        match conn.execute(
+            "UPDATE ImportJob SET status = ?1, attempts = 0, error = NULL, run_after = NULL, updated_at = CURRENT_TIMESTAMP
+             WHERE id = ?2 AND status IN ('failed', 'cancelled')",
+            params![ImportJobStatus::Queued, id],
+        )? {
+            0 => Err(DBError::Validation(
+                "only failed or cancelled imports can be retried".to_owned(),
+            )),
+            _ => Ok(import_job_by_id(&conn, id)?),
+        }
+    }
+
+    /// Cancels a queued job. Jobs already running can't be cancelled.
+    pub fn cancel_import_job(&self, id: &i32) -> Result<ImportJob, DBError> {
+        let conn = self.pool.get().map_err(|_| DBError::ConnectionPool)?;
+        match conn.execute(
+            "UPDATE ImportJob SET status = ?1, run_after = NULL, updated_at = CURRENT_TIMESTAMP
+             WHERE id = ?2 AND status = ?3",
+            params![ImportJobStatus::Cancelled, id, ImportJobStatus::Queued],
+        )? {
+            0 => Err(DBError::Validation(
+                "only queued imports can be cancelled".to_owned(),
+            )),
+            _ => Ok(import_job_by_id(&conn, id)?),
+        }
+    }
+
+    /// Removes jobs that are no longer queued or running from the queue.
+    pub fn clear_finished_import_jobs(&self) -> Result<(), DBError> {
+        let conn = self.pool.get().map_err(|_| DBError::ConnectionPool)?;
+        conn.execute(
+            "DELETE FROM ImportJob WHERE status NOT IN ('queued', 'running')",
+            [],
+        )?;
+        Ok(())
+    }
+}
+
+/// Brings the schema of a database up to date, whether it is new, the library's or an older
+/// snapshot being restored.
+pub fn prepare_schema(conn: &mut Connection) -> Result<(), DBError> {
+    init_table(conn)?;
+    migrate(conn)?;
+    backfill_canonical_urls(conn)?;
+    Ok(())
+}
+
+fn init_table(conn: &Connection) -> Result<(), DBError> {
+    conn.execute_batch(
+        "
+CREATE TABLE IF NOT EXISTS RecipeDetails (
+  id            INTEGER PRIMARY KEY AUTOINCREMENT,
+  image         TEXT NOT NULL,
+  url           TEXT NOT NULL,
+  servings      INTEGER NOT NULL,
+  time          INTEGER NOT NULL,
+  calories      INTEGER NOT NULL,
+  source        TEXT,
+  title         TEXT NOT NULL,
+  ingredients   TEXT,
+  instructions  TEXT,
+  date_added    DATETIME DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE TABLE IF NOT EXISTS Tag (
+  id            INTEGER PRIMARY KEY AUTOINCREMENT,
+  name          TEXT NOT NULL UNIQUE COLLATE NOCASE
+);
+
+CREATE TABLE IF NOT EXISTS RecipeTag (
+  recipe_id     INTEGER NOT NULL REFERENCES RecipeDetails(id) ON DELETE CASCADE,
+  tag_id        INTEGER NOT NULL REFERENCES Tag(id) ON DELETE CASCADE,
+  PRIMARY KEY (recipe_id, tag_id)
+);
+
+CREATE TABLE IF NOT EXISTS Collection (
+  id            INTEGER PRIMARY KEY AUTOINCREMENT,
+  name          TEXT NOT NULL,
+  cover_image   TEXT,
+  position      INTEGER NOT NULL,
+  date_added    DATETIME DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE TABLE IF NOT EXISTS CollectionRecipe (
+  collection_id INTEGER NOT NULL REFERENCES Collection(id) ON DELETE CASCADE,
+  recipe_id     INTEGER NOT NULL REFERENCES RecipeDetails(id) ON DELETE CASCADE,
+  position      INTEGER NOT NULL,
+  PRIMARY KEY (collection_id, recipe_id)
+);
+    ",
+    )?;
+    Ok(())
+}
+
+fn migrate(conn: &mut Connection) -> Result<(), DBError> {
+    let version: usize = conn.query_row("PRAGMA user_version", [], |row| row.get(0))?;
+    if version >= MIGRATIONS.len() {
+        return Ok(());
+    }
+
+    // Rebuilding a table means dropping the original, which would cascade to the rows
+    // referencing it, so foreign keys are only checked once all migrations have run. The
+    // pragma has no effect inside a transaction.
+    conn.pragma_update(None, "foreign_keys", false)?;
+    let result = (|| -> Result<(), DBError> {
+        let tx = conn.transaction()?;
+        for (index, migration) in MIGRATIONS.iter().enumerate().skip(version) {
+            tx.execute_batch(migration)?;
+            tx.pragma_update(None, "user_version", index + 1)?;
+        }
+        let violation: Option<String> = tx
+            .query_row("PRAGMA foreign_key_check", [], |row| row.get(0))
+            .optional()?;
+        if let Some(table) = violation {
+            return Err(DBError::Validation(format!(
+                "migration left rows in {table} with a dangling reference"
+            )));
+        }
+        tx.commit()?;
+        Ok(())
+    })();
+    conn.pragma_update(None, "foreign_keys", true)?;
+
+    result
+}
+
+/// Fills in the canonical URL of recipes saved before it was recorded. The normalization
+/// lives in Rust, so it can't be part of a migration.
+fn backfill_canonical_urls(conn: &mut Connection) -> Result<(), DBError> {
+    let tx = conn.transaction()?;
+
+    let recipes = {
+        let mut stmt =
+            tx.prepare("SELECT id, url FROM RecipeDetails WHERE canonical_url IS NULL")?;
+        let rows = stmt.query_map([], |row| {
+            Ok((row.get::<_, i32>(0)?, row.get::<_, Option<String>>(1)?))
+        })?;
+        rows.collect::<rusqlite::Result<Vec<_>>>()?
+    };
+    for (id, url) in recipes {
+        tx.execute(
+            "UPDATE RecipeDetails SET canonical_url = ?1 WHERE id = ?2",
+            params![canonicalize_url(url.as_deref().unwrap_or_default()), id],
+        )?;
+    }
+
+    tx.commit()?;
+    Ok(())
+}
+
+/// Returns the images recipes and collection covers point to.
+pub fn image_paths(conn: &Connection) -> Result<Vec<String>, DBError> {
+    let mut stmt = conn.prepare(
+        "SELECT image FROM RecipeDetails UNION SELECT cover_image FROM Collection WHERE cover_image IS NOT NULL",
+    )?;
+    let paths = stmt
+        .query_map([], |row| row.get(0))?
+        .collect::<rusqlite::Result<Vec<String>>>()?;
+    Ok(paths)
+}
+
+/// Points recipes, their revisions and collection covers using the image at `from` to `to`.
+/// The variants of the image are left to be made again by `backfill_image_variants`.
+pub fn move_image_path(conn: &Connection, from: &str, to: &str) -> Result<(), DBError> {
+    conn.execute(
+        "UPDATE RecipeDetails SET image = ?2, image_thumbnail = NULL, image_medium = NULL WHERE image = ?1",
+        params![from, to],
+    )?;
+    conn.execute(
+        "UPDATE RecipeRevision
+         SET snapshot = json_set(snapshot, '$.image', ?2, '$.image_thumbnail', NULL, '$.image_medium', NULL)
+         WHERE json_extract(snapshot, '$.image') = ?1",
+        params![from, to],
+    )?;
+    conn.execute(
+        "UPDATE Collection SET cover_image = ?2 WHERE cover_image = ?1",
+        params![from, to],
+    )?;
+    Ok(())
+}
+
+/// Numbers the recipes of every collection `recipe_id` is in from 0 again, keeping their order,
+/// so no two recipes of a collection share a position.
+fn renumber_collections_of(conn: &Connection, recipe_id: &i32) -> Result<(), DBError> {
+    let rows: Vec<(i32, i32)> = query_all(
+        conn,
+        "SELECT collection_id, recipe_id FROM CollectionRecipe
+         WHERE collection_id IN (SELECT collection_id FROM CollectionRecipe WHERE recipe_id = ?1)
+         ORDER BY collection_id, position, recipe_id",
+        params![recipe_id],
+        |row| Ok((row.get(0)?, row.get(1)?)),
+    )?;
+
+    let mut position = 0;
+    let mut previous_collection = None;
+    for (collection_id, recipe_id) in rows {
+        if previous_collection != Some(collection_id) {
+            previous_collection = Some(collection_id);
+            position = 0;
+        }
+        conn.execute(
+            "UPDATE CollectionRecipe SET position = ?1 WHERE collection_id = ?2 AND recipe_id = ?3",
+            params![position, collection_id, recipe_id],
+        )?;
+        position += 1;
+    }
+    Ok(())
+}
+
+/// Runs a query and collects all of its rows.
+fn query_all<T, P: Params, F: FnMut(&Row) -> rusqlite::Result<T>>(
+    conn: &Connection,
+    sql: &str,
+    params: P,
+    f: F,
+) -> rusqlite::Result<Vec<T>> {
+    let mut stmt = conn.prepare_cached(sql)?;
+    let rows = stmt.query_map(params, f)?;
+    rows.collect()
+}
+
+fn recipe_from_row(row: &Row) -> rusqlite::Result<RecipeDetails> {
+    let ingredients: Option<String> = row.get(8)?;
+    let ingredients = string_to_vec(&ingredients).map_err(|_| rusqlite::Error::InvalidQuery)?;
+    let instructions: Option<String> = row.get(9)?;
+    let instructions = string_to_vec(&instructions).map_err(|_| rusqlite::Error::InvalidQuery)?;
+    Ok(RecipeDetails {
+        id: row.get(0)?,
+        image: row.get(1)?,
+        url: row.get(2)?,
+        servings: row.get(3)?,
+        time: row.get(4)?,
+        calories: row.get(5)?,
+        source: row.get(6)?,
+        title: row.get(7)?,
+        ingredients,
+        instructions,
+        date_added: row.get(10)?,
+        deleted_at: row.get(11)?,
+        version: row.get(12)?,
+        updated_at: row.get(13)?,
+        canonical_url: row.get(14)?,
+        image_thumbnail: row.get(15)?,
+        image_medium: row.get(16)?,
+    })
+}
+
+/// Position of the last recipe of a page in `list_recipes`, handed to the frontend as an opaque
+/// string.
+#[derive(Serialize, Deserialize)]
+struct RecipeCursor {
+    sort: RecipeSort,
+    value: serde_json::Value,
+    id: i32,
+}
+
+impl RecipeCursor {
+    fn new(sort: RecipeSort, sort_value: &Value, id: i32) -> Self {
+        let value = match sort_value {
+            Value::Integer(integer) => serde_json::Value::from(*integer),
+            Value::Real(real) => serde_json::Value::from(*real),
+            Value::Text(text) => serde_json::Value::from(text.as_str()),
+            Value::Null | Value::Blob(_) => serde_json::Value::Null,
+        };
+        RecipeCursor { sort, value, id }
+    }
+
+    fn encode(&self) -> Result<String, DBError> {
+        Ok(serde_json::to_string(self)?)
+    }
+
+    fn decode(cursor: &str) -> Result<Self, DBError> {
+        serde_json::from_str(cursor)
+            .map_err(|_| DBError::Validation("invalid pagination cursor".to_owned()))
+    }
+
+    fn sql_value(&self) -> Value {
+        match &self.value {
+            serde_json::Value::Number(number) => match number.as_i64() {
+                Some(integer) => Value::Integer(integer),
+                None => Value::Real(number.as_f64().unwrap_or_default()),
+            },
+            serde_json::Value::String(text) => Value::Text(text.clone()),
+            _ => Value::Null,
+        }
+    }
+}
+
+fn does_recipe_exist_by_id(conn: &Connection, id: &i32) -> rusqlite::Result<bool> {
+    conn.query_row(
+        "SELECT EXISTS(SELECT 1 FROM RecipeDetails WHERE id = ?1 AND deleted_at IS NULL)",
+        params![id],
+        |row| row.get(0),
+    )
+}
+
+/// Inserts a recipe as a new row and returns its id. `date_added` is kept when set, for
+/// recipes brought over from elsewhere.
+fn insert_recipe(tx: &Connection, recipe: &RecipeDetails) -> Result<i32, DBError> {
+    tx.execute(
+        "INSERT INTO RecipeDetails (image, url, servings, time, calories, source, title, ingredients, instructions, date_added, canonical_url, image_thumbnail, image_medium) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, COALESCE(?10, CURRENT_TIMESTAMP), ?11, ?12, ?13)",
+        params![&recipe.image, &recipe.url, recipe.servings, recipe.time, recipe.calories, &recipe.source, &recipe.title, vec_to_string(&recipe.ingredients)?, vec_to_string(&recipe.instructions)?, &recipe.date_added, canonical_url_of(recipe), existing_variant(&recipe.image, ImageVariant::Thumbnail), existing_variant(&recipe.image, ImageVariant::Medium)],
+    )?;
+    Ok(tx.last_insert_rowid() as i32)
+}
+
+/// The normalized URL a recipe is saved under, taken from the page's own canonical URL when
+/// it has one.
+fn canonical_url_of(recipe: &RecipeDetails) -> String {
+    canonicalize_url(
+        recipe
+            .canonical_url
+            .as_deref()
+            .or(recipe.url.as_deref())
+            .unwrap_or_default(),
+    )
+}
+
+/// Returns the id of a live recipe, other than `exclude`, saved under the canonical URL of
+/// `recipe` or of its page URL.
+fn find_duplicate(
+    conn: &Connection,
+    recipe: &RecipeDetails,
+    exclude: Option<&i32>,
+) -> rusqlite::Result<Option<i32>> {
+    conn.query_row(
+        "SELECT id FROM RecipeDetails
+         WHERE canonical_url IN (?1, ?2) AND canonical_url != '' AND deleted_at IS NULL AND id IS NOT ?3
+         ORDER BY id LIMIT 1",
+        params![
+            canonical_url_of(recipe),
+            canonicalize_url(recipe.url.as_deref().unwrap_or_default()),
+            exclude
+        ],
+        |row| row.get(0),
+    )
+    .optional()
+}
+
+/// Returns the id of a live recipe with the same title and ingredients as `recipe`, when
+/// neither has a URL to tell them apart by.
+fn find_same_recipe_without_url(
+    conn: &Connection,
+    recipe: &RecipeDetails,
+) -> Result<Option<i32>, DBError> {
+    if !canonical_url_of(recipe).is_empty() {
+        return Ok(None);
+    }
+    Ok(conn
+        .query_row(
+            "SELECT id FROM RecipeDetails
+             WHERE canonical_url = '' AND title = ?1 COLLATE NOCASE AND ingredients IS ?2
+               AND deleted_at IS NULL
+             ORDER BY id LIMIT 1",
+            params![recipe.title.trim(), vec_to_string(&recipe.ingredients)?],
+            |row| row.get(0),
+        )
+        .optional()?)
+}
+
+fn live_recipe_by_id(conn: &Connection, id: &i32) -> rusqlite::Result<Option<RecipeDetails>> {
+    let mut stmt =
+        conn.prepare_cached("SELECT * FROM RecipeDetails WHERE id = ?1 AND deleted_at IS NULL")?;
+    let mut rows = stmt.query_map(params![id], recipe_from_row)?;
+    rows.next().transpose()
+}
+
+/// Writes an update of a recipe within `tx`, see `Database::update_recipe`.
+fn update_recipe(tx: &Connection, recipe: &RecipeDetails) -> Result<(), DBError> {
+    let id = match &recipe.id {
+        Some(id) => id,
+        None => return Err(DBError::MissingID),
+    };
+    let version = match &recipe.version {
+        Some(version) => version,
+        None => return Err(DBError::MissingVersion),
+    };
+
+    let ingredients_str = vec_to_string(&recipe.ingredients)?;
+    let instructions_str = vec_to_string(&recipe.instructions)?;
+
+    let current = live_recipe_by_id(tx, id)?.ok_or(DBError::NoRecord)?;
+    if current.version.as_ref() != Some(version) {
+        return Err(DBError::Conflict(Box::new(current)));
+    }
+
+    // The stored canonical URL came from the page itself, so it's only replaced when the
+    // recipe is pointed at another page
+    let canonical_url = match recipe.url == current.url {
+        true => current.canonical_url,
+        false => {
+            let recipe = RecipeDetails {
+                canonical_url: None,
+                ..recipe.clone()
+            };
+            if let Some(existing_id) = find_duplicate(tx, &recipe, Some(id))? {
+                return Err(DBError::Duplicate(existing_id));
+            }
+            Some(canonical_url_of(&recipe))
+        }
+    };
+
+    // Recipes saved before revisions existed get their original state recorded first, so
+    // the update can still be reverted
+    let has_revisions: bool = tx.query_row(
+        "SELECT EXISTS(SELECT 1 FROM RecipeRevision WHERE recipe_id = ?1)",
+        params![&id],
+        |row| row.get(0),
+    )?;
+    if !has_revisions {
+        record_revision(tx, id)?;
+    }
+
+    // The version check is repeated in the update itself in case another connection
+    // committed in between
+    let updated = tx.execute(
+    "UPDATE RecipeDetails SET image = ?1, url = ?2, servings = ?3, time = ?4, calories = ?5, source = ?6, title = ?7, ingredients = ?8, instructions = ?9, date_added = ?10, canonical_url = ?11, image_thumbnail = ?12, image_medium = ?13, version = version + 1, updated_at = CURRENT_TIMESTAMP WHERE id = ?14 AND version = ?15",
+    params![&recipe.image, &recipe.url, &recipe.servings, &recipe.time, &recipe.calories, &recipe.source, &recipe.title, &ingredients_str, &instructions_str, &recipe.date_added, &canonical_url, existing_variant(&recipe.image, ImageVariant::Thumbnail), existing_variant(&recipe.image, ImageVariant::Medium), &id, &version],
+)?;
+    if updated == 0 {
+        return match live_recipe_by_id(tx, id)? {
+            Some(current) => Err(DBError::Conflict(Box::new(current))),
+            None => Err(DBError::NoRecord),
+        };
+    }
+    record_revision(tx, id)?;
+
+    Ok(())
+}
+
+/// Whether a recipe, trashed ones included, a revision of one or a collection cover uses the
+/// image at `path`.
+fn is_image_used(conn: &Connection, path: &str) -> rusqlite::Result<bool> {
+    conn.query_row(
+        "SELECT EXISTS(SELECT 1 FROM RecipeDetails WHERE image = ?1)
+           OR EXISTS(SELECT 1 FROM Collection WHERE cover_image = ?1)
+           OR EXISTS(SELECT 1 FROM RecipeRevision WHERE json_extract(snapshot, '$.image') = ?1)",
+        params![path],
+        |row| row.get(0),
+    )
+}
+
+/// Permanently deletes the trashed recipes matching `condition` within `tx`, returning the
+/// images nothing uses any more: no recipe, revision or collection cover.
+fn purge_recipes<P: Params>(
+    tx: &Connection,
+    condition: &str,
+    params: P,
+) -> Result<Vec<String>, DBError> {
+    let trashed = {
+        let mut stmt = tx.prepare(&format!(
+            "SELECT id, image FROM RecipeDetails WHERE deleted_at IS NOT NULL AND {condition}"
+        ))?;
+        let rows = stmt
+            .query_map(params, |row| {
+                Ok((row.get::<_, i32>(0)?, row.get::<_, String>(1)?))
+            })?
+            .collect::<Result<Vec<_>, _>>()?;
+        rows
+    };
+
+    for (id, _) in &trashed {
+        tx.execute("DELETE FROM RecipeTag WHERE recipe_id = ?1", params![id])?;
+        tx.execute("DELETE FROM CookLog WHERE recipe_id = ?1", params![id])?;
+        tx.execute(
+            "DELETE FROM RecipeRevision WHERE recipe_id = ?1",
+            params![id],
+        )?;
+        tx.execute(
+            "DELETE FROM CollectionRecipe WHERE recipe_id = ?1",
+            params![id],
+        )?;
+        tx.execute("DELETE FROM RecipeDetails WHERE id = ?1", params![id])?;
+    }
+
+    let mut unused_images = Vec::new();
+    for (_, image) in trashed {
+        if !image.is_empty() && !unused_images.contains(&image) && !is_image_used(tx, &image)? {
+            unused_images.push(image);
+        }
+    }
+
+    Ok(unused_images)
+}
+
+fn revision_from_row(row: &Row) -> rusqlite::Result<RecipeRevision> {
+    let snapshot: String = row.get(2)?;
+    let recipe = serde_json::from_str(&snapshot).map_err(|_| rusqlite::Error::InvalidQuery)?;
+    Ok(RecipeRevision {
+        id: row.get(0)?,
+        recipe_id: row.get(1)?,
+        recipe,
+        created_at: row.get(3)?,
+    })
+}
+
+/// Appends the current state of a recipe to its revision history.
+fn record_revision(conn: &Connection, recipe_id: &i32) -> Result<(), DBError> {
+    let recipe = conn.query_row(
+        "SELECT * FROM RecipeDetails WHERE id = ?1",
+        params![recipe_id],
+        recipe_from_row,
+    )?;
+    conn.execute(
+        "INSERT INTO RecipeRevision (recipe_id, snapshot) VALUES (?1, ?2)",
+        params![recipe_id, serde_json::to_string(&recipe)?],
+    )?;
+    Ok(())
+}
+
+/// Tags a recipe with its Edamam labels.
+fn add_label_tags(tx: &Connection, recipe_id: &i32, labels: &RecipeLabels) -> rusqlite::Result<()> {
+    for (name, category) in labels.tags() {
+        let tag_id = upsert_tag(tx, name, Some(category))?;
+        tx.execute(
+            "INSERT OR IGNORE INTO RecipeTag (recipe_id, tag_id, origin) VALUES (?1, ?2, ?3)",
+            params![recipe_id, tag_id, TagOrigin::Edamam],
+        )?;
+    }
+    Ok(())
+}
+
+fn tag_from_row(row: &Row) -> rusqlite::Result<Tag> {
+    Ok(Tag {
+        id: row.get(0)?,
+        name: row.get(1)?,
+        category: row.get(2)?,
+    })
+}
+
+/// Returns the id of the tag called `name`, creating it if needed. A category is only filled
+/// in when the tag does not have one yet, so an existing user tag keeps its name but becomes
+/// filterable once Edamam applies the same label.
+fn upsert_tag(
+    conn: &Connection,
+    name: &str,
+    category: Option<TagCategory>,
+) -> rusqlite::Result<i32> {
+    conn.execute(
+        "INSERT OR IGNORE INTO Tag (name, category) VALUES (?1, ?2)",
+        params![name, category],
+    )?;
+    conn.execute(
+        "UPDATE Tag SET category = COALESCE(category, ?2) WHERE name = ?1",
+        params![name, category],
+    )?;
+    conn.query_row("SELECT id FROM Tag WHERE name = ?1", params![name], |row| {
+        row.get(0)
+    })
+}
+
+fn collection_from_row(row: &Row) -> rusqlite::Result<Collection> {
+    Ok(Collection {
+        id: row.get(0)?,
+        name: row.get(1)?,
+        cover_image: row.get(2)?,
+        position: row.get(3)?,
+        recipe_count: row.get(4)?,
+        date_added: row.get(5)?,
+    })
+}
+
+fn cook_stats_from_row(row: &Row) -> rusqlite::Result<CookStats> {
+    Ok(CookStats {
+        recipe_id: row.get(0)?,
+        times_cooked: row.get(1)?,
+        last_cooked: row.get(2)?,
+        average_rating: row.get(3)?,
+    })
+}
+
+/// Checks the rating and servings of a cook log entry and returns its `cooked_on` date
+/// normalized by SQLite, or `None` when the entry has no date.
+fn validate_cook_log_entry(
+    conn: &Connection,
+    entry: &CookLogEntry,
+) -> Result<Option<String>, DBError> {
+    if let Some(rating) = entry.rating {
+        if !(1..=5).contains(&rating) {
+            return Err(DBError::Validation(format!(
+                "rating must be between 1 and 5, got {rating}"
+            )));
+        }
+    }
+
+    if let Some(servings_made) = entry.servings_made {
+        if servings_made < 1 {
+            return Err(DBError::Validation(format!(
+                "servings made must be at least 1, got {servings_made}"
+            )));
+        }
+    }
+
+    match &entry.cooked_on {
+        Some(cooked_on) => {
+            let date: Option<String> =
+                conn.query_row("SELECT date(?1)", params![cooked_on], |row| row.get(0))?;
+            match date {
+                Some(date) => Ok(Some(date)),
+                None => Err(DBError::Validation(format!(
+                    "{cooked_on} is not a valid date"
+                ))),
+            }
+        }
+        None => Ok(None),
+    }
+}
+
+const IMPORT_JOB_COLUMNS: &str =
+    "id, url, status, attempts, recipe_id, error, created_at, updated_at";
+
+fn stored_image_from_row(row: &Row) -> rusqlite::Result<StoredImage> {
+    Ok(StoredImage {
+        hash: row.get(0)?,
+        path: row.get(1)?,
+        size: row.get(2)?,
+        ref_count: row.get(3)?,
+    })
+}
+
+fn import_job_from_row(row: &Row) -> rusqlite::Result<ImportJob> {
+    Ok(ImportJob {
+        id: row.get(0)?,
+        url: row.get(1)?,
+        status: row.get(2)?,
+        attempts: row.get(3)?,
+        recipe_id: row.get(4)?,
+        error: row.get(5)?,
+        created_at: row.get(6)?,
+        updated_at: row.get(7)?,
+    })
+}
+
+fn import_job_by_id(conn: &Connection, id: &i32) -> rusqlite::Result<ImportJob> {
+    conn.query_row(
+        &format!("SELECT {IMPORT_JOB_COLUMNS} FROM ImportJob WHERE id = ?1"),
+        params![id],
+        import_job_from_row,
+    )
+}
+
+/// Inserts an imported recipe along with its `tags`, failing with `DBError::Duplicate` if it is
+/// already saved.
+fn import_into(tx: &Connection, recipe: &RecipeDetails, tags: &[String]) -> Result<i32, DBError> {
+    if let Some(existing_id) = find_duplicate(tx, recipe, None)? {
+        return Err(DBError::Duplicate(existing_id));
+    }
+    let id = insert_recipe(tx, recipe)?;
+    record_revision(tx, &id)?;
+
+    for name in tags {
+        let name = validate_name(name)?;
+        let tag_id = upsert_tag(tx, name, None)?;
+        tx.execute(
+            "INSERT OR IGNORE INTO RecipeTag (recipe_id, tag_id, origin) VALUES (?1, ?2, ?3)",
+            params![id, tag_id, TagOrigin::User],
+        )?;
+    }
+    Ok(id)
+}
+
+fn validate_name(name: &str) -> Result<&str, DBError> {
+    match name.trim() {
+        "" => Err(DBError::Validation("name cannot be empty".to_owned())),
+        name => Ok(name),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn recipe(title: &str, url: &str) -> RecipeDetails {
+        RecipeDetails {
+            title: title.to_string(),
+            url: Some(url.to_string()),
+            servings: 4,
+            ingredients: Some(vec!["1 cup flour".to_string()]),
+            instructions: Some(vec!["Mix.".to_string()]),
+            ..Default::default()
+        }
+    }
+
+    fn add(db: &Database, title: &str) -> i32 {
+        let url = format!(
+            "https://example.com/{}",
+            title.to_lowercase().replace(' ', "-")
+        );
+        db.add_recipe(&recipe(title, &url), None, None).unwrap()
+    }
+
+    fn titles(recipes: Vec<RecipeDetails>) -> Vec<String> {
+        let mut titles: Vec<String> = recipes.into_iter().map(|recipe| recipe.title).collect();
+        titles.sort();
+        titles
+    }
+
+    #[test]
+    fn filters_recipes_by_any_or_all_tags() {
+        let db = Database::in_memory().unwrap();
+        let soup = add(&db, "Soup");
+        let stew = add(&db, "Stew");
+        add(&db, "Salad");
+
+        let winter = db.add_tag_to_recipe(&soup, "Winter").unwrap();
+        // Tags are matched by name whatever their case
+        assert_eq!(db.add_tag_to_recipe(&stew, "winter").unwrap(), winter);
+        let quick = db.add_tag_to_recipe(&soup, "Quick").unwrap();
+        assert!(matches!(
+            db.add_tag_to_recipe(&soup, "  "),
+            Err(DBError::Validation(_))
+        ));
+
+        let any = db
+            .get_recipes_by_tags(&[winter.id, quick.id], false)
+            .unwrap();
+        assert_eq!(titles(any), vec!["Soup", "Stew"]);
+        let all = db
+            .get_recipes_by_tags(&[winter.id, quick.id], true)
+            .unwrap();
+        assert_eq!(titles(all), vec!["Soup"]);
+
+        db.delete_tag(&quick.id).unwrap();
+        let tags = db.get_tags_for_recipe(&soup).unwrap();
+        assert_eq!(tags.len(), 1);
+        assert_eq!(tags[0].name, "Winter");
+    }
+
+    #[test]
+    fn keeps_collections_in_order() {
+        let db = Database::in_memory().unwrap();
+        let soup = add(&db, "Soup");
+        let stew = add(&db, "Stew");
+        let salad = add(&db, "Salad");
+
+        let dinners = db.create_collection("Dinners", None).unwrap();
+        let lunches = db.create_collection("Lunches", None).unwrap();
+        assert_eq!((dinners.position, lunches.position), (0, 1));
+
+        for id in [soup, stew, salad, soup] {
+            db.add_recipe_to_collection(&dinners.id, &id).unwrap();
+        }
+        let in_dinners = |db: &Database| -> Vec<String> {
+            db.get_collection_recipes(&dinners.id)
+                .unwrap()
+                .into_iter()
+                .map(|recipe| recipe.title)
+                .collect()
+        };
+        assert_eq!(in_dinners(&db), vec!["Soup", "Stew", "Salad"]);
+
+        db.reorder_collection_recipes(&dinners.id, &[salad, soup, stew])
+            .unwrap();
+        assert_eq!(in_dinners(&db), vec!["Salad", "Soup", "Stew"]);
+
+        db.reorder_collections(&[lunches.id, dinners.id]).unwrap();
+        let names: Vec<String> = db
+            .get_all_collections()
+            .unwrap()
+            .into_iter()
+            .map(|collection| collection.name)
+            .collect();
+        assert_eq!(names, vec!["Lunches", "Dinners"]);
+
+        assert!(matches!(
+            db.add_recipe_to_collection(&dinners.id, &999),
+            Err(DBError::NoRecord)
+        ));
+    }
+
+    #[test]
+    fn tags_recipes_with_their_edamam_labels() {
+        let db = Database::in_memory().unwrap();
+        let curry = db
+            .add_recipe(
+                &recipe("Curry", "https://example.com/curry"),
+                None,
+                Some(&RecipeLabels {
+                    cuisine_type: vec!["indian".to_string(), " ".to_string()],
+                    meal_type: vec!["lunch/dinner".to_string()],
+                    ..Default::default()
+                }),
+            )
+            .unwrap();
+        let pasta = add(&db, "Pasta");
+        // A tag the user made already gets the category once Edamam applies it
+        db.add_tag_to_recipe(&pasta, "Italian").unwrap();
+        db.add_recipe(
+            &recipe("Pizza", "https://example.com/pizza"),
+            None,
+            Some(&RecipeLabels {
+                cuisine_type: vec!["italian".to_string()],
+                ..Default::default()
+            }),
+        )
+        .unwrap();
+
+        let tags = db.get_tags_for_recipe(&curry).unwrap();
+        assert_eq!(tags.len(), 2);
+        assert!(tags.iter().all(|tag| tag.origin == TagOrigin::Edamam));
+
+        let cuisines: Vec<String> = db
+            .get_tags_by_category(&TagCategory::Cuisine)
+            .unwrap()
+            .into_iter()
+            .map(|tag| tag.name)
+            .collect();
+        assert_eq!(cuisines, vec!["indian", "Italian"]);
+
+        let italian = db
+            .get_recipes_by_labels(&["Italian".to_string()], &[])
+            .unwrap();
+        assert_eq!(titles(italian), vec!["Pasta", "Pizza"]);
+        let indian_lunch = db
+            .get_recipes_by_labels(&["indian".to_string()], &["lunch/dinner".to_string()])
+            .unwrap();
+        assert_eq!(titles(indian_lunch), vec!["Curry"]);
+    }
+
+    #[test]
+    fn logs_cooks_and_sums_them_up() {
+        let db = Database::in_memory().unwrap();
+        let soup = add(&db, "Soup");
+        let cook = |cooked_on: &str, rating: Option<i32>| CookLogEntry {
+            recipe_id: soup,
+            cooked_on: Some(cooked_on.to_string()),
+            rating,
+            ..Default::default()
+        };
+
+        db.add_cook_log_entry(&cook("2023-01-05", Some(4))).unwrap();
+        let second = db.add_cook_log_entry(&cook("2023-02-10", None)).unwrap();
+        db.add_cook_log_entry(&cook("2023-03-01 18:30", Some(5)))
+            .unwrap();
+        assert!(matches!(
+            db.add_cook_log_entry(&cook("2023-04-01", Some(6))),
+            Err(DBError::Validation(_))
+        ));
+        assert!(matches!(
+            db.add_cook_log_entry(&cook("yesterday", None)),
+            Err(DBError::Validation(_))
+        ));
+        assert!(matches!(
+            db.add_cook_log_entry(&CookLogEntry {
+                recipe_id: 999,
+                ..Default::default()
+            }),
+            Err(DBError::NoRecord)
+        ));
+
+        let dates: Vec<Option<String>> = db
+            .get_cook_log(&soup)
+            .unwrap()
+            .into_iter()
+            .map(|entry| entry.cooked_on)
+            .collect();
+        assert_eq!(
+            dates,
+            vec![
+                Some("2023-03-01".to_string()),
+                Some("2023-02-10".to_string()),
+                Some("2023-01-05".to_string()),
+            ]
+        );
+
+        let stats = db.get_cook_stats(&soup).unwrap();
+        assert_eq!(stats.times_cooked, 3);
+        assert_eq!(stats.last_cooked.as_deref(), Some("2023-03-01"));
+        assert_eq!(stats.average_rating, Some(4.5));
+
+        db.delete_cook_log_entry(&second).unwrap();
+        assert_eq!(db.get_cook_stats(&soup).unwrap().times_cooked, 2);
+    }
+
+    #[test]
+    fn purging_only_gives_back_images_nothing_else_uses() {
+        let db = Database::in_memory().unwrap();
+        let with_image = |title: &str, image: &str| {
+            let url = format!("https://example.com/{title}");
+            db.add_recipe(&recipe(title, &url), Some(image.to_string()), None)
+                .unwrap()
+        };
+        let soup = with_image("soup", "/images/soup.jpg");
+        let stew = with_image("stew", "/images/stew.jpg");
+        let salad = with_image("salad", "/images/salad.jpg");
+        with_image("salad-again", "/images/salad.jpg");
+        db.create_collection("Winter", Some("/images/stew.jpg".to_string()))
+            .unwrap();
+
+        for id in [soup, stew, salad] {
+            db.delete_recipe_by_id(&id).unwrap();
+        }
+        assert_eq!(db.empty_trash().unwrap(), vec!["/images/soup.jpg"]);
+    }
+
+    #[test]
+    fn trashes_restores_and_purges_recipes() {
+        let db = Database::in_memory().unwrap();
+        let soup = add(&db, "Soup");
+        let stew = add(&db, "Stew");
+        for id in [soup, stew] {
+            db.add_tag_to_recipe(&id, "Winter").unwrap();
+            db.add_cook_log_entry(&CookLogEntry {
+                recipe_id: id,
+                ..Default::default()
+            })
+            .unwrap();
+        }
+
+        db.delete_recipe_by_id(&soup).unwrap();
+        assert!(matches!(
+            db.delete_recipe_by_id(&soup),
+            Err(DBError::NoRecord)
+        ));
+        assert_eq!(titles(db.get_all_recipes().unwrap()), vec!["Stew"]);
+        assert_eq!(titles(db.get_trashed_recipes().unwrap()), vec!["Soup"]);
+        // Trashed recipes keep their tags so that they come back as they were
+        db.restore_recipe_by_id(&soup).unwrap();
+        assert_eq!(db.get_tags_for_recipe(&soup).unwrap().len(), 1);
+
+        db.delete_recipe_by_id(&soup).unwrap();
+        db.delete_recipe_by_id(&stew).unwrap();
+        let conn = db.pool.get().unwrap();
+        conn.execute(
+            "UPDATE RecipeDetails SET deleted_at = datetime('now', '-40 days') WHERE id = ?1",
+            params![soup],
+        )
+        .unwrap();
+        drop(conn);
+        db.purge_expired_trash(&30).unwrap();
+        assert_eq!(titles(db.get_trashed_recipes().unwrap()), vec!["Stew"]);
+
+        let conn = db.pool.get().unwrap();
+        let count = |table: &str| -> i32 {
+            conn.query_row(
+                &format!("SELECT COUNT(*) FROM {table} WHERE recipe_id = ?1"),
+                params![soup],
+                |row| row.get(0),
+            )
+            .unwrap()
+        };
+        assert_eq!(count("RecipeTag"), 0);
+        assert_eq!(count("CookLog"), 0);
+        assert_eq!(count("RecipeRevision"), 0);
+        drop(conn);
+        assert_eq!(db.get_cook_stats(&stew).unwrap().times_cooked, 1);
+        assert!(matches!(
+            db.restore_recipe_by_id(&soup),
+            Err(DBError::NoRecord)
+        ));
+    }
+
+    #[test]
+    fn rejects_updates_made_from_a_stale_copy() {
+        let db = Database::in_memory().unwrap();
+        let soup = add(&db, "Soup");
+        let read = db.get_recipe_by_id(&soup).unwrap().unwrap();
+        assert_eq!(read.version, Some(1));
+
+        db.update_recipe(&RecipeDetails {
+            servings: 6,
+            ..read.clone()
+        })
+        .unwrap();
+        match db.update_recipe(&RecipeDetails {
+            title: "Tomato Soup".to_string(),
+            ..read.clone()
+        }) {
+            Err(DBError::Conflict(current)) => {
+                assert_eq!(current.version, Some(2));
+                assert_eq!(current.servings, 6);
+                assert_eq!(current.title, "Soup");
+            }
+            other => panic!("expected a conflict, got {other:?}"),
+        }
+        assert!(matches!(
+            db.update_recipe(&RecipeDetails {
+                version: None,
+                ..read
+            }),
+            Err(DBError::MissingVersion)
+        ));
+
+        let saved = db.get_recipe_by_id(&soup).unwrap().unwrap();
+        assert_eq!((saved.title.as_str(), saved.servings), ("Soup", 6));
+        assert_eq!(db.get_recipe_revisions(&soup).unwrap().len(), 2);
+    }
+
+    #[test]
+    fn pages_through_every_recipe_once_across_ties() {
+        let db = Database::in_memory().unwrap();
+        // Recipes added within the same second share their date, and several their time
+        for (title, time) in [
+            ("Bread", 60),
+            ("apple pie", 60),
+            ("Curry", 30),
+            ("Apple Pie", 60),
+            ("Dal", 30),
+            ("Eggs", 5),
+            ("Fish", 30),
+        ] {
+            let url = format!("https://example.com/{}", title.replace(' ', "-"));
+            db.add_recipe(
+                &RecipeDetails {
+                    time,
+                    ..recipe(title, &url)
+                },
+                None,
+                None,
+            )
+            .unwrap();
+        }
+
+        for sort in [RecipeSort::DateAdded, RecipeSort::Title, RecipeSort::Time] {
+            for direction in [SortDirection::Asc, SortDirection::Desc] {
+                let mut query = RecipeListQuery {
+                    sort,
+                    direction,
+                    limit: Some(2),
+                    ..Default::default()
+                };
+                let mut listed = Vec::new();
+                loop {
+                    let page = db.list_recipes(&query).unwrap();
+                    assert!(page.recipes.len() <= 2);
+                    listed.extend(page.recipes);
+                    match page.next_cursor {
+                        Some(cursor) => query.cursor = Some(cursor),
+                        None => break,
+                    }
+                }
+
+                let mut ids: Vec<i32> = listed.iter().map(|recipe| recipe.id).collect();
+                ids.sort_unstable();
+                ids.dedup();
+                assert_eq!(ids.len(), 7, "{sort:?} {direction:?}");
+                assert_eq!(listed.len(), 7, "{sort:?} {direction:?}");
+
+                let key = |recipe: &RecipeSummary| match sort {
+                    RecipeSort::Title => (0, recipe.title.to_lowercase(), recipe.id),
+                    RecipeSort::Time => (recipe.time, String::new(), recipe.id),
+                    _ => (0, String::new(), recipe.id),
+                };
+                let in_order = listed.windows(2).all(|pair| match direction {
+                    SortDirection::Asc => key(&pair[0]) < key(&pair[1]),
+                    SortDirection::Desc => key(&pair[0]) > key(&pair[1]),
+                });
+                assert!(in_order, "{sort:?} {direction:?}");
+            }
+        }
+
+        let first_page = db
+            .list_recipes(&RecipeListQuery {
+                sort: RecipeSort::Time,
+                limit: Some(2),
+                ..Default::default()
+            })
+            .unwrap();
+        assert!(matches!(
+            db.list_recipes(&RecipeListQuery {
+                sort: RecipeSort::Title,
+                cursor: first_page.next_cursor,
+                ..Default::default()
+            }),
+            Err(DBError::Validation(_))
+        ));
+    }
+    #[test]
+    fn migrates_a_new_library_to_the_latest_schema() {
+        let db = Database::in_memory().unwrap();
+        let mut conn = db.pool.get().unwrap();
+        let version: usize = conn
+            .query_row("PRAGMA user_version", [], |row| row.get(0))
+            .unwrap();
+        assert_eq!(version, SCHEMA_VERSION);
+        // Preparing the schema again leaves the library as it is
+        prepare_schema(&mut conn).unwrap();
+    }
+
+    #[test]
+    fn restores_a_library_but_keeps_the_backups_of_this_device() {
+        let db = Database::in_memory().unwrap();
+        add(&db, "Soup");
+        db.update_settings(&Settings {
+            trash_retention_days: Some(30),
+            backup_dir: Some("/home/me/backups".to_string()),
+            backup_interval_hours: Some(24),
+            backup_generations: Some(5),
+        })
+        .unwrap();
+        db.record_backup().unwrap();
+
+        let other = Database::in_memory().unwrap();
+        add(&other, "Stew");
+        other
+            .update_settings(&Settings {
+                trash_retention_days: Some(7),
+                backup_dir: Some("/Users/them/backups".to_string()),
+                backup_interval_hours: Some(1),
+                backup_generations: None,
+            })
+            .unwrap();
+        let path = std::env::temp_dir().join(format!("{}.db", uuid::Uuid::new_v4()));
+        other.backup_to(&path).unwrap();
+
+        db.restore_from(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(titles(db.get_all_recipes().unwrap()), vec!["Stew"]);
+        let settings = db.get_settings().unwrap();
+        assert_eq!(settings.trash_retention_days, Some(7));
+        assert_eq!(settings.backup_dir.as_deref(), Some("/home/me/backups"));
+        assert_eq!(settings.backup_interval_hours, Some(24));
+        assert_eq!(settings.backup_generations, Some(5));
+        assert!(!db.is_backup_due(&24).unwrap());
+    }
+
+    #[test]
+    fn configures_connections_for_concurrent_use() {
+        let path = std::env::temp_dir().join(format!("{}.db", uuid::Uuid::new_v4()));
+        let mut conn = Connection::open(&path).unwrap();
+        configure_connection(&mut conn).unwrap();
+
+        let pragma = |name: &str| -> String {
+            conn.query_row(&format!("PRAGMA {name}"), [], |row| row.get::<_, Value>(0))
+                .map(|value| match value {
+                    Value::Integer(number) => number.to_string(),
+                    Value::Text(text) => text,
+                    other => format!("{other:?}"),
+                })
+                .unwrap()
+        };
+        assert_eq!(pragma("journal_mode"), "wal");
+        assert_eq!(pragma("foreign_keys"), "1");
+        assert_eq!(pragma("busy_timeout"), "5000");
+        // NORMAL
+        assert_eq!(pragma("synchronous"), "1");
+
+        drop(conn);
+        for suffix in ["", "-wal", "-shm"] {
+            let _ = std::fs::remove_file(format!("{}{suffix}", path.display()));
+        }
     }
 }
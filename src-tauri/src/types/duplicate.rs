@@ -0,0 +1,378 @@
+use serde::{Deserialize, Serialize};
+use std::{
+    cmp::Ordering,
+    collections::{BTreeSet, HashMap, HashSet},
+};
+use ts_rs::TS;
+
+use super::recipe::RecipeDetails;
+
+/// Words in ingredient lines that say how much of an ingredient to use or how to prepare it,
+/// rather than what it is.
+const INGREDIENT_STOPWORDS: &[&str] = &[
+    "and",
+    "or",
+    "of",
+    "the",
+    "for",
+    "to",
+    "taste",
+    "cup",
+    "cups",
+    "tbsp",
+    "tablespoon",
+    "tablespoons",
+    "tsp",
+    "teaspoon",
+    "teaspoons",
+    "ml",
+    "oz",
+    "ounce",
+    "ounces",
+    "lb",
+    "lbs",
+    "pound",
+    "pounds",
+    "gram",
+    "grams",
+    "kg",
+    "pinch",
+    "dash",
+    "can",
+    "cans",
+    "package",
+    "clove",
+    "cloves",
+    "large",
+    "medium",
+    "small",
+    "fresh",
+    "freshly",
+    "chopped",
+    "diced",
+    "minced",
+    "sliced",
+    "finely",
+    "roughly",
+    "ground",
+    "optional",
+    "divided",
+    "plus",
+    "more",
+];
+
+/// Words in titles that say little about the dish, so sharing one doesn't make two recipes
+/// worth comparing.
+const TITLE_STOPWORDS: &[&str] = &[
+    "the", "and", "with", "best", "easy", "quick", "simple", "homemade", "recipe", "classic",
+    "perfect",
+];
+
+/// Two saved recipes that are likely the same dish. `recipe_id` is the one saved first.
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize, TS)]
+#[ts(export)]
+pub struct DuplicateCandidate {
+    pub recipe_id: i32,
+    pub title: String,
+    pub duplicate_id: i32,
+    pub duplicate_title: String,
+    /// Dice coefficient of the character bigrams of the titles, from 0 to 1
+    pub title_similarity: f64,
+    /// Share of ingredient words the recipes have in common, from 0 to 1. `None` if either
+    /// recipe has no ingredients.
+    pub ingredient_overlap: Option<f64>,
+    /// Mean of `title_similarity` and `ingredient_overlap`, or the title similarity alone
+    pub score: f64,
+}
+
+/// The parts of a recipe compared when looking for duplicates, computed once per recipe.
+struct Fingerprint<'a> {
+    recipe: &'a RecipeDetails,
+    bigrams: HashSet<(char, char)>,
+    ingredients: HashSet<String>,
+}
+
+impl<'a> Fingerprint<'a> {
+    fn new(recipe: &'a RecipeDetails) -> Self {
+        let ingredients = recipe
+            .ingredients
+            .iter()
+            .flatten()
+            .flat_map(|line| words(line))
+            .filter(|word| {
+                word.len() > 2
+                    && word.chars().all(char::is_alphabetic)
+                    && !INGREDIENT_STOPWORDS.contains(&word.as_str())
+            })
+            .map(|word| match word.strip_suffix('s') {
+                Some(singular) if !singular.ends_with('s') => singular.to_string(),
+                _ => word,
+            })
+            .collect();
+
+        Fingerprint {
+            recipe,
+            bigrams: title_bigrams(&recipe.title),
+            ingredients,
+        }
+    }
+
+    /// Keys of the buckets the recipe is compared within: its canonical URL and the words of
+    /// its title.
+    fn bucket_keys(&self) -> HashSet<String> {
+        let mut keys: HashSet<String> = words(&self.recipe.title)
+            .into_iter()
+            .filter(|word| word.chars().count() > 2 && !TITLE_STOPWORDS.contains(&word.as_str()))
+            .map(|word| format!("title:{word}"))
+            .collect();
+        if let Some(canonical_url) = self
+            .recipe
+            .canonical_url
+            .as_ref()
+            .filter(|url| !url.is_empty())
+        {
+            keys.insert(format!("url:{canonical_url}"));
+        }
+        keys
+    }
+}
+
+/// Lowercase words of `text`, split on anything that isn't a letter or digit.
+fn words(text: &str) -> Vec<String> {
+    text.to_lowercase()
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|word| !word.is_empty())
+        .map(str::to_string)
+        .collect()
+}
+
+fn title_bigrams(title: &str) -> HashSet<(char, char)> {
+    words(title)
+        .iter()
+        .flat_map(|word| {
+            let chars: Vec<char> = word.chars().collect();
+            chars
+                .windows(2)
+                .map(|pair| (pair[0], pair[1]))
+                .collect::<Vec<_>>()
+        })
+        .collect()
+}
+
+fn dice<T: Eq + std::hash::Hash>(a: &HashSet<T>, b: &HashSet<T>) -> f64 {
+    match a.len() + b.len() {
+        0 => 0.0,
+        total => 2.0 * a.intersection(b).count() as f64 / total as f64,
+    }
+}
+
+fn jaccard<T: Eq + std::hash::Hash>(a: &HashSet<T>, b: &HashSet<T>) -> f64 {
+    match a.union(b).count() {
+        0 => 0.0,
+        total => a.intersection(b).count() as f64 / total as f64,
+    }
+}
+
+/// Compares the pairs of `recipes` sharing a canonical URL or a title word and returns those
+/// scoring at least `min_score`, best matches first. Recipes with nothing in common aren't
+/// compared, so a large library isn't gone through pair by pair.
+pub fn find_duplicate_candidates(
+    recipes: &[RecipeDetails],
+    min_score: f64,
+) -> Vec<DuplicateCandidate> {
+    let fingerprints: Vec<Fingerprint> = recipes.iter().map(Fingerprint::new).collect();
+
+    let mut buckets: HashMap<String, Vec<usize>> = HashMap::new();
+    for (index, fingerprint) in fingerprints.iter().enumerate() {
+        for key in fingerprint.bucket_keys() {
+            buckets.entry(key).or_default().push(index);
+        }
+    }
+    // Ordered so that candidates with the same score come out the same every time
+    let mut pairs = BTreeSet::new();
+    for indices in buckets.values() {
+        for (position, first) in indices.iter().enumerate() {
+            for second in &indices[position + 1..] {
+                pairs.insert((*first, *second));
+            }
+        }
+    }
+
+    let mut candidates = Vec::new();
+    for (first, second) in pairs {
+        let (first, second) = (&fingerprints[first], &fingerprints[second]);
+        let title_similarity = dice(&first.bigrams, &second.bigrams);
+        let ingredient_overlap = match first.ingredients.is_empty() || second.ingredients.is_empty()
+        {
+            true => None,
+            false => Some(jaccard(&first.ingredients, &second.ingredients)),
+        };
+        let score = match ingredient_overlap {
+            Some(overlap) => (title_similarity + overlap) / 2.0,
+            None => title_similarity,
+        };
+        if score < min_score {
+            continue;
+        }
+
+        let (recipe, duplicate) = match first.recipe.id <= second.recipe.id {
+            true => (first.recipe, second.recipe),
+            false => (second.recipe, first.recipe),
+        };
+        candidates.push(DuplicateCandidate {
+            recipe_id: recipe.id.unwrap_or_default(),
+            title: recipe.title.clone(),
+            duplicate_id: duplicate.id.unwrap_or_default(),
+            duplicate_title: duplicate.title.clone(),
+            title_similarity,
+            ingredient_overlap,
+            score,
+        });
+    }
+
+    candidates.sort_by(|a, b| b.score.partial_cmp(&a.score).unwrap_or(Ordering::Equal));
+    candidates
+}
+
+/// Fills in the fields `recipe` is missing with those of `other`, keeping the ones it has.
+pub fn fill_missing_fields(recipe: &RecipeDetails, other: &RecipeDetails) -> RecipeDetails {
+    let is_missing = |list: &Option<Vec<String>>| list.as_ref().map_or(true, Vec::is_empty);
+    let mut merged = recipe.clone();
+    if merged.url.is_none() {
+        merged.url = other.url.clone();
+        merged.canonical_url = other.canonical_url.clone();
+    }
+    if merged.image.is_empty() {
+        merged.image = other.image.clone();
+    }
+    if merged.servings == 0 {
+        merged.servings = other.servings;
+    }
+    if merged.time == 0 {
+        merged.time = other.time;
+    }
+    if merged.calories == 0 {
+        merged.calories = other.calories;
+    }
+    if merged.source.is_none() {
+        merged.source = other.source.clone();
+    }
+    if is_missing(&merged.ingredients) {
+        merged.ingredients = other.ingredients.clone();
+    }
+    if is_missing(&merged.instructions) {
+        merged.instructions = other.instructions.clone();
+    }
+    merged
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn recipe(id: i32, title: &str, ingredients: &[&str]) -> RecipeDetails {
+        RecipeDetails {
+            id: Some(id),
+            title: title.to_string(),
+            ingredients: Some(ingredients.iter().map(|line| line.to_string()).collect()),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn pairs_near_identical_titles_with_reordered_ingredients() {
+        let recipes = [
+            recipe(
+                1,
+                "Chocolate Chip Cookies",
+                &["2 cups flour", "1 cup butter", "1 cup chocolate chips"],
+            ),
+            recipe(
+                2,
+                "Chocolate-chip cookies!",
+                &["1 cup chocolate chips", "225 g butter", "2 cups flour"],
+            ),
+            recipe(3, "Banana Bread", &["3 bananas", "2 cups flour"]),
+        ];
+        let candidates = find_duplicate_candidates(&recipes, 0.5);
+        assert_eq!(candidates.len(), 1);
+
+        let candidate = &candidates[0];
+        assert_eq!((candidate.recipe_id, candidate.duplicate_id), (1, 2));
+        assert_eq!(candidate.title_similarity, 1.0);
+        assert_eq!(candidate.ingredient_overlap, Some(1.0));
+        assert_eq!(candidate.score, 1.0);
+    }
+
+    #[test]
+    fn scores_against_the_threshold() {
+        let recipes = [
+            recipe(1, "Tomato Soup", &["4 tomatoes", "1 onion"]),
+            recipe(2, "Tomato Basil Soup", &["4 tomatoes", "1 onion", "basil"]),
+        ];
+        let score = find_duplicate_candidates(&recipes, 0.0)[0].score;
+        assert!(score > 0.5 && score < 1.0, "{score}");
+        assert_eq!(find_duplicate_candidates(&recipes, score).len(), 1);
+        assert!(find_duplicate_candidates(&recipes, score + 0.01).is_empty());
+
+        // Without ingredients to compare, the title decides alone
+        let recipes = [recipe(1, "Tomato Soup", &[]), recipe(2, "Tomato Soup", &[])];
+        let candidates = find_duplicate_candidates(&recipes, 0.9);
+        assert_eq!(candidates[0].ingredient_overlap, None);
+        assert_eq!(candidates[0].score, 1.0);
+    }
+
+    #[test]
+    fn only_compares_recipes_with_something_in_common() {
+        let mut first = recipe(1, "Nonna's Ragù", &["500 g beef mince"]);
+        let mut second = recipe(2, "Sunday sauce", &["500 g beef mince"]);
+        // Sharing a title word or a canonical URL is what makes two recipes worth comparing
+        assert!(find_duplicate_candidates(&[first.clone(), second.clone()], 0.0).is_empty());
+
+        first.canonical_url = Some("https://example.com/ragu".to_string());
+        second.canonical_url = first.canonical_url.clone();
+        assert_eq!(find_duplicate_candidates(&[first, second], 0.0).len(), 1);
+
+        let easy = [
+            recipe(1, "Easy Pancakes", &[]),
+            recipe(2, "Easy Flatbread", &[]),
+        ];
+        assert!(find_duplicate_candidates(&easy, 0.0).is_empty());
+    }
+
+    #[test]
+    fn fills_only_the_fields_the_recipe_is_missing() {
+        let kept = RecipeDetails {
+            title: "Pancakes".to_string(),
+            servings: 4,
+            source: Some("Grandma".to_string()),
+            ingredients: Some(Vec::new()),
+            ..Default::default()
+        };
+        let other = RecipeDetails {
+            title: "Fluffy Pancakes".to_string(),
+            url: Some("https://example.com/pancakes".to_string()),
+            canonical_url: Some("https://example.com/pancakes".to_string()),
+            image: "https://example.com/pancakes.jpg".to_string(),
+            servings: 2,
+            time: 20,
+            source: Some("example.com".to_string()),
+            ingredients: Some(vec!["1 cup flour".to_string()]),
+            instructions: Some(vec!["Whisk.".to_string()]),
+            ..Default::default()
+        };
+
+        let merged = fill_missing_fields(&kept, &other);
+        assert_eq!(merged.title, "Pancakes");
+        assert_eq!(merged.servings, 4);
+        assert_eq!(merged.source.as_deref(), Some("Grandma"));
+        assert_eq!(merged.url, other.url);
+        assert_eq!(merged.canonical_url, other.canonical_url);
+        assert_eq!(merged.image, other.image);
+        assert_eq!(merged.time, 20);
+        assert_eq!(merged.ingredients, other.ingredients);
+        assert_eq!(merged.instructions, other.instructions);
+
+        assert_eq!(fill_missing_fields(&other, &kept), other);
+    }
+}
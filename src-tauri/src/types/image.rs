@@ -0,0 +1,38 @@
+use serde::{Deserialize, Serialize};
+use ts_rs::TS;
+
+/// A file in the images directory, named after the SHA-256 of its content so that the same
+/// photo is only stored once. `ref_count` is kept up to date by triggers on the recipes and
+/// collections using it.
+#[derive(Debug, Clone, PartialEq)]
+pub struct StoredImage {
+    /// Hex encoded SHA-256 of the file
+    pub hash: String,
+    pub path: String,
+    pub size: i64,
+    /// Number of recipes and collection covers using the image, trashed recipes included
+    pub ref_count: i32,
+}
+
+/// An image recipes or collection covers point to.
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize, TS)]
+#[ts(export)]
+pub struct ImageReference {
+    pub path: String,
+    pub recipe_ids: Vec<i32>,
+    pub collection_ids: Vec<i32>,
+}
+
+/// What `gc_images` cleaned up.
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize, TS)]
+#[ts(export)]
+pub struct ImageGcReport {
+    /// Images nothing uses any more, along with their variants
+    pub files_removed: u32,
+    pub bytes_reclaimed: u64,
+    /// Images saved before they were stored by content, now indexed
+    pub images_indexed: u32,
+    /// Local images recipes or collections point to that are gone from the disk. They are
+    /// left for the user to replace.
+    pub missing: Vec<ImageReference>,
+}
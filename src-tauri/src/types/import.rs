@@ -0,0 +1,179 @@
+use serde::{Deserialize, Serialize};
+use ts_rs::TS;
+
+use super::recipe::RecipeDetails;
+
+#[derive(Default, Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, TS)]
+#[serde(rename_all = "snake_case")]
+#[ts(export)]
+pub enum ImportStatus {
+    Imported,
+    /// Already in the library, `recipe_id` is the saved copy
+    Duplicate,
+    #[default]
+    Failed,
+}
+
+/// What happened to one recipe of an import.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, TS)]
+#[ts(export)]
+pub struct RecipeImportResult {
+    /// Title of the recipe, or where it was found in the file if it has none
+    pub title: String,
+    pub status: ImportStatus,
+    pub recipe_id: Option<i32>,
+    /// Data that couldn't be carried over, or why the recipe failed to import
+    pub messages: Vec<String>,
+}
+
+/// A recipe read from text, to be reviewed and then saved with `add_recipe`.
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize, TS)]
+#[ts(export)]
+pub struct RecipeDraft {
+    pub recipe: RecipeDetails,
+    /// Categories the text files the recipe under, to be added as tags
+    pub categories: Vec<String>,
+    /// Text that was left out or couldn't be understood
+    pub messages: Vec<String>,
+}
+
+/// A field of `RecipeDetails` a column of an imported table can fill.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, TS)]
+#[serde(rename_all = "snake_case")]
+#[ts(export)]
+pub enum RecipeField {
+    Title,
+    Url,
+    Image,
+    Source,
+    Servings,
+    Time,
+    Calories,
+    Ingredients,
+    Instructions,
+    /// Not a field of the recipe, the column's items are added as tags
+    Tags,
+    DateAdded,
+}
+
+/// How a column holding a list, such as the ingredients, is split into its items.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, TS)]
+#[serde(rename_all = "snake_case")]
+#[ts(export)]
+pub enum ListSplit {
+    Lines,
+    Semicolons,
+    Commas,
+    Pipes,
+    /// Before each step number, such as `1.` or `2)`
+    Numbered,
+}
+
+/// Maps a column of an imported table to the recipe field it fills.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, TS)]
+#[ts(export)]
+pub struct FieldMapping {
+    /// Header of the CSV column, or key of the JSON objects with `.` between nested keys
+    pub column: String,
+    pub field: RecipeField,
+    /// How to split lists, defaults to lines for ingredients and instructions and to commas
+    /// for tags. JSON arrays are already split.
+    pub split: Option<ListSplit>,
+}
+
+/// What happened, or would happen in a dry run, to one row of an imported table.
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize, TS)]
+#[ts(export)]
+pub struct TableRowResult {
+    /// Number of the row among the recipes, starting from 1
+    pub row: u32,
+    /// The recipe read from the row, for previewing
+    pub recipe: RecipeDetails,
+    pub tags: Vec<String>,
+    /// `imported` for rows a dry run would import
+    pub status: ImportStatus,
+    pub recipe_id: Option<i32>,
+    /// Why the row failed validation
+    pub errors: Vec<String>,
+}
+
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize, TS)]
+#[ts(export)]
+pub struct TableImportReport {
+    /// Whether the recipes were saved. Nothing is saved in a dry run, or when rows failed
+    /// validation unless they were to be skipped.
+    pub committed: bool,
+    pub rows: Vec<TableRowResult>,
+}
+
+/// A step of `import_recipe_from_url`, in the order they run.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, TS)]
+#[serde(rename_all = "snake_case")]
+#[ts(export)]
+pub enum UrlImportStep {
+    /// Loading the page and checking it has a recipe that can be scraped
+    Validate,
+    Scrape,
+    /// Reading the scraped data into a recipe
+    Map,
+    DownloadImage,
+    Save,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, TS)]
+#[serde(rename_all = "snake_case")]
+#[ts(export)]
+pub enum StepStatus {
+    Succeeded,
+    Failed,
+    /// Not run, because an earlier step failed or it wasn't needed
+    Skipped,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, TS)]
+#[ts(export)]
+pub struct StepOutcome {
+    pub step: UrlImportStep,
+    pub status: StepStatus,
+    /// Why the step failed or was skipped, or what it couldn't do
+    pub messages: Vec<String>,
+    /// Whether the step failed for a reason that may not last, such as the site being
+    /// unreachable, so that trying again later could get past it
+    pub retryable: bool,
+}
+
+/// What `import_recipe_from_url` did, step by step.
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize, TS)]
+#[ts(export)]
+pub struct UrlImportReport {
+    pub status: ImportStatus,
+    /// The saved recipe, or the one it duplicates
+    pub recipe_id: Option<i32>,
+    /// The recipe read from the page, once it has been
+    pub recipe: Option<RecipeDetails>,
+    pub steps: Vec<StepOutcome>,
+}
+
+/// A link found in a browser's bookmarks export that likely leads to a recipe.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, TS)]
+#[ts(export)]
+pub struct BookmarkLink {
+    pub url: String,
+    pub title: String,
+    /// Folders the bookmark is filed under, outermost first
+    pub folders: Vec<String>,
+    /// Whether recipe-scrapers has a scraper for the site. Other links made the list because
+    /// of the folder they're in or their title, and are read from their schema.org markup.
+    pub is_scrapable: bool,
+    /// The saved recipe, if the link is already in the library
+    pub recipe_id: Option<i32>,
+}
+
+/// The likely recipe links of a bookmarks export, to pick from and queue for import.
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize, TS)]
+#[ts(export)]
+pub struct BookmarkReport {
+    pub links: Vec<BookmarkLink>,
+    /// Number of web links left out as unlikely to be recipes
+    pub skipped: u32,
+}
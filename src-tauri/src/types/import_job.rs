@@ -0,0 +1,77 @@
+use rusqlite::types::{FromSql, FromSqlError, FromSqlResult, ToSql, ToSqlOutput, ValueRef};
+use serde::{Deserialize, Serialize};
+use ts_rs::TS;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, TS)]
+#[serde(rename_all = "snake_case")]
+#[ts(export)]
+pub enum ImportJobStatus {
+    /// Waiting for a worker, including between attempts
+    Queued,
+    Running,
+    Succeeded,
+    /// Already in the library, `recipe_id` is the saved copy
+    Duplicate,
+    /// Failed for good, after running out of attempts or with an error retrying won't fix
+    Failed,
+    Cancelled,
+}
+
+impl ImportJobStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ImportJobStatus::Queued => "queued",
+            ImportJobStatus::Running => "running",
+            ImportJobStatus::Succeeded => "succeeded",
+            ImportJobStatus::Duplicate => "duplicate",
+            ImportJobStatus::Failed => "failed",
+            ImportJobStatus::Cancelled => "cancelled",
+        }
+    }
+}
+
+impl ToSql for ImportJobStatus {
+    fn to_sql(&self) -> rusqlite::Result<ToSqlOutput<'_>> {
+        Ok(ToSqlOutput::from(self.as_str()))
+    }
+}
+
+impl FromSql for ImportJobStatus {
+    fn column_result(value: ValueRef<'_>) -> FromSqlResult<Self> {
+        match value.as_str()? {
+            "queued" => Ok(ImportJobStatus::Queued),
+            "running" => Ok(ImportJobStatus::Running),
+            "succeeded" => Ok(ImportJobStatus::Succeeded),
+            "duplicate" => Ok(ImportJobStatus::Duplicate),
+            "failed" => Ok(ImportJobStatus::Failed),
+            "cancelled" => Ok(ImportJobStatus::Cancelled),
+            _ => Err(FromSqlError::InvalidType),
+        }
+    }
+}
+
+/// A recipe URL queued to be imported in the background. Jobs are kept in the database, so an
+/// import interrupted by closing the app carries on when it is opened again.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, TS)]
+#[ts(export)]
+pub struct ImportJob {
+    pub id: i32,
+    pub url: String,
+    pub status: ImportJobStatus,
+    /// Number of times the import has been started
+    pub attempts: i32,
+    pub recipe_id: Option<i32>,
+    /// Why the last attempt failed
+    pub error: Option<String>,
+    pub created_at: String,
+    pub updated_at: String,
+}
+
+/// Sent to the frontend as the `import-job` event whenever a job changes status.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, TS)]
+#[ts(export)]
+pub struct ImportJobEvent {
+    pub job: ImportJob,
+    /// Jobs still queued or running, across the whole queue
+    pub remaining: i32,
+}
@@ -0,0 +1,61 @@
+use serde::{Deserialize, Serialize};
+use ts_rs::TS;
+
+/// Describes the contents of a library archive, stored in it as `manifest.json`.
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize, TS)]
+#[ts(export)]
+pub struct LibraryManifest {
+    /// Layout of the archive, bumped whenever it changes in a way older versions can't read
+    pub format_version: u32,
+    pub app_version: String,
+    /// Schema version of the database snapshot
+    pub schema_version: i32,
+    pub created_at: String,
+    pub recipe_count: i32,
+    pub files: Vec<ArchiveEntry>,
+}
+
+/// A file in a library archive besides the manifest.
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize, TS)]
+#[ts(export)]
+pub struct ArchiveEntry {
+    /// Path within the archive
+    pub path: String,
+    /// Hex encoded SHA-256 of the file's contents
+    pub sha256: String,
+    pub size: u64,
+    /// For images, the path recipes in the snapshot refer to them by
+    pub source_path: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, TS)]
+#[serde(rename_all = "snake_case")]
+#[ts(export)]
+pub enum RestoreMode {
+    /// Replace the whole library with the archived one
+    Replace,
+    /// Add the archived recipes missing from the library, along with their tags, collections,
+    /// cook log and revisions
+    Merge,
+}
+
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize, TS)]
+#[ts(export)]
+pub struct LibraryImportReport {
+    pub recipes_added: i32,
+    /// Archived recipes already in the library, only skipped when merging
+    pub recipes_skipped: i32,
+    pub images_restored: i32,
+}
+
+/// An automatic backup that can be restored with `import_library`.
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize, TS)]
+#[ts(export)]
+pub struct RestorePoint {
+    pub path: String,
+    pub created_at: String,
+    pub recipe_count: i32,
+    pub app_version: String,
+    /// Size of the archive in bytes
+    pub size: u64,
+}
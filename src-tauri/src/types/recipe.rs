@@ -1,4 +1,5 @@
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 use ts_rs::TS;
 
 #[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize, TS)]
@@ -8,6 +9,9 @@ pub struct RecipeData {
     #[serde(rename = "canonical_url")]
     pub canonical_url: Option<String>,
     pub category: Option<String>,
+    /// In minutes
+    #[serde(rename = "cook_time")]
+    pub cook_time: Option<f64>,
     pub host: Option<String>,
     pub image: Option<String>,
     pub ingredients: Option<Vec<String>>,
@@ -15,10 +19,18 @@ pub struct RecipeData {
     #[serde(rename = "instructions_list")]
     pub instructions_list: Option<Vec<String>>,
     pub language: Option<String>,
+    /// Nutrition facts such as `calories`, with their units, as in `350 kcal`
+    pub nutrients: Option<HashMap<String, String>>,
+    /// In minutes
+    #[serde(rename = "prep_time")]
+    pub prep_time: Option<f64>,
     pub ratings: Option<f64>,
     #[serde(rename = "site_name")]
     pub site_name: Option<String>,
     pub title: Option<String>,
+    /// In minutes
+    #[serde(rename = "total_time")]
+    pub total_time: Option<f64>,
     pub yields: Option<String>,
 }
 
@@ -26,7 +38,8 @@ pub struct RecipeData {
 #[ts(export)]
 pub struct RecipeDetails {
     pub image: String,
-    pub url: String,
+    /// Page the recipe was saved from, `None` for recipes written from scratch
+    pub url: Option<String>,
     pub servings: i32,
     pub time: i32,
     pub calories: i32,
@@ -36,4 +49,93 @@ pub struct RecipeDetails {
     pub title: String,
     pub id: Option<i32>,
     pub date_added: Option<String>,
+    pub deleted_at: Option<String>,
+    /// Incremented on every update, must match the stored value for an update to go through
+    pub version: Option<i32>,
+    pub updated_at: Option<String>,
+    /// The page's own canonical URL if it declares one. Saved recipes hold the normalized form
+    /// used to detect duplicates.
+    pub canonical_url: Option<String>,
+    /// Scaled down copies of a saved image, filled in by the library and ignored when saving
+    pub image_thumbnail: Option<String>,
+    pub image_medium: Option<String>,
+}
+
+/// What `add_recipe` does when the recipe is already saved under the same canonical URL.
+#[derive(Default, Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, TS)]
+#[serde(rename_all = "snake_case")]
+#[ts(export)]
+pub enum DuplicatePolicy {
+    /// Fail with `DBError::Duplicate`
+    #[default]
+    Reject,
+    /// Fill in the fields missing from the saved recipe and add the new labels to it
+    Merge,
+}
+
+/// The fields of a saved recipe needed to render it in the library, without its ingredients
+/// and instructions. The full recipe is fetched with `get_recipe_by_id`.
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize, TS)]
+#[ts(export)]
+pub struct RecipeSummary {
+    pub id: i32,
+    pub title: String,
+    pub image: String,
+    /// Scaled down copy of a saved image, to show instead of the image when there is one
+    pub image_thumbnail: Option<String>,
+    pub source: Option<String>,
+    pub servings: i32,
+    pub time: i32,
+    pub calories: i32,
+    pub date_added: Option<String>,
+    pub last_cooked: Option<String>,
+    pub times_cooked: i32,
+    pub average_rating: Option<f64>,
+}
+
+#[derive(Default, Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, TS)]
+#[serde(rename_all = "snake_case")]
+#[ts(export)]
+pub enum RecipeSort {
+    #[default]
+    DateAdded,
+    Title,
+    Time,
+    Calories,
+    LastCooked,
+    Rating,
+}
+
+#[derive(Default, Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, TS)]
+#[serde(rename_all = "snake_case")]
+#[ts(export)]
+pub enum SortDirection {
+    Asc,
+    #[default]
+    Desc,
+}
+
+/// Options for `list_recipes`. Pass the `next_cursor` of the previous page as `cursor` to
+/// fetch the page after it, keeping `sort`, `direction` and the filters unchanged.
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize, TS)]
+#[serde(default)]
+#[ts(export)]
+pub struct RecipeListQuery {
+    pub sort: RecipeSort,
+    pub direction: SortDirection,
+    pub cursor: Option<String>,
+    /// Defaults to 50, capped at 500
+    pub limit: Option<i32>,
+    /// Only list recipes carrying these tags
+    pub tag_ids: Vec<i32>,
+    /// Require every tag in `tag_ids` rather than any one of them
+    pub match_all_tags: bool,
+}
+
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize, TS)]
+#[ts(export)]
+pub struct RecipePage {
+    pub recipes: Vec<RecipeSummary>,
+    /// `None` on the last page
+    pub next_cursor: Option<String>,
 }
@@ -25,17 +25,28 @@ pub struct EdamamResponse {
     pub hits: Vec<Hit>,
 }
 
-impl EdamamResponse {
-    fn check_if_scrapable(
-        url: &str,
-        scrapable_domains: &[String],
-    ) -> Result<bool, CheckScrapableError> {
-        let parsed_url = Url::parse(url).map_err(CheckScrapableError::UrlParseError)?;
-        let host = parsed_url.host_str().ok_or(CheckScrapableError::NoHost)?;
-        let domain_name = domain_str(host).ok_or(CheckScrapableError::NoDomain)?;
-        Ok(scrapable_domains.contains(&domain_name.to_owned()))
-    }
+/// Domains recipe-scrapers has a scraper for, read from
+/// `../../scripts/get_valid_hosts/output_hosts.txt`, which lists one per line.
+pub fn scrapable_domains() -> Vec<String> {
+    include_str!("../../scripts/get_valid_hosts/output_hosts.txt")
+        .lines()
+        .map(String::from)
+        .collect()
+}
 
+/// Whether recipe-scrapers has a scraper for the site `url` is on, going by its registrable
+/// domain, so that `www.` and other subdomains match too.
+pub fn check_if_scrapable(
+    url: &str,
+    scrapable_domains: &[String],
+) -> Result<bool, CheckScrapableError> {
+    let parsed_url = Url::parse(url).map_err(CheckScrapableError::UrlParseError)?;
+    let host = parsed_url.host_str().ok_or(CheckScrapableError::NoHost)?;
+    let domain_name = domain_str(host).ok_or(CheckScrapableError::NoDomain)?;
+    Ok(scrapable_domains.contains(&domain_name.to_owned()))
+}
+
+impl EdamamResponse {
     /// Updates the scrapable status of all search `hits` in the instance.
     ///
     /// This method reads from a text file of valid hosts, and checks whether each hit's recipe URL
@@ -56,14 +67,10 @@ impl EdamamResponse {
     /// each `hit`.
     ///
     pub fn set_all_hits_scrapable_status(&mut self) {
-        let scrapable_domains: Vec<String> =
-            include_str!("../../scripts/get_valid_hosts/output_hosts.txt")
-                .lines()
-                .map(String::from)
-                .collect();
+        let scrapable_domains = scrapable_domains();
 
         for hit in &mut self.hits {
-            match EdamamResponse::check_if_scrapable(&hit.recipe.url, &scrapable_domains) {
+            match check_if_scrapable(&hit.recipe.url, &scrapable_domains) {
                 Ok(scrapable) => {
                     hit.set_scrapable(scrapable);
                 }
@@ -249,6 +256,17 @@ pub struct Images {
     pub large: Option<Image>,
 }
 
+impl Images {
+    /// URLs of the variants to fall back on when the recipe's own image can't be downloaded,
+    /// largest first. Thumbnails are too small to show a recipe with.
+    pub fn fallback_urls(&self) -> Vec<String> {
+        [&self.large, &self.regular, &self.small]
+            .iter()
+            .filter_map(|image| image.as_ref().map(|image| image.url.clone()))
+            .collect()
+    }
+}
+
 #[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize, TS)]
 #[serde(rename_all = "camelCase")]
 #[ts(export)]
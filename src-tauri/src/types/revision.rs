@@ -0,0 +1,180 @@
+use serde::{Deserialize, Serialize};
+use ts_rs::TS;
+
+use super::recipe::RecipeDetails;
+
+/// A snapshot of a saved recipe, taken every time it is saved.
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize, TS)]
+#[ts(export)]
+pub struct RecipeRevision {
+    pub id: i32,
+    pub recipe_id: i32,
+    pub recipe: RecipeDetails,
+    pub created_at: Option<String>,
+}
+
+/// A field that differs between two revisions. List fields also carry the lines that were
+/// added and removed, in their order of appearance.
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize, TS)]
+#[ts(export)]
+pub struct FieldChange {
+    pub field: String,
+    pub before: Option<String>,
+    pub after: Option<String>,
+    pub added: Vec<String>,
+    pub removed: Vec<String>,
+}
+
+/// Compares the user-editable fields of two versions of a recipe.
+pub fn diff_recipes(before: &RecipeDetails, after: &RecipeDetails) -> Vec<FieldChange> {
+    let mut changes = Vec::new();
+
+    let scalars = [
+        (
+            "title",
+            Some(before.title.clone()),
+            Some(after.title.clone()),
+        ),
+        (
+            "image",
+            Some(before.image.clone()),
+            Some(after.image.clone()),
+        ),
+        ("url", before.url.clone(), after.url.clone()),
+        (
+            "servings",
+            Some(before.servings.to_string()),
+            Some(after.servings.to_string()),
+        ),
+        (
+            "time",
+            Some(before.time.to_string()),
+            Some(after.time.to_string()),
+        ),
+        (
+            "calories",
+            Some(before.calories.to_string()),
+            Some(after.calories.to_string()),
+        ),
+        ("source", before.source.clone(), after.source.clone()),
+    ];
+    for (field, before, after) in scalars {
+        if before != after {
+            changes.push(FieldChange {
+                field: field.to_owned(),
+                before,
+                after,
+                ..Default::default()
+            });
+        }
+    }
+
+    let lists = [
+        ("ingredients", &before.ingredients, &after.ingredients),
+        ("instructions", &before.instructions, &after.instructions),
+    ];
+    for (field, before, after) in lists {
+        if before == after {
+            continue;
+        }
+        let before_lines = before.clone().unwrap_or_default();
+        let after_lines = after.clone().unwrap_or_default();
+        changes.push(FieldChange {
+            field: field.to_owned(),
+            before: before.as_ref().map(|lines| lines.join("\n")),
+            after: after.as_ref().map(|lines| lines.join("\n")),
+            added: after_lines
+                .iter()
+                .filter(|line| !before_lines.contains(line))
+                .cloned()
+                .collect(),
+            removed: before_lines
+                .iter()
+                .filter(|line| !after_lines.contains(line))
+                .cloned()
+                .collect(),
+        });
+    }
+
+    changes
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn lines(lines: &[&str]) -> Option<Vec<String>> {
+        Some(lines.iter().map(|line| line.to_string()).collect())
+    }
+
+    fn pancakes() -> RecipeDetails {
+        RecipeDetails {
+            title: "Pancakes".to_string(),
+            servings: 4,
+            ingredients: lines(&["1 cup flour", "1 egg", "1 cup milk"]),
+            instructions: lines(&["Whisk.", "Fry."]),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn finds_nothing_between_equal_recipes() {
+        // Bookkeeping fields aren't edited, so they don't make a difference either
+        let saved_again = RecipeDetails {
+            version: Some(2),
+            updated_at: Some("2023-05-01 10:00:00".to_string()),
+            ..pancakes()
+        };
+        assert!(diff_recipes(&pancakes(), &saved_again).is_empty());
+    }
+
+    #[test]
+    fn lists_the_lines_added_and_removed() {
+        let after = RecipeDetails {
+            ingredients: lines(&["1 egg", "1 cup milk", "1 cup oat flour", "1 tsp sugar"]),
+            instructions: lines(&["Fry.", "Whisk."]),
+            ..pancakes()
+        };
+        let changes = diff_recipes(&pancakes(), &after);
+        assert_eq!(changes.len(), 2);
+
+        let ingredients = &changes[0];
+        assert_eq!(ingredients.field, "ingredients");
+        assert_eq!(ingredients.added, vec!["1 cup oat flour", "1 tsp sugar"]);
+        assert_eq!(ingredients.removed, vec!["1 cup flour"]);
+        assert_eq!(
+            ingredients.after.as_deref(),
+            Some("1 egg\n1 cup milk\n1 cup oat flour\n1 tsp sugar")
+        );
+
+        // Reordered steps change the field without adding or removing any
+        let instructions = &changes[1];
+        assert_eq!(instructions.field, "instructions");
+        assert!(instructions.added.is_empty() && instructions.removed.is_empty());
+        assert_eq!(instructions.before.as_deref(), Some("Whisk.\nFry."));
+    }
+
+    #[test]
+    fn reports_fields_that_were_missing_before() {
+        let before = RecipeDetails {
+            instructions: None,
+            ..pancakes()
+        };
+        let after = RecipeDetails {
+            source: Some("Grandma".to_string()),
+            servings: 6,
+            ..pancakes()
+        };
+        let changes = diff_recipes(&before, &after);
+        let fields: Vec<&str> = changes.iter().map(|change| change.field.as_str()).collect();
+        assert_eq!(fields, vec!["servings", "source", "instructions"]);
+
+        assert_eq!(changes[0].before.as_deref(), Some("4"));
+        assert_eq!(changes[0].after.as_deref(), Some("6"));
+        assert_eq!(changes[1].before, None);
+        assert_eq!(changes[1].after.as_deref(), Some("Grandma"));
+        assert_eq!(changes[2].before, None);
+        assert_eq!(changes[2].added, vec!["Whisk.", "Fry."]);
+        assert!(changes[2].removed.is_empty());
+    }
+}
@@ -0,0 +1,28 @@
+use serde::{Deserialize, Serialize};
+use ts_rs::TS;
+
+/// User preferences persisted in the `Setting` table. Settings that were never saved fall
+/// back to the values from `Default`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, TS)]
+#[ts(export)]
+pub struct Settings {
+    /// Days a recipe stays in the trash before it is purged on launch, `None` keeps it forever
+    pub trash_retention_days: Option<i32>,
+    /// Directory automatic backups are written to, `None` uses `backups/` in the app data dir
+    pub backup_dir: Option<String>,
+    /// Hours between automatic backups, `None` turns them off
+    pub backup_interval_hours: Option<i32>,
+    /// Number of automatic backups kept before the oldest are deleted, `None` keeps them all
+    pub backup_generations: Option<i32>,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Settings {
+            trash_retention_days: Some(30),
+            backup_dir: None,
+            backup_interval_hours: Some(24),
+            backup_generations: Some(7),
+        }
+    }
+}
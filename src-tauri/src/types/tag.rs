@@ -0,0 +1,139 @@
+use rusqlite::types::{FromSql, FromSqlError, FromSqlResult, ToSql, ToSqlOutput, ValueRef};
+use serde::{Deserialize, Serialize};
+use ts_rs::TS;
+
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize, TS)]
+#[ts(export)]
+pub struct Tag {
+    pub id: i32,
+    pub name: String,
+    pub category: Option<TagCategory>,
+}
+
+/// A tag as applied to a specific recipe, along with who applied it. The same tag can be
+/// applied to a recipe once per origin.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, TS)]
+#[ts(export)]
+pub struct RecipeTag {
+    pub id: i32,
+    pub name: String,
+    pub category: Option<TagCategory>,
+    pub origin: TagOrigin,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, TS)]
+#[serde(rename_all = "lowercase")]
+#[ts(export)]
+pub enum TagOrigin {
+    User,
+    Edamam,
+}
+
+impl TagOrigin {
+    fn as_str(&self) -> &'static str {
+        match self {
+            TagOrigin::User => "user",
+            TagOrigin::Edamam => "edamam",
+        }
+    }
+}
+
+impl ToSql for TagOrigin {
+    fn to_sql(&self) -> rusqlite::Result<ToSqlOutput<'_>> {
+        Ok(ToSqlOutput::from(self.as_str()))
+    }
+}
+
+impl FromSql for TagOrigin {
+    fn column_result(value: ValueRef<'_>) -> FromSqlResult<Self> {
+        match value.as_str()? {
+            "user" => Ok(TagOrigin::User),
+            "edamam" => Ok(TagOrigin::Edamam),
+            _ => Err(FromSqlError::InvalidType),
+        }
+    }
+}
+
+/// The Edamam label group a tag came from, used to filter the library by cuisine, meal type
+/// and so on. Tags created by the user have no category.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, TS)]
+#[serde(rename_all = "lowercase")]
+#[ts(export)]
+pub enum TagCategory {
+    Diet,
+    Health,
+    Caution,
+    Cuisine,
+    Meal,
+    Dish,
+}
+
+impl TagCategory {
+    fn as_str(&self) -> &'static str {
+        match self {
+            TagCategory::Diet => "diet",
+            TagCategory::Health => "health",
+            TagCategory::Caution => "caution",
+            TagCategory::Cuisine => "cuisine",
+            TagCategory::Meal => "meal",
+            TagCategory::Dish => "dish",
+        }
+    }
+}
+
+impl ToSql for TagCategory {
+    fn to_sql(&self) -> rusqlite::Result<ToSqlOutput<'_>> {
+        Ok(ToSqlOutput::from(self.as_str()))
+    }
+}
+
+impl FromSql for TagCategory {
+    fn column_result(value: ValueRef<'_>) -> FromSqlResult<Self> {
+        match value.as_str()? {
+            "diet" => Ok(TagCategory::Diet),
+            "health" => Ok(TagCategory::Health),
+            "caution" => Ok(TagCategory::Caution),
+            "cuisine" => Ok(TagCategory::Cuisine),
+            "meal" => Ok(TagCategory::Meal),
+            "dish" => Ok(TagCategory::Dish),
+            _ => Err(FromSqlError::InvalidType),
+        }
+    }
+}
+
+/// The labels Edamam attaches to a search hit. Field names match the Edamam `Recipe`, so the
+/// frontend can pass `hit.recipe` as is.
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize, TS)]
+#[serde(rename_all = "camelCase", default)]
+#[ts(export)]
+pub struct RecipeLabels {
+    pub diet_labels: Vec<String>,
+    pub health_labels: Vec<String>,
+    pub cautions: Vec<String>,
+    pub cuisine_type: Vec<String>,
+    pub meal_type: Vec<String>,
+    pub dish_type: Vec<String>,
+}
+
+impl RecipeLabels {
+    /// Flattens the labels into `(name, category)` pairs, skipping blank entries.
+    pub fn tags(&self) -> Vec<(&str, TagCategory)> {
+        [
+            (&self.diet_labels, TagCategory::Diet),
+            (&self.health_labels, TagCategory::Health),
+            (&self.cautions, TagCategory::Caution),
+            (&self.cuisine_type, TagCategory::Cuisine),
+            (&self.meal_type, TagCategory::Meal),
+            (&self.dish_type, TagCategory::Dish),
+        ]
+        .into_iter()
+        .flat_map(|(labels, category)| {
+            labels
+                .iter()
+                .map(|label| label.trim())
+                .filter(|label| !label.is_empty())
+                .map(move |label| (label, category))
+        })
+        .collect()
+    }
+}
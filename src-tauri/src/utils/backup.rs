@@ -0,0 +1,126 @@
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    thread,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+use tauri::{AppHandle, Manager};
+
+use crate::{
+    config::get_or_create_app_dir,
+    error::DBError,
+    types::{db::Database, library::RestorePoint, settings::Settings},
+    utils::library::{export_library, read_manifest, verify_archive},
+};
+
+const BACKUP_PREFIX: &str = "backup-";
+const BACKUP_EXTENSION: &str = "zip";
+/// How often the schedule checks whether a backup is due. Backups themselves are taken at the
+/// interval from the settings.
+const CHECK_INTERVAL: Duration = Duration::from_secs(15 * 60);
+
+/// Takes automatic backups for as long as the app runs, starting with one on launch if the last
+/// backup is overdue. Failures are logged and retried at the next check.
+pub fn spawn_backup_schedule(app: AppHandle) {
+    thread::spawn(move || loop {
+        let db = app.state::<Database>();
+        if let Err(error) = backup_if_due(&db, app.clone()) {
+            println!("Error backing up the library: {error}");
+        }
+        thread::sleep(CHECK_INTERVAL);
+    });
+}
+
+fn backup_if_due(db: &Database, app: AppHandle) -> Result<(), DBError> {
+    let settings = db.get_settings()?;
+    let is_due = match settings.backup_interval_hours {
+        Some(hours) => db.is_backup_due(&hours)?,
+        None => false,
+    };
+    if !is_due {
+        return Ok(());
+    }
+
+    let backup_dir = backup_dir(&settings, app.clone())?;
+    fs::create_dir_all(&backup_dir)?;
+    let seconds = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs();
+    let name = format!("{BACKUP_PREFIX}{seconds}.{BACKUP_EXTENSION}");
+
+    // Written under a temporary name so that a backup interrupted midway is never listed as a
+    // restore point
+    let partial_path = backup_dir.join(format!("{name}.partial"));
+    let result = export_library(db, &partial_path, app.clone())
+        .and_then(|_| verify_archive(&partial_path, app))
+        .and_then(|_| Ok(fs::rename(&partial_path, backup_dir.join(&name))?));
+    if let Err(error) = result {
+        let _ = fs::remove_file(&partial_path);
+        return Err(error);
+    }
+    db.record_backup()?;
+
+    if let Some(generations) = settings.backup_generations {
+        for old_backup in backup_paths(&backup_dir)?
+            .iter()
+            .skip(generations.max(1) as usize)
+        {
+            fs::remove_file(old_backup)?;
+        }
+    }
+    Ok(())
+}
+
+fn backup_dir(settings: &Settings, app: AppHandle) -> Result<PathBuf, DBError> {
+    match &settings.backup_dir {
+        Some(dir) => Ok(PathBuf::from(dir)),
+        None => Ok(get_or_create_app_dir(app)?.join("backups")),
+    }
+}
+
+/// Automatic backups in `backup_dir`, newest first.
+fn backup_paths(backup_dir: &Path) -> Result<Vec<PathBuf>, DBError> {
+    if !backup_dir.is_dir() {
+        return Ok(Vec::new());
+    }
+
+    let mut paths: Vec<PathBuf> = fs::read_dir(backup_dir)?
+        .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+        .filter(|path| {
+            let is_backup = path.file_name().map_or(false, |name| {
+                name.to_string_lossy().starts_with(BACKUP_PREFIX)
+            });
+            is_backup
+                && path
+                    .extension()
+                    .map_or(false, |ext| ext == BACKUP_EXTENSION)
+        })
+        .collect();
+    // Names only differ in their timestamp, which has the same number of digits until 2286
+    paths.sort();
+    paths.reverse();
+    Ok(paths)
+}
+
+/// Lists the automatic backups in the backup directory, newest first. Archives whose manifest
+/// can't be read are left out.
+pub fn list_restore_points(db: &Database, app: AppHandle) -> Result<Vec<RestorePoint>, DBError> {
+    let backup_dir = backup_dir(&db.get_settings()?, app)?;
+
+    let restore_points = backup_paths(&backup_dir)?
+        .into_iter()
+        .filter_map(|path| {
+            let manifest = read_manifest(&path).ok()?;
+            let size = fs::metadata(&path).ok()?.len();
+            Some(RestorePoint {
+                path: path.to_string_lossy().into_owned(),
+                created_at: manifest.created_at,
+                recipe_count: manifest.recipe_count,
+                app_version: manifest.app_version,
+                size,
+            })
+        })
+        .collect();
+    Ok(restore_points)
+}
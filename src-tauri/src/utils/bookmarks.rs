@@ -0,0 +1,199 @@
+use scraper::{ElementRef, Html, Selector};
+use std::{collections::HashSet, fs, path::Path};
+use url::Url;
+
+use crate::{
+    error::DBError,
+    types::{
+        db::Database,
+        import::{BookmarkLink, BookmarkReport},
+        response::{check_if_scrapable, scrapable_domains},
+    },
+    utils::canonical_url::canonicalize_url,
+};
+
+/// Beginnings of words in a folder name or a bookmark's title suggesting it leads to a recipe,
+/// even on a site recipe-scrapers has no scraper for. Words as broad as "food" or "cook" are
+/// left out, as they are as likely to turn up in restaurants, shops and news.
+const RECIPE_WORDS: &[&str] = &[
+    "recipe", "recette", "receta", "rezept", "ricett", "baking", "dessert",
+];
+
+/// A link as filed in a bookmarks export.
+#[derive(Debug, Clone, PartialEq)]
+struct Bookmark {
+    url: String,
+    title: String,
+    folders: Vec<String>,
+}
+
+/// Reads the web links of a bookmarks export in the Netscape format Chrome, Firefox and Safari
+/// all export to. Folders are `<H3>` headings, each followed by the `<DL>` list of what they
+/// hold.
+fn parse_bookmarks(html: &str) -> Vec<Bookmark> {
+    let document = Html::parse_document(html);
+    let selector = match Selector::parse("a[href]") {
+        Ok(selector) => selector,
+        Err(_) => return Vec::new(),
+    };
+
+    document
+        .select(&selector)
+        .filter_map(|link| {
+            let url = link.value().attr("href")?.trim();
+            let is_web_link = Url::parse(url).map_or(false, |url| {
+                matches!(url.scheme(), "http" | "https") && url.has_host()
+            });
+            if !is_web_link {
+                return None;
+            }
+            Some(Bookmark {
+                url: url.to_string(),
+                title: link.text().collect::<String>().trim().to_string(),
+                folders: folders_of(link),
+            })
+        })
+        .collect()
+}
+
+fn folders_of(link: ElementRef) -> Vec<String> {
+    let mut folders: Vec<String> = link
+        .ancestors()
+        .filter_map(ElementRef::wrap)
+        .filter(|element| element.value().name() == "dl")
+        .filter_map(|list| list.prev_siblings().find_map(ElementRef::wrap))
+        .filter(|heading| heading.value().name() == "h3")
+        .map(|heading| heading.text().collect::<String>().trim().to_string())
+        .collect();
+    folders.reverse();
+    folders
+}
+
+/// Whether a word of `text` starts with one of `RECIPE_WORDS`, so that "Recipes" counts but
+/// "Prerecipes" doesn't.
+fn mentions_recipe(text: &str) -> bool {
+    text.to_lowercase()
+        .split(|c: char| !c.is_alphanumeric())
+        .any(|word| RECIPE_WORDS.iter().any(|stem| word.starts_with(stem)))
+}
+
+/// Lists the links of a bookmarks export that likely lead to recipes: those on a site
+/// recipe-scrapers has a scraper for, and those filed under a folder or titled with a word
+/// such as "recipe". Links to the same page are listed once.
+pub fn read_bookmarks(db: &Database, path: &Path) -> Result<BookmarkReport, DBError> {
+    let html = fs::read_to_string(path)?;
+    let domains = scrapable_domains();
+
+    let mut report = BookmarkReport::default();
+    let mut seen = HashSet::new();
+    for bookmark in parse_bookmarks(&html) {
+        if !seen.insert(canonicalize_url(&bookmark.url)) {
+            continue;
+        }
+        let is_scrapable = check_if_scrapable(&bookmark.url, &domains).unwrap_or(false);
+        let is_likely_recipe = is_scrapable
+            || mentions_recipe(&bookmark.title)
+            || bookmark
+                .folders
+                .iter()
+                .any(|folder| mentions_recipe(folder));
+        if !is_likely_recipe {
+            report.skipped += 1;
+            continue;
+        }
+
+        report.links.push(BookmarkLink {
+            recipe_id: db.find_recipe_by_url(&bookmark.url)?,
+            url: bookmark.url,
+            title: bookmark.title,
+            folders: bookmark.folders,
+            is_scrapable,
+        });
+    }
+    Ok(report)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EXPORT: &str = r#"<!DOCTYPE NETSCAPE-Bookmark-file-1>
+<META HTTP-EQUIV="Content-Type" CONTENT="text/html; charset=UTF-8">
+<TITLE>Bookmarks</TITLE>
+<H1>Bookmarks</H1>
+<DL><p>
+    <DT><H3>Bookmarks bar</H3>
+    <DL><p>
+        <DT><A HREF="https://news.example.com/">News</A>
+        <DT><H3>Recipes</H3>
+        <DL><p>
+            <DT><A HREF="https://example.com/lasagna">Lasagna</A>
+            <DT><H3>Weeknight</H3>
+            <DL><p>
+                <DT><A HREF=" https://example.com/stir-fry ">Stir fry</A>
+                <DT><A HREF="javascript:void(0)">Bookmarklet</A>
+            </DL><p>
+        </DL><p>
+        <DT><A HREF="https://example.org/cake">Chocolate cake</A>
+    </DL><p>
+    <DT><A HREF="ftp://example.com/files">Files</A>
+</DL><p>
+"#;
+
+    #[test]
+    fn reads_web_links_with_the_folders_they_are_filed_under() {
+        let bookmarks = parse_bookmarks(EXPORT);
+        let read: Vec<(&str, &str, Vec<&str>)> = bookmarks
+            .iter()
+            .map(|bookmark| {
+                (
+                    bookmark.url.as_str(),
+                    bookmark.title.as_str(),
+                    bookmark.folders.iter().map(String::as_str).collect(),
+                )
+            })
+            .collect();
+        assert_eq!(
+            read,
+            vec![
+                ("https://news.example.com/", "News", vec!["Bookmarks bar"]),
+                (
+                    "https://example.com/lasagna",
+                    "Lasagna",
+                    vec!["Bookmarks bar", "Recipes"]
+                ),
+                (
+                    "https://example.com/stir-fry",
+                    "Stir fry",
+                    vec!["Bookmarks bar", "Recipes", "Weeknight"]
+                ),
+                (
+                    "https://example.org/cake",
+                    "Chocolate cake",
+                    vec!["Bookmarks bar"]
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn matches_recipe_words_at_the_start_of_words() {
+        for text in [
+            "Recipes",
+            "Mes recettes",
+            "Rezepte: Kuchen",
+            "Vegan desserts",
+        ] {
+            assert!(mentions_recipe(text), "{text}");
+        }
+        for text in [
+            "Seafood restaurants",
+            "Cookie policy",
+            "Mealtime news",
+            "Food bank",
+            "Prerecipes",
+        ] {
+            assert!(!mentions_recipe(text), "{text}");
+        }
+    }
+}
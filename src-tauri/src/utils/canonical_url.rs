@@ -0,0 +1,148 @@
+use scraper::{Html, Selector};
+use url::Url;
+
+/// Query parameters added by newsletters, ads and social networks to track where a visit came
+/// from. Parameters starting with `utm_` are removed as well.
+const TRACKING_PARAMS: &[&str] = &[
+    "fbclid", "gclid", "dclid", "msclkid", "yclid", "igshid", "mc_cid", "mc_eid", "_ga", "_gl",
+    "ref_src", "cmpid",
+];
+
+/// Query parameters and path segments that select the AMP or print version of a page, also
+/// selected with `output=amp` or `output=print`.
+const VARIANT_MARKERS: &[&str] = &["amp", "print", "wprm_print"];
+
+/// Host prefixes of mobile and AMP mirrors of a site.
+const HOST_PREFIXES: &[&str] = &["www.", "m.", "mobile.", "amp."];
+
+/// Reduces a recipe URL to the form used to detect duplicates, so that links to the same page
+/// compare equal.
+///
+/// The URL is switched to `https`, the `www.`, `m.` and `amp.` host prefixes, the fragment,
+/// tracking parameters, AMP and print markers and a trailing slash are removed, and the
+/// remaining query parameters are sorted. Anything that isn't an `http(s)` URL is returned
+/// trimmed but otherwise unchanged.
+pub fn canonicalize_url(url: &str) -> String {
+    let url = url.trim();
+    let mut parsed = match Url::parse(url) {
+        Ok(parsed) if matches!(parsed.scheme(), "http" | "https") => parsed,
+        _ => return url.to_string(),
+    };
+
+    let _ = parsed.set_scheme("https");
+    let _ = parsed.set_port(None);
+    parsed.set_fragment(None);
+
+    if let Some(host) = parsed.host_str().map(str::to_string) {
+        let mut host = host.as_str();
+        while let Some(stripped) = HOST_PREFIXES
+            .iter()
+            .find_map(|prefix| host.strip_prefix(prefix))
+            .filter(|stripped| stripped.contains('.'))
+        {
+            host = stripped;
+        }
+        let host = host.to_string();
+        let _ = parsed.set_host(Some(&host));
+    }
+
+    let mut query: Vec<(String, String)> = parsed
+        .query_pairs()
+        .filter(|(key, value)| {
+            let key = key.to_lowercase();
+            let is_tracking = key.starts_with("utm_") || TRACKING_PARAMS.contains(&key.as_str());
+            let is_variant = VARIANT_MARKERS.contains(&key.as_str())
+                || (key == "output" && VARIANT_MARKERS.contains(&value.to_lowercase().as_str()));
+            !is_tracking && !is_variant
+        })
+        .map(|(key, value)| (key.into_owned(), value.into_owned()))
+        .collect();
+    query.sort();
+    if query.is_empty() {
+        parsed.set_query(None);
+    } else {
+        parsed.query_pairs_mut().clear().extend_pairs(query);
+    }
+
+    let segments: Vec<String> = parsed
+        .path_segments()
+        .map(|segments| {
+            segments
+                .filter(|segment| {
+                    !segment.is_empty()
+                        && !VARIANT_MARKERS.contains(&segment.to_lowercase().as_str())
+                })
+                .map(str::to_string)
+                .collect()
+        })
+        .unwrap_or_default();
+    parsed.set_path(&segments.join("/"));
+
+    let mut canonical = parsed.to_string();
+    if canonical.ends_with('/') {
+        canonical.pop();
+    }
+    canonical
+}
+
+/// Returns the URL in the `<link rel="canonical">` tag of an HTML page, resolved against the
+/// `url` the page was fetched from.
+pub fn canonical_link(body: &str, url: &str) -> Option<String> {
+    let html = Html::parse_document(body);
+    let selector = Selector::parse(r#"link[rel="canonical"][href]"#).ok()?;
+    let href = html.select(&selector).next()?.value().attr("href")?;
+
+    let canonical = Url::parse(url).ok()?.join(href.trim()).ok()?;
+    match canonical.scheme() {
+        "http" | "https" => Some(canonical.to_string()),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn removes_mirrors_variants_and_tracking() {
+        assert_eq!(
+            canonicalize_url("http://www.example.com/pasta/amp/?utm_source=feed#recipe"),
+            "https://example.com/pasta"
+        );
+        assert_eq!(
+            canonicalize_url("https://m.example.com/pasta?fbclid=abc&output=print"),
+            "https://example.com/pasta"
+        );
+    }
+
+    #[test]
+    fn keeps_and_sorts_content_parameters() {
+        // Some sites select the recipe with `ref`, so it isn't treated as tracking
+        assert_eq!(
+            canonicalize_url("https://example.com/recipe?ref=1234&ref_src=twsrc"),
+            "https://example.com/recipe?ref=1234"
+        );
+        assert_eq!(
+            canonicalize_url("https://example.com/recipe?b=2&a=1"),
+            "https://example.com/recipe?a=1&b=2"
+        );
+    }
+
+    #[test]
+    fn leaves_other_urls_unchanged() {
+        assert_eq!(
+            canonicalize_url("  file:///recipes/pasta  "),
+            "file:///recipes/pasta"
+        );
+        assert_eq!(canonicalize_url(""), "");
+    }
+
+    #[test]
+    fn resolves_relative_canonical_links() {
+        let body = r#"<html><head><link rel="canonical" href="/pasta"></head></html>"#;
+        assert_eq!(
+            canonical_link(body, "https://example.com/amp/pasta"),
+            Some("https://example.com/pasta".to_string())
+        );
+    }
+}
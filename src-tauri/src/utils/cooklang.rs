@@ -0,0 +1,783 @@
+use std::{
+    collections::HashSet,
+    fs, io,
+    path::{Path, PathBuf},
+};
+use tauri::AppHandle;
+use url::Url;
+
+use crate::{
+    error::DBError,
+    types::{
+        db::Database,
+        import::{ImportStatus, RecipeImportResult},
+        recipe::RecipeDetails,
+        tag::RecipeTag,
+    },
+    utils::{
+        export::file_stem,
+        image::local_image_path,
+        import::save_imported_recipe,
+        parse::{parse_calories, parse_ingredient, parse_minutes, parse_servings, IngredientParts},
+    },
+};
+
+/// Extensions of the image Cooklang keeps next to a recipe under the same name.
+const IMAGE_EXTENSIONS: &[&str] = &["jpg", "jpeg", "png", "webp", "gif"];
+
+/// Characters that can't appear in the name of a multi-word ingredient, cookware or timer.
+const NAME_BREAKS: &str = "@#~{}\n.,;:!?()";
+
+/// A recipe read from a Cooklang `.cook` file.
+#[derive(Default, Debug, Clone, PartialEq)]
+pub struct CooklangRecipe {
+    /// Metadata with lowercase keys, in the order it appears
+    pub metadata: Vec<(String, String)>,
+    /// One line per ingredient, such as `2 cups flour`, in the order they're first used
+    pub ingredients: Vec<String>,
+    /// Steps with their ingredients, cookware and timers written out as plain text
+    pub steps: Vec<String>,
+    /// Notes, the lines starting with `>`
+    pub notes: Vec<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ComponentKind {
+    Ingredient,
+    Cookware,
+    Timer,
+}
+
+/// An ingredient, cookware or timer used in a step.
+#[derive(Debug, Clone, PartialEq)]
+struct Component {
+    kind: ComponentKind,
+    name: String,
+    quantity: Option<String>,
+    unit: Option<String>,
+    note: Option<String>,
+    /// Refers back to an ingredient used earlier or is hidden, so isn't listed
+    unlisted: bool,
+}
+
+impl Component {
+    /// How the component reads in the text of its step.
+    fn text(&self) -> String {
+        match (self.kind, &self.quantity) {
+            (ComponentKind::Timer, Some(quantity)) => match &self.unit {
+                Some(unit) => format!("{quantity} {unit}"),
+                None => quantity.clone(),
+            },
+            _ => self.name.clone(),
+        }
+    }
+
+    fn ingredient_line(&self) -> String {
+        let line = [&self.quantity, &self.unit, &Some(self.name.clone())]
+            .iter()
+            .filter_map(|part| part.as_deref())
+            .collect::<Vec<_>>()
+            .join(" ");
+        match &self.note {
+            Some(note) => format!("{line}, {note}"),
+            None => line,
+        }
+    }
+}
+
+/// Parses the text of a Cooklang recipe. Comments and section headings are dropped.
+pub fn parse_cooklang(text: &str) -> CooklangRecipe {
+    let text = strip_block_comments(&text.replace("\r\n", "\n"));
+    let lines: Vec<&str> = text.lines().collect();
+    let mut recipe = CooklangRecipe::default();
+
+    let mut body = &lines[..];
+    if lines.first().map(|line| line.trim()) == Some("---") {
+        if let Some(end) = lines[1..].iter().position(|line| line.trim() == "---") {
+            parse_front_matter(&lines[1..end + 1], &mut recipe.metadata);
+            body = &lines[end + 2..];
+        }
+    }
+
+    let mut paragraph = Vec::new();
+    let mut seen = HashSet::new();
+    // The trailing blank line ends the last paragraph
+    for line in body.iter().chain(std::iter::once(&"")) {
+        let ends_paragraph = line.trim().is_empty() || line.trim_start().starts_with('=');
+        if ends_paragraph && !paragraph.is_empty() {
+            recipe.add_step(&paragraph.join(" "), &mut seen);
+            paragraph.clear();
+        }
+
+        let line = strip_line_comment(line).trim();
+        if ends_paragraph || line.is_empty() {
+            continue;
+        }
+        if let Some(metadata) = line.strip_prefix(">>") {
+            if let Some((key, value)) = metadata.split_once(':') {
+                recipe
+                    .metadata
+                    .push((key.trim().to_lowercase(), value.trim().to_string()));
+            }
+        } else if let Some(note) = line.strip_prefix('>') {
+            recipe.notes.push(note.trim().to_string());
+        } else {
+            paragraph.push(line);
+        }
+    }
+    recipe
+}
+
+impl CooklangRecipe {
+    /// Adds a paragraph as a step, listing the ingredients it uses. A paragraph of nothing but
+    /// ingredients lists them without adding a step.
+    fn add_step(&mut self, paragraph: &str, seen: &mut HashSet<String>) {
+        let (text, components, has_prose) = parse_step(paragraph);
+
+        for component in &components {
+            if component.kind != ComponentKind::Ingredient || component.unlisted {
+                continue;
+            }
+            let is_new = seen.insert(component.name.to_lowercase());
+            if is_new || component.quantity.is_some() {
+                self.ingredients.push(component.ingredient_line());
+            }
+        }
+
+        let is_ingredient_list = !has_prose
+            && components
+                .iter()
+                .any(|component| component.kind == ComponentKind::Ingredient);
+        // Comments and line breaks can leave runs of spaces behind
+        let text = text.split_whitespace().collect::<Vec<_>>().join(" ");
+        if !is_ingredient_list && !text.is_empty() {
+            self.steps.push(text);
+        }
+    }
+}
+
+fn strip_block_comments(text: &str) -> String {
+    let mut stripped = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(start) = rest.find("[-") {
+        stripped.push_str(&rest[..start]);
+        rest = match rest[start + 2..].find("-]") {
+            Some(end) => &rest[start + end + 4..],
+            None => "",
+        };
+    }
+    stripped.push_str(rest);
+    stripped
+}
+
+/// Drops a `--` comment from the end of a line, leaving escaped dashes alone.
+fn strip_line_comment(line: &str) -> &str {
+    let bytes = line.as_bytes();
+    let mut index = 0;
+    while index + 1 < bytes.len() {
+        match bytes[index] {
+            b'\\' => index += 2,
+            b'-' if bytes[index + 1] == b'-' => return &line[..index],
+            _ => index += 1,
+        }
+    }
+    line
+}
+
+/// Reads the `key: value` pairs of YAML front matter. Lists, whether written inline or as
+/// `- item` lines, are read as their items separated by commas.
+fn parse_front_matter(lines: &[&str], metadata: &mut Vec<(String, String)>) {
+    for line in lines {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+
+        if let Some(item) = line.strip_prefix("- ") {
+            if let Some((_, value)) = metadata.last_mut() {
+                if !value.is_empty() {
+                    value.push_str(", ");
+                }
+                value.push_str(&unquote(item));
+            }
+        } else if let Some((key, value)) = line.split_once(':') {
+            let value = value.trim();
+            let value = match value
+                .strip_prefix('[')
+                .and_then(|list| list.strip_suffix(']'))
+            {
+                Some(list) => split_list(list)
+                    .iter()
+                    .map(|item| unquote(item))
+                    .collect::<Vec<_>>()
+                    .join(", "),
+                None => unquote(value),
+            };
+            metadata.push((key.trim().to_lowercase(), value));
+        }
+    }
+}
+
+/// Splits an inline YAML list on the commas outside quotes.
+fn split_list(list: &str) -> Vec<&str> {
+    let mut items = Vec::new();
+    let mut quote = None;
+    let mut start = 0;
+    for (index, c) in list.char_indices() {
+        match (c, quote) {
+            ('"' | '\'', None) => quote = Some(c),
+            (c, Some(open)) if c == open => quote = None,
+            (',', None) => {
+                items.push(list[start..index].trim());
+                start = index + 1;
+            }
+            _ => {}
+        }
+    }
+    items.push(list[start..].trim());
+    items.retain(|item| !item.is_empty());
+    items
+}
+
+fn unquote(value: &str) -> String {
+    let value = value.trim();
+    if value.starts_with('"') {
+        if let Ok(value) = serde_json::from_str::<String>(value) {
+            return value;
+        }
+    }
+    match value
+        .strip_prefix('\'')
+        .and_then(|value| value.strip_suffix('\''))
+    {
+        Some(quoted) => quoted.replace("''", "'"),
+        None => value.to_string(),
+    }
+}
+
+/// Reads the ingredients, cookware and timers of a step, returning its plain text, its
+/// components and whether it has any text of its own.
+fn parse_step(paragraph: &str) -> (String, Vec<Component>, bool) {
+    let mut text = String::with_capacity(paragraph.len());
+    let mut components = Vec::new();
+    let mut has_prose = false;
+
+    let mut index = 0;
+    while let Some(c) = paragraph[index..].chars().next() {
+        match c {
+            '\\' => {
+                index += 1;
+                if let Some(escaped) = paragraph[index..].chars().next() {
+                    text.push(escaped);
+                    has_prose |= escaped.is_alphanumeric();
+                    index += escaped.len_utf8();
+                }
+            }
+            '@' | '#' | '~' => match parse_component(&paragraph[index..]) {
+                Some((component, len)) => {
+                    text.push_str(&component.text());
+                    components.push(component);
+                    index += len;
+                }
+                None => {
+                    text.push(c);
+                    index += 1;
+                }
+            },
+            _ => {
+                text.push(c);
+                has_prose |= c.is_alphanumeric();
+                index += c.len_utf8();
+            }
+        }
+    }
+    (text, components, has_prose)
+}
+
+/// Reads the component `text` starts with, returning it and its length in bytes.
+fn parse_component(text: &str) -> Option<(Component, usize)> {
+    let kind = match text.as_bytes().first()? {
+        b'@' => ComponentKind::Ingredient,
+        b'#' => ComponentKind::Cookware,
+        _ => ComponentKind::Timer,
+    };
+
+    let mut start = 1;
+    let mut unlisted = false;
+    if kind == ComponentKind::Ingredient {
+        while let Some(modifier) = text[start..].chars().next().filter(|c| "&?+-".contains(*c)) {
+            unlisted |= modifier == '&' || modifier == '-';
+            start += 1;
+        }
+    }
+
+    // Names of more than one word end in braces, single words don't need them
+    let after = &text[start..];
+    let (name, mut end) = match after
+        .find('{')
+        .filter(|brace| !after[..*brace].contains(|c: char| NAME_BREAKS.contains(c)))
+    {
+        Some(brace) => (after[..brace].trim(), start + brace),
+        None => {
+            let len = after
+                .find(|c: char| !(c.is_alphanumeric() || c == '_' || c == '-'))
+                .unwrap_or(after.len());
+            (&after[..len], start + len)
+        }
+    };
+
+    let (mut quantity, mut unit) = (None, None);
+    if text[end..].starts_with('{') {
+        let close = text[end..].find('}')?;
+        let amount = &text[end + 1..end + close];
+        let (amount_quantity, amount_unit) = match amount.split_once('%') {
+            Some((quantity, unit)) => (quantity, Some(unit)),
+            None => (amount, None),
+        };
+        quantity = Some(amount_quantity.trim().trim_end_matches('*').trim())
+            .filter(|quantity| !quantity.is_empty())
+            .map(str::to_string);
+        unit = amount_unit
+            .map(str::trim)
+            .filter(|unit| !unit.is_empty())
+            .map(str::to_string);
+        end += close + 1;
+    }
+    if name.is_empty() && !(kind == ComponentKind::Timer && quantity.is_some()) {
+        return None;
+    }
+
+    let mut note = None;
+    if kind == ComponentKind::Ingredient && text[end..].starts_with('(') {
+        if let Some(close) = text[end..].find(')') {
+            note = Some(text[end + 1..end + close].trim())
+                .filter(|note| !note.is_empty())
+                .map(str::to_string);
+            end += close + 1;
+        }
+    }
+
+    let component = Component {
+        kind,
+        name: name.to_string(),
+        quantity,
+        unit,
+        note,
+        unlisted,
+    };
+    Some((component, end))
+}
+
+/// Writes a recipe as Cooklang, with its details as YAML front matter. Each ingredient is
+/// marked up where its step first mentions it, the rest are listed before the first step.
+pub fn recipe_cooklang(recipe: &RecipeDetails, tags: &[RecipeTag]) -> String {
+    let mut cook = String::from("---\n");
+    push_metadata(&mut cook, "title", &yaml_string(&recipe.title));
+    if let Some(source) = &recipe.source {
+        push_metadata(&mut cook, "source.name", &yaml_string(source));
+    }
+    if let Some(url) = &recipe.url {
+        push_metadata(&mut cook, "source.url", &yaml_string(url));
+    }
+    if recipe.servings > 0 {
+        push_metadata(&mut cook, "servings", &recipe.servings.to_string());
+    }
+    if recipe.time > 0 {
+        push_metadata(&mut cook, "time", &format!("{} minutes", recipe.time));
+    }
+    if recipe.calories > 0 {
+        push_metadata(&mut cook, "calories", &recipe.calories.to_string());
+    }
+    if !tags.is_empty() {
+        let tags: Vec<String> = tags.iter().map(|tag| yaml_string(&tag.name)).collect();
+        push_metadata(&mut cook, "tags", &format!("[{}]", tags.join(", ")));
+    }
+    if recipe.image.starts_with("http") {
+        push_metadata(&mut cook, "image", &yaml_string(&recipe.image));
+    }
+    cook.push_str("---\n");
+
+    let mut steps: Vec<Vec<Segment>> = recipe
+        .instructions
+        .iter()
+        .flatten()
+        .map(|step| {
+            let step = step.split_whitespace().collect::<Vec<_>>().join(" ");
+            vec![Segment::Text(escape_cooklang(&step))]
+        })
+        .collect();
+
+    let mut unplaced = Vec::new();
+    for line in recipe.ingredients.iter().flatten() {
+        let parts = parse_ingredient(line);
+        let name: String = parts
+            .name
+            .chars()
+            .filter(|c| !NAME_BREAKS.contains(*c) && !"\\~".contains(*c))
+            .collect();
+        let name = name.trim();
+        if name.is_empty() {
+            continue;
+        }
+
+        let placed = name == parts.name
+            && steps
+                .iter_mut()
+                .any(|segments| mark_up_ingredient(segments, name, &parts));
+        if !placed {
+            unplaced.push(ingredient_markup(name, &parts));
+        }
+    }
+
+    if !unplaced.is_empty() {
+        cook.push('\n');
+        cook.push_str(&unplaced.join(", "));
+        cook.push('\n');
+    }
+    for segments in steps {
+        cook.push('\n');
+        for segment in segments {
+            match segment {
+                Segment::Text(text) | Segment::Markup(text) => cook.push_str(&text),
+            }
+        }
+        cook.push('\n');
+    }
+    cook
+}
+
+/// Part of a step being written out, with its ingredients marked up one at a time.
+enum Segment {
+    Text(String),
+    Markup(String),
+}
+
+/// Marks up the first mention of an ingredient in a step's text, if it has one.
+fn mark_up_ingredient(segments: &mut Vec<Segment>, name: &str, parts: &IngredientParts) -> bool {
+    for index in 0..segments.len() {
+        let text = match &segments[index] {
+            Segment::Text(text) => text,
+            Segment::Markup(_) => continue,
+        };
+        if let Some(start) = find_word(text, name) {
+            let end = start + name.len();
+            let replacement = vec![
+                Segment::Text(text[..start].to_string()),
+                Segment::Markup(ingredient_markup(&text[start..end], parts)),
+                Segment::Text(text[end..].to_string()),
+            ];
+            segments.splice(index..index + 1, replacement);
+            return true;
+        }
+    }
+    false
+}
+
+/// Byte offset of the first whole-word, case-insensitive match of `word` in `text`.
+fn find_word(text: &str, word: &str) -> Option<usize> {
+    let haystack = text.to_ascii_lowercase();
+    let needle = word.to_ascii_lowercase();
+    haystack
+        .match_indices(&needle)
+        .map(|(start, _)| start)
+        .find(|start| {
+            let before = haystack[..*start].chars().next_back();
+            let after = haystack[start + needle.len()..].chars().next();
+            !before.map_or(false, char::is_alphanumeric)
+                && !after.map_or(false, char::is_alphanumeric)
+        })
+}
+
+fn ingredient_markup(name: &str, parts: &IngredientParts) -> String {
+    // Cooklang quantities are single numbers, fractions or plain text
+    let quantity = parts.quantity.as_ref().map(|quantity| {
+        if quantity.contains(" to ") {
+            quantity.replace(" to ", "-")
+        } else if quantity.contains(' ') {
+            let amount = format!("{:.2}", parts.amount.unwrap_or_default());
+            amount
+                .trim_end_matches('0')
+                .trim_end_matches('.')
+                .to_string()
+        } else {
+            quantity.clone()
+        }
+    });
+    let amount = match (quantity, &parts.unit) {
+        (Some(quantity), Some(unit)) => format!("{quantity}%{unit}"),
+        (Some(quantity), None) => quantity,
+        (None, _) => String::new(),
+    };
+    let note = parts
+        .note
+        .as_ref()
+        .map(|note| format!("({})", note.replace(')', "")))
+        .unwrap_or_default();
+    format!("@{name}{{{amount}}}{note}")
+}
+
+/// Escapes the characters in a step's text that Cooklang would read as markup.
+fn escape_cooklang(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    let mut previous = None;
+    for c in text.chars() {
+        match c {
+            '@' | '#' | '~' | '\\' => escaped.push('\\'),
+            '-' if matches!(previous, Some('-') | Some('[')) => escaped.push('\\'),
+            '>' | '=' if escaped.is_empty() => escaped.push('\\'),
+            _ => {}
+        }
+        escaped.push(c);
+        previous = Some(c);
+    }
+    escaped
+}
+
+fn push_metadata(cook: &mut String, key: &str, value: &str) {
+    cook.push_str(&format!("{key}: {value}\n"));
+}
+
+/// Quotes a YAML value when it would otherwise be read as something else.
+fn yaml_string(value: &str) -> String {
+    let is_plain = !value.is_empty()
+        && value.trim() == value
+        && !value.starts_with(|c: char| "-?:,[]{}#&*!|>'\"%@`".contains(c))
+        && !value.contains(": ")
+        && !value.contains(" #")
+        && !value.contains(['[', ']', '{', '}', ',']);
+    match is_plain {
+        true => value.to_string(),
+        false => serde_json::to_string(value).unwrap_or_default(),
+    }
+}
+
+/// Maps a Cooklang recipe onto a recipe and its tags, noting in `messages` what couldn't be
+/// mapped. `title` is used if the recipe has no title of its own.
+fn to_recipe(
+    cooklang: &CooklangRecipe,
+    title: &str,
+    messages: &mut Vec<String>,
+) -> (RecipeDetails, Vec<String>) {
+    let mut recipe = RecipeDetails {
+        title: title.to_string(),
+        ingredients: Some(cooklang.ingredients.clone()).filter(|lines| !lines.is_empty()),
+        instructions: Some(cooklang.steps.clone()).filter(|steps| !steps.is_empty()),
+        ..Default::default()
+    };
+    let mut tags = Vec::new();
+    let (mut prep_time, mut cook_time) = (None, None);
+
+    for (key, value) in &cooklang.metadata {
+        if value.is_empty() {
+            continue;
+        }
+        match key.as_str() {
+            "title" => recipe.title = value.clone(),
+            "source" | "source.url" | "url" if value.starts_with("http") => {
+                recipe.url = Some(value.clone())
+            }
+            "source" | "source.name" => recipe.source = Some(value.clone()),
+            "servings" | "serves" | "yield" => match parse_servings(value) {
+                Some(servings) => recipe.servings = servings,
+                None => messages.push(format!("Servings \"{value}\" weren't understood")),
+            },
+            "time" | "duration" | "total time" | "time required" => match parse_minutes(value) {
+                Some(time) => recipe.time = time,
+                None => messages.push(format!("Time \"{value}\" wasn't understood")),
+            },
+            "prep time" | "prep_time" => prep_time = parse_minutes(value),
+            "cook time" | "cook_time" => cook_time = parse_minutes(value),
+            "calories" => match parse_calories(&format!("calories: {value}")) {
+                Some(calories) => recipe.calories = calories,
+                None => messages.push(format!("Calories \"{value}\" weren't understood")),
+            },
+            "tags" | "tag" => tags.extend(
+                value
+                    .split(',')
+                    .map(str::trim)
+                    .filter(|tag| !tag.is_empty())
+                    .map(str::to_string),
+            ),
+            "image" | "images" if value.starts_with("http") => recipe.image = value.clone(),
+            _ => messages.push(format!("Metadata \"{key}\" wasn't imported")),
+        }
+    }
+
+    if recipe.time == 0 && (prep_time.is_some() || cook_time.is_some()) {
+        recipe.time = prep_time.unwrap_or(0) + cook_time.unwrap_or(0);
+    }
+    if recipe.source.is_none() {
+        recipe.source = recipe
+            .url
+            .as_deref()
+            .and_then(|url| Url::parse(url).ok())
+            .and_then(|url| url.host_str().map(str::to_string));
+    }
+    if !cooklang.notes.is_empty() {
+        messages.push("Notes weren't imported".to_string());
+    }
+    (recipe, tags)
+}
+
+/// Imports Cooklang recipes from `.cook` files, and from the `.cook` files anywhere within
+/// directories. An image next to a recipe with the same name is imported as its photo.
+pub fn import_cooklang(
+    db: &Database,
+    paths: &[PathBuf],
+    app: AppHandle,
+) -> Result<Vec<RecipeImportResult>, DBError> {
+    let mut files = Vec::new();
+    for path in paths {
+        match path.is_dir() {
+            true => find_cook_files(path, &mut files)?,
+            false => files.push(path.clone()),
+        }
+    }
+
+    let results = files
+        .iter()
+        .map(|file| {
+            let title = file
+                .file_stem()
+                .map_or(String::new(), |stem| stem.to_string_lossy().into_owned());
+            let mut result = RecipeImportResult {
+                title: title.clone(),
+                status: ImportStatus::Failed,
+                recipe_id: None,
+                messages: Vec::new(),
+            };
+            let text = match fs::read_to_string(file) {
+                Ok(text) => text,
+                Err(error) => {
+                    result.messages.push(error.to_string());
+                    return result;
+                }
+            };
+
+            let cooklang = parse_cooklang(&text);
+            let (recipe, tags) = to_recipe(&cooklang, &title, &mut result.messages);
+            result.title = recipe.title.clone();
+            let photo = IMAGE_EXTENSIONS
+                .iter()
+                .find_map(|extension| fs::read(file.with_extension(extension)).ok());
+
+            save_imported_recipe(
+                db,
+                recipe,
+                &tags,
+                photo.as_deref(),
+                app.clone(),
+                &mut result,
+            );
+            result
+        })
+        .collect();
+    Ok(results)
+}
+
+/// Collects the `.cook` files within `dir` in name order, skipping hidden directories such
+/// as `.git`.
+fn find_cook_files(dir: &Path, files: &mut Vec<PathBuf>) -> io::Result<()> {
+    let mut entries = fs::read_dir(dir)?
+        .map(|entry| entry.map(|entry| entry.path()))
+        .collect::<io::Result<Vec<_>>>()?;
+    entries.sort();
+
+    for path in entries {
+        let is_hidden = path
+            .file_name()
+            .map_or(false, |name| name.to_string_lossy().starts_with('.'));
+        if path.is_dir() && !is_hidden {
+            find_cook_files(&path, files)?;
+        } else if path
+            .extension()
+            .map_or(false, |extension| extension == "cook")
+        {
+            files.push(path);
+        }
+    }
+    Ok(())
+}
+
+/// Writes recipes to `dir` as `.cook` files, each with its downloaded image next to it,
+/// returning the paths of the recipes written.
+pub fn export_cooklang(
+    db: &Database,
+    recipes: &[RecipeDetails],
+    dir: &Path,
+    app: AppHandle,
+) -> Result<Vec<String>, DBError> {
+    fs::create_dir_all(dir)?;
+
+    recipes
+        .iter()
+        .map(|recipe| {
+            let tags = db.get_tags_for_recipe(&recipe.id.unwrap_or_default())?;
+            let stem = file_stem(recipe);
+            let path = dir.join(format!("{stem}.cook"));
+            fs::write(&path, recipe_cooklang(recipe, &tags))?;
+
+            if let Some(image_path) = local_image_path(&recipe.image, app.clone())? {
+                let extension = image_path
+                    .extension()
+                    .map_or("jpg".to_string(), |extension| {
+                        extension.to_string_lossy().into_owned()
+                    });
+                fs::copy(&image_path, dir.join(format!("{stem}.{extension}")))?;
+            }
+            Ok(path.to_string_lossy().into_owned())
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SAUCE: &str = "---
+title: Tomato Sauce
+servings: 4
+tags: [pasta, vegetarian]
+---
+
+-- Simmer it long enough to lose the raw taste
+Heat @olive oil{2%tbsp} in a #large pot{} and cook @garlic{3%cloves}(minced) for ~{1%minute}.
+
+Add @crushed tomatoes{800%g} and simmer for ~{20%minutes}. Season with @salt{} and @&garlic{1%clove}.
+
+> Keeps for a week in the fridge.
+";
+
+    #[test]
+    fn reads_multi_word_ingredients_cookware_and_timers() {
+        let cooklang = parse_cooklang(SAUCE);
+        assert_eq!(
+            cooklang.ingredients,
+            vec![
+                "2 tbsp olive oil",
+                "3 cloves garlic, minced",
+                "800 g crushed tomatoes",
+                "salt",
+            ]
+        );
+        assert_eq!(
+            cooklang.steps,
+            vec![
+                "Heat olive oil in a large pot and cook garlic for 1 minute.",
+                "Add crushed tomatoes and simmer for 20 minutes. Season with salt and garlic.",
+            ]
+        );
+        assert_eq!(cooklang.notes, vec!["Keeps for a week in the fridge."]);
+    }
+
+    #[test]
+    fn maps_front_matter_onto_the_recipe() {
+        let mut messages = Vec::new();
+        let (recipe, tags) = to_recipe(&parse_cooklang(SAUCE), "tomato-sauce", &mut messages);
+        assert_eq!(recipe.title, "Tomato Sauce");
+        assert_eq!(recipe.servings, 4);
+        assert_eq!(recipe.ingredients.map(|lines| lines.len()), Some(4));
+        assert_eq!(tags, vec!["pasta", "vegetarian"]);
+        assert_eq!(messages, vec!["Notes weren't imported"]);
+    }
+}
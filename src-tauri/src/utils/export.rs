@@ -0,0 +1,317 @@
+use serde_json::{json, Map, Value};
+use std::{fs, path::Path};
+
+use crate::{
+    error::DBError,
+    types::{
+        recipe::RecipeDetails,
+        tag::{RecipeTag, TagCategory},
+    },
+};
+
+/// Describes a recipe as a schema.org `Recipe`, the JSON-LD recipe sites embed in their
+/// pages. `image` replaces the recipe's own image, so that a downloaded image can be inlined.
+/// The image is left out when there is neither.
+pub fn recipe_json_ld(recipe: &RecipeDetails, tags: &[RecipeTag], image: Option<&str>) -> Value {
+    let mut json_ld = Map::new();
+    json_ld.insert("@context".into(), json!("https://schema.org"));
+    json_ld.insert("@type".into(), json!("Recipe"));
+    json_ld.insert("name".into(), json!(recipe.title));
+    if let Some(url) = &recipe.url {
+        json_ld.insert("url".into(), json!(url));
+    }
+    if let Some(image) = exported_image(recipe, image) {
+        json_ld.insert("image".into(), json!(image));
+    }
+
+    if let Some(source) = &recipe.source {
+        json_ld.insert(
+            "publisher".into(),
+            json!({ "@type": "Organization", "name": source }),
+        );
+    }
+    if recipe.servings > 0 {
+        json_ld.insert(
+            "recipeYield".into(),
+            json!(format!("{} servings", recipe.servings)),
+        );
+    }
+    if recipe.time > 0 {
+        json_ld.insert("totalTime".into(), json!(iso_duration(recipe.time)));
+    }
+    if recipe.calories > 0 {
+        json_ld.insert(
+            "nutrition".into(),
+            json!({
+                "@type": "NutritionInformation",
+                "calories": format!("{} calories", recipe.calories),
+            }),
+        );
+    }
+    if let Some(ingredients) = &recipe.ingredients {
+        json_ld.insert("recipeIngredient".into(), json!(ingredients));
+    }
+    if let Some(instructions) = &recipe.instructions {
+        let steps: Vec<Value> = instructions
+            .iter()
+            .map(|text| json!({ "@type": "HowToStep", "text": text }))
+            .collect();
+        json_ld.insert("recipeInstructions".into(), Value::Array(steps));
+    }
+
+    let tag_names = |categories: &[TagCategory]| -> Vec<&str> {
+        tags.iter()
+            .filter(|tag| tag.category.map_or(false, |c| categories.contains(&c)))
+            .map(|tag| tag.name.as_str())
+            .collect()
+    };
+    let cuisines = tag_names(&[TagCategory::Cuisine]);
+    if !cuisines.is_empty() {
+        json_ld.insert("recipeCuisine".into(), json!(cuisines));
+    }
+    let categories = tag_names(&[TagCategory::Meal, TagCategory::Dish]);
+    if !categories.is_empty() {
+        json_ld.insert("recipeCategory".into(), json!(categories));
+    }
+    if !tags.is_empty() {
+        let keywords: Vec<&str> = tags.iter().map(|tag| tag.name.as_str()).collect();
+        json_ld.insert("keywords".into(), json!(keywords.join(", ")));
+    }
+
+    Value::Object(json_ld)
+}
+
+/// Formats minutes as an ISO 8601 duration, such as `PT1H30M`.
+fn iso_duration(minutes: i32) -> String {
+    match (minutes / 60, minutes % 60) {
+        (0, minutes) => format!("PT{minutes}M"),
+        (hours, 0) => format!("PT{hours}H"),
+        (hours, minutes) => format!("PT{hours}H{minutes}M"),
+    }
+}
+
+/// The image to export, `image` if it was inlined and otherwise the recipe's own image if it is
+/// on the web. Paths to downloaded images mean nothing outside of the app.
+fn exported_image<'a>(recipe: &'a RecipeDetails, image: Option<&'a str>) -> Option<&'a str> {
+    let is_exportable = |image: &str| {
+        image.starts_with("data:image/")
+            || image.starts_with("https://")
+            || image.starts_with("http://")
+    };
+    image
+        .into_iter()
+        .chain([recipe.image.as_str()])
+        .find(|image| is_exportable(image))
+}
+
+fn escape_html(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&#39;"),
+            _ => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+const PAGE_STYLE: &str = "
+body { font-family: Georgia, serif; max-width: 46rem; margin: 2rem auto; padding: 0 1rem; color: #222; line-height: 1.5; }
+img { display: block; max-width: 100%; max-height: 24rem; margin: 1rem auto; object-fit: cover; }
+.meta { color: #555; display: flex; flex-wrap: wrap; gap: 0 1.5rem; padding: 0; list-style: none; }
+li { margin-bottom: 0.4rem; }
+a { color: inherit; }
+footer { margin-top: 2rem; font-size: 0.85rem; color: #777; }
+@media print { body { margin: 0; } footer { display: none; } h2 { break-after: avoid; } li { break-inside: avoid; } }
+";
+
+/// Renders a recipe as a standalone, printable HTML page that embeds its JSON-LD. Pass the
+/// image as a `data:` URI to keep the page self-contained.
+pub fn recipe_html(recipe: &RecipeDetails, tags: &[RecipeTag], image: Option<&str>) -> String {
+    let title = escape_html(&recipe.title);
+    // `</script>` in a string would end the script element early
+    let json_ld = serde_json::to_string_pretty(&recipe_json_ld(recipe, tags, image))
+        .unwrap_or_default()
+        .replace("</", "<\\/");
+
+    let mut meta = Vec::new();
+    if recipe.servings > 0 {
+        meta.push(format!("<li>Serves {}</li>", recipe.servings));
+    }
+    if recipe.time > 0 {
+        meta.push(format!("<li>{} minutes</li>", recipe.time));
+    }
+    if recipe.calories > 0 {
+        meta.push(format!("<li>{} calories</li>", recipe.calories));
+    }
+    match (&recipe.source, &recipe.url) {
+        (Some(source), Some(url)) => meta.push(format!(
+            "<li>From <a href=\"{}\">{}</a></li>",
+            escape_html(url),
+            escape_html(source)
+        )),
+        (Some(source), None) => meta.push(format!("<li>From {}</li>", escape_html(source))),
+        (None, _) => {}
+    }
+    let image = match exported_image(recipe, image) {
+        Some(image) => format!("<img src=\"{}\" alt=\"{title}\">\n", escape_html(image)),
+        None => String::new(),
+    };
+    let footer = match &recipe.url {
+        Some(url) => format!(
+            "<footer>Original recipe: <a href=\"{url}\">{url}</a></footer>",
+            url = escape_html(url)
+        ),
+        None => String::new(),
+    };
+
+    let list = |items: &Option<Vec<String>>, tag: &str| -> String {
+        let items: String = items
+            .iter()
+            .flatten()
+            .map(|item| format!("<li>{}</li>", escape_html(item)))
+            .collect();
+        format!("<{tag}>{items}</{tag}>")
+    };
+
+    format!(
+        "<!DOCTYPE html>
+<html>
+<head>
+<meta charset=\"utf-8\">
+<meta name=\"viewport\" content=\"width=device-width, initial-scale=1\">
+<title>{title}</title>
+<style>{PAGE_STYLE}</style>
+<script type=\"application/ld+json\">
+{json_ld}
+</script>
+</head>
+<body>
+<h1>{title}</h1>
+<ul class=\"meta\">{meta}</ul>
+{image}<h2>Ingredients</h2>
+{ingredients}
+<h2>Instructions</h2>
+{instructions}
+{footer}
+</body>
+</html>
+",
+        meta = meta.join(""),
+        ingredients = list(&recipe.ingredients, "ul"),
+        instructions = list(&recipe.instructions, "ol"),
+    )
+}
+
+/// A file name for a recipe without its extension, made from its title and id.
+pub fn file_stem(recipe: &RecipeDetails) -> String {
+    let slug = recipe
+        .title
+        .to_lowercase()
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|word| !word.is_empty())
+        .collect::<Vec<_>>()
+        .join("-");
+    format!("{slug}-{}", recipe.id.unwrap_or_default())
+}
+
+/// Writes `html` to `dir` under the recipe's `file_stem`, returning the path written to.
+pub fn write_recipe_html(
+    dir: &Path,
+    recipe: &RecipeDetails,
+    html: &str,
+) -> Result<String, DBError> {
+    let path = dir.join(format!("{}.html", file_stem(recipe)));
+    fs::write(&path, html)?;
+    Ok(path.to_string_lossy().into_owned())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::tag::TagOrigin;
+
+    fn pancakes() -> RecipeDetails {
+        RecipeDetails {
+            id: Some(7),
+            title: "Pancakes & <Syrup>".to_string(),
+            image: "https://example.com/pancakes.jpg".to_string(),
+            url: Some("https://example.com/pancakes?a=1&b=2".to_string()),
+            source: Some("Example".to_string()),
+            servings: 4,
+            time: 90,
+            calories: 350,
+            ingredients: Some(vec!["1 cup flour".to_string(), "1 egg".to_string()]),
+            instructions: Some(vec!["Whisk.".to_string(), "Fry.</script>".to_string()]),
+            ..Default::default()
+        }
+    }
+
+    fn tag(name: &str, category: TagCategory) -> RecipeTag {
+        RecipeTag {
+            id: 1,
+            name: name.to_string(),
+            category: Some(category),
+            origin: TagOrigin::User,
+        }
+    }
+
+    #[test]
+    fn describes_recipes_as_schema_org_json_ld() {
+        let tags = [
+            tag("French", TagCategory::Cuisine),
+            tag("Breakfast", TagCategory::Meal),
+        ];
+        let json_ld = recipe_json_ld(&pancakes(), &tags, None);
+        assert_eq!(json_ld["@type"], "Recipe");
+        assert_eq!(json_ld["name"], "Pancakes & <Syrup>");
+        assert_eq!(json_ld["image"], "https://example.com/pancakes.jpg");
+        assert_eq!(json_ld["publisher"]["name"], "Example");
+        assert_eq!(json_ld["recipeYield"], "4 servings");
+        assert_eq!(json_ld["totalTime"], "PT1H30M");
+        assert_eq!(json_ld["nutrition"]["calories"], "350 calories");
+        assert_eq!(json_ld["recipeIngredient"], json!(["1 cup flour", "1 egg"]));
+        assert_eq!(json_ld["recipeInstructions"][0]["@type"], "HowToStep");
+        assert_eq!(json_ld["recipeInstructions"][1]["text"], "Fry.</script>");
+        assert_eq!(json_ld["recipeCuisine"], json!(["French"]));
+        assert_eq!(json_ld["recipeCategory"], json!(["Breakfast"]));
+        assert_eq!(json_ld["keywords"], "French, Breakfast");
+    }
+
+    #[test]
+    fn prefers_an_inlined_image() {
+        let inlined = "data:image/jpeg;base64,AAAA";
+        let json_ld = recipe_json_ld(&pancakes(), &[], Some(inlined));
+        assert_eq!(json_ld["image"], inlined);
+        assert!(
+            recipe_html(&pancakes(), &[], Some(inlined)).contains(&format!("src=\"{inlined}\""))
+        );
+    }
+
+    #[test]
+    fn leaves_out_images_that_are_not_on_the_web() {
+        for image in ["", "/home/me/.local/share/app/images/abc.jpg"] {
+            let recipe = RecipeDetails {
+                image: image.to_string(),
+                ..pancakes()
+            };
+            assert!(recipe_json_ld(&recipe, &[], None).get("image").is_none());
+            assert!(!recipe_html(&recipe, &[], None).contains("<img"));
+        }
+    }
+
+    #[test]
+    fn escapes_the_recipe_in_html() {
+        let html = recipe_html(&pancakes(), &[], None);
+        assert!(html.contains("<h1>Pancakes &amp; &lt;Syrup&gt;</h1>"));
+        assert!(html.contains("href=\"https://example.com/pancakes?a=1&amp;b=2\""));
+        assert!(html.contains("<li>Fry.&lt;/script&gt;</li>"));
+        // The only closing script tag is the one ending the JSON-LD
+        assert_eq!(html.matches("</script>").count(), 1);
+        assert!(html.contains("Fry.<\\/script>"));
+    }
+}
@@ -1,50 +1,500 @@
 use crate::config::get_or_create_images_path;
-use crate::error::{DBError, RequestError};
+use crate::error::{DBError, ImageDownloadError, RequestError};
 use crate::types::client::ReqwestClient;
+use crate::types::db::Database;
+use crate::types::image::ImageGcReport;
 use crate::utils::request::build_request;
-use std::fs::File;
+use base64::{engine::general_purpose::STANDARD, Engine as _};
+use image::{codecs::jpeg::JpegEncoder, imageops::FilterType, DynamicImage, ImageFormat};
+use reqwest::header::CONTENT_TYPE;
+use sha2::{Digest, Sha256};
+use std::collections::HashSet;
+use std::fs::{self, File};
 use std::io::Write;
-use std::path::Path;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
 use tauri::AppHandle;
-use url::Url;
 
+/// Smaller copies of a saved image, made when it is saved so that the library doesn't load
+/// full size photos to show a card.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ImageVariant {
+    /// For cards in the library grid
+    Thumbnail,
+    /// For the recipe page
+    Medium,
+}
+
+impl ImageVariant {
+    const ALL: [ImageVariant; 2] = [ImageVariant::Thumbnail, ImageVariant::Medium];
+
+    /// Reads the name a variant is requested by, the suffix of its file name.
+    pub fn from_suffix(suffix: &str) -> Option<ImageVariant> {
+        ImageVariant::ALL
+            .into_iter()
+            .find(|variant| variant.suffix() == suffix)
+    }
+
+    pub fn suffix(&self) -> &'static str {
+        match self {
+            ImageVariant::Thumbnail => "thumb",
+            ImageVariant::Medium => "medium",
+        }
+    }
+
+    /// Longest side of the variant in pixels
+    fn max_size(&self) -> u32 {
+        match self {
+            ImageVariant::Thumbnail => 400,
+            ImageVariant::Medium => 1200,
+        }
+    }
+}
+
+const VARIANT_JPEG_QUALITY: u8 = 82;
+/// Extension of images still being written by `save_image`
+const PART_EXTENSION: &str = "part";
+/// How long `gc_images` leaves a new file alone
+const GC_GRACE_PERIOD: Duration = Duration::from_secs(60 * 60);
+
+/// Makes the variants missing from images saved before variants were, returning the number of
+/// images given variants. Remote images are skipped, and so are images that can't be decoded.
+pub fn backfill_image_variants(db: &Database, app: AppHandle) -> Result<u32, DBError> {
+    let mut backfilled = 0;
+    for image in db.get_images_without_variants()? {
+        let image_path = match local_image_path(&image, app.clone())? {
+            Some(image_path) => image_path,
+            None => continue,
+        };
+        if let Err(error) = generate_variants(&image_path) {
+            println!("Error resizing image {image}: {error}");
+            continue;
+        }
+        db.set_image_variants(
+            &image,
+            existing_variant(&image, ImageVariant::Thumbnail).as_deref(),
+            existing_variant(&image, ImageVariant::Medium).as_deref(),
+        )?;
+        backfilled += 1;
+    }
+    Ok(backfilled)
+}
+
+/// Largest image that is downloaded or copied into the library, well above the size of any
+/// photo a recipe needs
+const MAX_IMAGE_BYTES: usize = 15_000_000;
+
+/// Downloads a recipe's image into the images directory and returns its path, trying each of
+/// `fallbacks` in turn when `image` can't be downloaded. Returns `None` when none of them can
+/// be, so that the recipe keeps its remote image.
 pub async fn download_image(
     client: tauri::State<'_, ReqwestClient>,
+    db: &Database,
     image: &str,
+    fallbacks: &[String],
     app: AppHandle,
 ) -> Result<Option<String>, DBError> {
-    let image_extension = match get_extension_from_url(image) {
-        Some(extension) => extension,
-        None => return Ok(None),
+    let urls = std::iter::once(image)
+        .chain(fallbacks.iter().map(String::as_str))
+        .filter(|url| !url.trim().is_empty());
+    for url in urls {
+        match fetch_image(client.clone(), url).await {
+            Ok((bytes, extension)) => return Ok(Some(save_image(db, &bytes, extension, app)?)),
+            Err(error) => println!("Error downloading image {url}: {error}"),
+        }
+    }
+    Ok(None)
+}
+
+/// Downloads an image and returns it along with the extension to save it with, going by its
+/// content rather than its URL, which often has no extension on image CDNs.
+async fn fetch_image(
+    client: tauri::State<'_, ReqwestClient>,
+    url: &str,
+) -> Result<(Vec<u8>, &'static str), ImageDownloadError> {
+    let request = build_request(url, None, client).await?;
+    let mut response = request.send().await.map_err(RequestError::Middleware)?;
+    if !response.status().is_success() {
+        return Err(ImageDownloadError::Status(response.status()));
+    }
+
+    // Error and login pages are often served with a success status
+    let content_type = response
+        .headers()
+        .get(CONTENT_TYPE)
+        .and_then(|content_type| content_type.to_str().ok())
+        .unwrap_or_default()
+        .to_lowercase();
+    if content_type.starts_with("text/") || content_type.contains("json") {
+        return Err(ImageDownloadError::ContentType(content_type));
+    }
+    if response
+        .content_length()
+        .map_or(false, |length| length > MAX_IMAGE_BYTES as u64)
+    {
+        return Err(ImageDownloadError::TooLarge(MAX_IMAGE_BYTES));
+    }
+
+    // The declared length can't be trusted, so the limit is checked as the image arrives
+    let mut bytes = Vec::new();
+    while let Some(chunk) = response.chunk().await.map_err(RequestError::Reqwest)? {
+        if bytes.len() + chunk.len() > MAX_IMAGE_BYTES {
+            return Err(ImageDownloadError::TooLarge(MAX_IMAGE_BYTES));
+        }
+        bytes.extend_from_slice(&chunk);
+    }
+    let extension = verify_image(&bytes)?;
+    Ok((bytes, extension))
+}
+
+/// Writes an image to the images directory, named after the SHA-256 of its content, and
+/// returns its path. An image already saved with the same content is used again rather than
+/// stored twice.
+pub fn save_image(
+    db: &Database,
+    bytes: &[u8],
+    extension: &str,
+    app: AppHandle,
+) -> Result<String, DBError> {
+    let hash = format!("{:x}", Sha256::digest(bytes));
+    let image_path = match db.get_stored_image(&hash)? {
+        Some(stored) if stored.ref_count > 0 && Path::new(&stored.path).is_file() => {
+            return Ok(stored.path)
+        }
+        // An unused copy is written again, which also keeps `gc_images` from removing it
+        // before the recipe it is saved for is
+        Some(stored) => PathBuf::from(stored.path),
+        None => get_or_create_images_path(app)?.join(format!("{hash}.{extension}")),
     };
 
-    let image_name = format!("{}.{}", uuid::Uuid::new_v4(), image_extension);
+    // Written under a temporary name first, so that a concurrent save of the same image never
+    // sees half of the file
+    let part_path = image_path.with_extension(format!("{}.{PART_EXTENSION}", uuid::Uuid::new_v4()));
+    let mut file = File::create(&part_path)?;
+    file.write_all(bytes)?;
+    drop(file);
+    fs::rename(&part_path, &image_path)?;
 
-    let image_path = format!(
-        "{}{}",
-        get_or_create_images_path(app)?.display(),
-        image_name
-    );
+    // The original is still shown where a variant is missing, so failing to make one isn't
+    // fatal
+    if let Err(error) = generate_variants(&image_path) {
+        println!("Error resizing image {}: {error}", image_path.display());
+    }
 
-    let request = build_request(image, None, client.clone()).await?;
-    let response = request.send().await.map_err(RequestError::Middleware)?;
+    let image_path = image_path.to_string_lossy().into_owned();
+    db.add_stored_image(&hash, &image_path, &(bytes.len() as i64))?;
+    Ok(image_path)
+}
 
-    match response.status().is_success() {
-        true => {
-            let bytes = response.bytes().await.map_err(RequestError::Reqwest)?;
-            let mut file = File::create(Path::new(&image_path))?;
-            file.write_all(&bytes)?;
-            Ok(Some(image_path))
+/// Path of a variant of a saved image, next to it and named after it. Remote URLs have no
+/// variants.
+fn variant_path(image: &str, variant: ImageVariant) -> Option<PathBuf> {
+    let image_path = Path::new(image);
+    if !image_path.is_absolute() {
+        return None;
+    }
+    let stem = image_path.file_stem()?.to_string_lossy();
+    Some(image_path.with_file_name(format!("{stem}-{}.jpg", variant.suffix())))
+}
+
+/// Returns the path of a variant of a saved image if it has been made.
+pub fn existing_variant(image: &str, variant: ImageVariant) -> Option<String> {
+    variant_path(image, variant)
+        .filter(|path| path.is_file())
+        .map(|path| path.to_string_lossy().into_owned())
+}
+
+/// Makes the thumbnail and medium variants of a saved image as JPEGs, scaled down to fit
+/// their size. Images already smaller are only re-encoded. Transparent areas are filled with
+/// white, as JPEG has no transparency.
+pub fn generate_variants(image_path: &Path) -> Result<(), DBError> {
+    let image = image::io::Reader::open(image_path)?
+        .with_guessed_format()?
+        .decode()?;
+
+    for variant in ImageVariant::ALL {
+        let path = match variant_path(&image_path.to_string_lossy(), variant) {
+            Some(path) => path,
+            None => continue,
+        };
+        let size = variant.max_size();
+        let resized = match image.width() > size || image.height() > size {
+            true => image.resize(size, size, FilterType::Triangle),
+            false => image.clone(),
+        };
+
+        let mut rgba = resized.to_rgba8();
+        for pixel in rgba.pixels_mut() {
+            let [red, green, blue, alpha] = pixel.0;
+            let blend = |channel: u8| {
+                ((channel as u32 * alpha as u32 + 255 * (255 - alpha as u32)) / 255) as u8
+            };
+            pixel.0 = [blend(red), blend(green), blend(blue), 255];
         }
+        let rgb = DynamicImage::ImageRgba8(rgba).to_rgb8();
+
+        let mut file = File::create(&path)?;
+        JpegEncoder::new_with_quality(&mut file, VARIANT_JPEG_QUALITY).encode_image(&rgb)?;
+    }
+    Ok(())
+}
+
+/// Recognizes an image by its first bytes, returning the extension it is saved with. Only
+/// formats the webview can display are recognized.
+pub fn sniff_image_extension(bytes: &[u8]) -> Option<&'static str> {
+    match bytes {
+        [0xFF, 0xD8, 0xFF, ..] => Some("jpg"),
+        [0x89, b'P', b'N', b'G', 0x0D, 0x0A, 0x1A, 0x0A, ..] => Some("png"),
+        [b'G', b'I', b'F', b'8', b'7' | b'9', b'a', ..] => Some("gif"),
+        [b'R', b'I', b'F', b'F', _, _, _, _, b'W', b'E', b'B', b'P', ..] => Some("webp"),
+        [_, _, _, _, b'f', b't', b'y', b'p', b'a', b'v', b'i', b'f' | b's', ..] => Some("avif"),
+        _ => None,
+    }
+}
+
+/// Checks that `bytes` hold a whole image the webview can display, no larger than the limit,
+/// and returns the extension to save it with.
+pub fn verify_image(bytes: &[u8]) -> Result<&'static str, ImageDownloadError> {
+    if bytes.len() > MAX_IMAGE_BYTES {
+        return Err(ImageDownloadError::TooLarge(MAX_IMAGE_BYTES));
+    }
+    let extension = sniff_image_extension(bytes).ok_or(ImageDownloadError::Unrecognized)?;
+
+    // No AVIF decoder is bundled, so AVIF images are only checked by their header
+    if let Some(format) = ImageFormat::from_extension(extension).filter(|_| extension != "avif") {
+        image::load_from_memory_with_format(bytes, format)?;
+    }
+    Ok(extension)
+}
+
+/// Copies an image file the user picked into the images directory and returns its path. The
+/// file must be an image going by its content, whatever its extension says.
+pub fn import_local_image(db: &Database, path: &Path, app: AppHandle) -> Result<String, DBError> {
+    let bytes = fs::read(path)?;
+    match verify_image(&bytes) {
+        Ok(extension) => save_image(db, &bytes, extension, app),
+        Err(error) => Err(DBError::Validation(format!("{}: {error}", path.display()))),
+    }
+}
+
+/// Removes a downloaded image and its variants from the images directory, unless a recipe or
+/// a collection cover still uses it, as recipes with the same photo share its file. Remote
+/// URLs and paths outside the images directory are left alone, as are files that are already
+/// gone.
+pub fn delete_image(db: &Database, image: &str, app: AppHandle) -> Result<(), DBError> {
+    let images_path = get_or_create_images_path(app)?;
+    let image_path = Path::new(image);
+
+    if image_path.starts_with(&images_path) && image_path.is_file() && !db.is_image_used(image)? {
+        fs::remove_file(image_path)?;
+        for variant in ImageVariant::ALL {
+            if let Some(path) = variant_path(image, variant).filter(|path| path.is_file()) {
+                fs::remove_file(path)?;
+            }
+        }
+        db.remove_stored_image(image)?;
+    }
+
+    Ok(())
+}
+
+/// Indexes the images in the images directory that aren't stored by content yet, namely those
+/// saved before images were and those copied in by a library restore, returning how many were
+/// indexed. Images are renamed after their hash, so that they can be served by it. A copy of
+/// an image already stored is swapped for it on the recipes using it, and left for
+/// `gc_images` to remove. Stored images whose file is gone are forgotten, and files that
+/// aren't images in a format the webview can display are skipped.
+pub fn index_images(db: &Database, app: AppHandle) -> Result<u32, DBError> {
+    let images_path = get_or_create_images_path(app)?;
+
+    let mut stored_paths = HashSet::new();
+    for stored in db.get_stored_images()? {
+        match Path::new(&stored.path).is_file() {
+            true => {
+                stored_paths.insert(PathBuf::from(stored.path));
+            }
+            false => db.remove_stored_image(&stored.path)?,
+        }
+    }
+
+    let mut indexed = 0;
+    for entry in fs::read_dir(&images_path)? {
+        let path = entry?.path();
+        if !path.is_file() || stored_paths.contains(&path) || !is_original_image(&path) {
+            continue;
+        }
+        let bytes = fs::read(&path)?;
+        // Whatever else ended up in the directory is left alone
+        let extension = match sniff_image_extension(&bytes) {
+            Some(extension) => extension,
+            None => continue,
+        };
+        let hash = format!("{:x}", Sha256::digest(&bytes));
+        let image = path.to_string_lossy();
+        if let Some(stored) = db.get_stored_image(&hash)? {
+            db.repoint_image(&image, &stored.path)?;
+            db.set_image_variants(
+                &stored.path,
+                existing_variant(&stored.path, ImageVariant::Thumbnail).as_deref(),
+                existing_variant(&stored.path, ImageVariant::Medium).as_deref(),
+            )?;
+            continue;
+        }
+
+        let hashed_path = images_path.join(format!("{hash}.{extension}"));
+        if hashed_path != path {
+            fs::rename(&path, &hashed_path)?;
+            for variant in ImageVariant::ALL {
+                if let Some(variant_path) =
+                    variant_path(&image, variant).filter(|path| path.is_file())
+                {
+                    fs::remove_file(variant_path)?;
+                }
+            }
+            if let Err(error) = generate_variants(&hashed_path) {
+                println!("Error resizing image {}: {error}", hashed_path.display());
+            }
+        }
+        let hashed_image = hashed_path.to_string_lossy();
+        db.add_stored_image(&hash, &hashed_image, &(bytes.len() as i64))?;
+        db.repoint_image(&image, &hashed_image)?;
+        db.set_image_variants(
+            &hashed_image,
+            existing_variant(&hashed_image, ImageVariant::Thumbnail).as_deref(),
+            existing_variant(&hashed_image, ImageVariant::Medium).as_deref(),
+        )?;
+        indexed += 1;
+    }
+
+    db.recount_image_refs()?;
+    Ok(indexed)
+}
+
+/// Removes the images no recipe, revision or collection cover uses any more, trashed recipes
+/// included, and reports the space reclaimed along with the rows pointing to images that are
+/// gone. Files changed in the last `GC_GRACE_PERIOD` are kept, as they may be saved for a
+/// recipe that isn't yet.
+pub fn gc_images(db: &Database, app: AppHandle) -> Result<ImageGcReport, DBError> {
+    let mut report = ImageGcReport {
+        images_indexed: index_images(db, app.clone())?,
+        ..Default::default()
+    };
+
+    // Images only revisions use are kept, so that restoring a revision brings its image back
+    let mut used: HashSet<PathBuf> = db
+        .get_revision_images()?
+        .iter()
+        .flat_map(|image| {
+            ImageVariant::ALL
+                .iter()
+                .filter_map(move |variant| variant_path(image, *variant))
+                .chain(std::iter::once(PathBuf::from(image)))
+        })
+        .collect();
+    let mut unused = HashSet::new();
+    for stored in db.get_stored_images()? {
+        let files = ImageVariant::ALL
+            .iter()
+            .filter_map(|variant| variant_path(&stored.path, *variant))
+            .chain(std::iter::once(PathBuf::from(&stored.path)));
+        match stored.ref_count > 0 {
+            true => used.extend(files),
+            false => {
+                unused.insert(PathBuf::from(&stored.path));
+            }
+        }
+    }
+
+    for entry in fs::read_dir(get_or_create_images_path(app)?)? {
+        let entry = entry?;
+        let path = entry.path();
+        let metadata = entry.metadata()?;
+        if !metadata.is_file() || used.contains(&path) {
+            continue;
+        }
+        let is_recent = metadata
+            .modified()
+            .ok()
+            .and_then(|modified| SystemTime::now().duration_since(modified).ok())
+            .map_or(true, |age| age < GC_GRACE_PERIOD);
+        if is_recent {
+            continue;
+        }
+
+        fs::remove_file(&path)?;
+        report.files_removed += 1;
+        report.bytes_reclaimed += metadata.len();
+        if unused.contains(&path) {
+            db.remove_stored_image(&path.to_string_lossy())?;
+        }
+    }
+
+    report.missing = db
+        .get_image_references()?
+        .into_iter()
+        .filter(|reference| {
+            let path = Path::new(&reference.path);
+            path.is_absolute() && !path.is_file()
+        })
+        .collect();
+    Ok(report)
+}
+
+/// Whether a file in the images directory is an image as saved, rather than one of its
+/// variants or a file still being written.
+fn is_original_image(path: &Path) -> bool {
+    let stem = path.file_stem().map(|stem| stem.to_string_lossy());
+    let extension = path
+        .extension()
+        .map(|extension| extension.to_string_lossy());
+    let is_variant = extension.as_deref() == Some("jpg")
+        && ImageVariant::ALL.iter().any(|variant| {
+            stem.as_deref().map_or(false, |stem| {
+                stem.ends_with(&format!("-{}", variant.suffix()))
+            })
+        });
+    !is_variant && extension.as_deref() != Some(PART_EXTENSION)
+}
+
+/// Returns the path of a downloaded image, or `None` for remote URLs and images that are gone.
+pub fn local_image_path(image: &str, app: AppHandle) -> Result<Option<PathBuf>, DBError> {
+    let images_path = get_or_create_images_path(app)?;
+    let image_path = Path::new(image);
+    match image_path.starts_with(&images_path) && image_path.is_file() {
+        true => Ok(Some(image_path.to_path_buf())),
         false => Ok(None),
     }
 }
 
-fn get_extension_from_url(url: &str) -> Option<String> {
-    let parsed_url = Url::parse(url).ok()?;
-    let path = parsed_url.path();
-    Path::new(path)
+/// Returns a downloaded image as a `data:` URI, so it can be embedded in exported files.
+/// Remote URLs and images that are gone give `None`.
+pub fn image_data_uri(image: &str, app: AppHandle) -> Result<Option<String>, DBError> {
+    let image_path = match local_image_path(image, app)? {
+        Some(image_path) => image_path,
+        None => return Ok(None),
+    };
+
+    let bytes = fs::read(&image_path)?;
+    Ok(Some(format!(
+        "data:{};base64,{}",
+        image_mime_type(&image_path),
+        STANDARD.encode(bytes)
+    )))
+}
+
+/// Returns the MIME type of a saved image, going by its extension.
+pub fn image_mime_type(image_path: &Path) -> &'static str {
+    let extension = image_path
         .extension()
         .and_then(std::ffi::OsStr::to_str)
-        .map(String::from)
+        .map(str::to_lowercase);
+    match extension.as_deref() {
+        Some("jpg") | Some("jpeg") => "image/jpeg",
+        Some("png") => "image/png",
+        Some("gif") => "image/gif",
+        Some("webp") => "image/webp",
+        Some("avif") => "image/avif",
+        Some("svg") => "image/svg+xml",
+        _ => "application/octet-stream",
+    }
 }
@@ -0,0 +1,158 @@
+use std::{error::Error, fs, io::ErrorKind, path::PathBuf};
+use tauri::{
+    http::{Request, Response, ResponseBuilder},
+    AppHandle, Manager,
+};
+use url::Url;
+
+use crate::{
+    config::get_or_create_images_path,
+    types::db::Database,
+    utils::image::{existing_variant, image_mime_type, ImageVariant},
+};
+
+/// Scheme the webview loads saved images from, as `foodie://image/<id>` where the id is the
+/// hash of a stored image. `foodie://image/<id>/thumb` and `foodie://image/<id>/medium` load
+/// its variants, or the image itself when the variant hasn't been made. Images that aren't
+/// stored by content yet are loaded by their file name, as `foodie://image/file/<name>`.
+pub const IMAGE_PROTOCOL: &str = "foodie";
+
+/// An image never changes under its id, as the id is the hash of its content
+const CACHE_CONTROL: &str = "public, max-age=31536000, immutable";
+
+/// The image a `foodie://` URI asks for.
+#[derive(Debug, Clone, PartialEq)]
+enum ImageRequest {
+    Stored {
+        id: String,
+        variant: Option<ImageVariant>,
+    },
+    /// A file of the images directory not indexed by `index_images` yet, such as one saved
+    /// before images were stored by content
+    File(String),
+}
+
+/// Serves a saved image to the webview. Only stored images and files directly in the images
+/// directory can be loaded, so the webview has no way to read any other file.
+pub fn handle_image_request(
+    app: &AppHandle,
+    request: &Request,
+) -> Result<Response, Box<dyn Error>> {
+    let (path, cache_control, etag) = match parse_image_uri(request.uri()) {
+        Some(ImageRequest::Stored { id, variant }) => {
+            let stored = match app.state::<Database>().get_stored_image(&id)? {
+                Some(stored) => stored,
+                None => return respond_with_status(404),
+            };
+            let path = variant
+                .and_then(|variant| existing_variant(&stored.path, variant))
+                .unwrap_or(stored.path);
+            let etag = match variant {
+                Some(variant) => format!("\"{id}-{}\"", variant.suffix()),
+                None => format!("\"{id}\""),
+            };
+            (PathBuf::from(path), CACHE_CONTROL, Some(etag))
+        }
+        // The file is renamed once it is indexed, so it isn't cached
+        Some(ImageRequest::File(name)) => (
+            get_or_create_images_path(app.clone())?.join(name),
+            "no-cache",
+            None,
+        ),
+        None => return respond_with_status(400),
+    };
+
+    let bytes = match fs::read(&path) {
+        Ok(bytes) => bytes,
+        Err(error) if error.kind() == ErrorKind::NotFound => return respond_with_status(404),
+        Err(error) => return Err(error.into()),
+    };
+    let response = ResponseBuilder::new()
+        .status(200)
+        .mimetype(image_mime_type(&path))
+        .header("Cache-Control", cache_control);
+    match etag {
+        Some(etag) => response.header("ETag", etag),
+        None => response,
+    }
+    .body(bytes)
+}
+
+/// Reads the image a `foodie://image/...` URI asks for. Custom protocols are served over https
+/// on Windows, where the URI arrives as `https://foodie.image/...`.
+fn parse_image_uri(uri: &str) -> Option<ImageRequest> {
+    let url = Url::parse(uri).ok()?;
+    let segments: Vec<&str> = url
+        .host_str()
+        .into_iter()
+        .chain(url.path_segments().into_iter().flatten())
+        .filter(|segment| !segment.is_empty())
+        .collect();
+
+    match segments.as_slice() {
+        ["image" | "foodie.image", "file", name] => {
+            // Names the app saves images under, which keeps the path inside the directory
+            let is_plain_name = !name.starts_with('.')
+                && name
+                    .chars()
+                    .all(|c| c.is_ascii_alphanumeric() || matches!(c, '.' | '-' | '_'));
+            match is_plain_name {
+                true => Some(ImageRequest::File(name.to_string())),
+                false => None,
+            }
+        }
+        ["image" | "foodie.image", id, rest @ ..] if rest.len() <= 1 => {
+            let id = id.to_lowercase();
+            if id.len() != 64 || !id.chars().all(|c| c.is_ascii_hexdigit()) {
+                return None;
+            }
+            let variant = match rest.first() {
+                Some(suffix) => Some(ImageVariant::from_suffix(suffix)?),
+                None => None,
+            };
+            Some(ImageRequest::Stored { id, variant })
+        }
+        _ => None,
+    }
+}
+
+fn respond_with_status(status: u16) -> Result<Response, Box<dyn Error>> {
+    ResponseBuilder::new().status(status).body(Vec::new())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_stored_images_and_their_variants() {
+        let id = "ab".repeat(32);
+        assert_eq!(
+            parse_image_uri(&format!("foodie://image/{id}")),
+            Some(ImageRequest::Stored {
+                id: id.clone(),
+                variant: None
+            })
+        );
+        assert_eq!(
+            parse_image_uri(&format!("https://foodie.image/{id}/thumb")),
+            Some(ImageRequest::Stored {
+                id: id.clone(),
+                variant: Some(ImageVariant::Thumbnail)
+            })
+        );
+        assert_eq!(parse_image_uri(&format!("foodie://image/{id}/large")), None);
+        assert_eq!(parse_image_uri("foodie://image/not-a-hash"), None);
+    }
+
+    #[test]
+    fn only_loads_plain_file_names() {
+        assert_eq!(
+            parse_image_uri("foodie://image/file/0b5e7a42-uuid.jpg"),
+            Some(ImageRequest::File("0b5e7a42-uuid.jpg".to_string()))
+        );
+        assert_eq!(parse_image_uri("foodie://image/file/..%2Frecipes.db"), None);
+        assert_eq!(parse_image_uri("foodie://image/file/../recipes.db"), None);
+        assert_eq!(parse_image_uri("foodie://image/file/.hidden"), None);
+    }
+}
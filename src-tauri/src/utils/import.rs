@@ -0,0 +1,84 @@
+use tauri::AppHandle;
+
+use crate::{
+    error::DBError,
+    types::{
+        db::Database,
+        import::{ImportStatus, RecipeImportResult},
+        recipe::RecipeDetails,
+    },
+    utils::image::{delete_image, save_image, verify_image},
+};
+
+/// Saves a recipe read from an import file along with its `tags` and `photo`, recording the
+/// outcome in `result`. Duplicates are left unsaved, and so are photos that aren't whole
+/// images.
+pub fn save_imported_recipe(
+    db: &Database,
+    mut recipe: RecipeDetails,
+    tags: &[String],
+    photo: Option<&[u8]>,
+    app: AppHandle,
+    result: &mut RecipeImportResult,
+) {
+    match db.find_duplicate_recipe(&recipe) {
+        Ok(Some(existing_id)) => {
+            result.status = ImportStatus::Duplicate;
+            result.recipe_id = Some(existing_id);
+            return;
+        }
+        Ok(None) => {}
+        Err(error) => {
+            result.status = ImportStatus::Failed;
+            result.messages.push(error.to_string());
+            return;
+        }
+    }
+
+    // The extension comes from the photo's content rather than the name it was imported with
+    let saved_photo = photo.and_then(|bytes| {
+        let extension = match verify_image(bytes) {
+            Ok(extension) => extension,
+            Err(error) => {
+                result
+                    .messages
+                    .push(format!("Photo wasn't imported: {error}"));
+                return None;
+            }
+        };
+        match save_image(db, bytes, extension, app.clone()) {
+            Ok(path) => Some(path),
+            Err(error) => {
+                result
+                    .messages
+                    .push(format!("Photo couldn't be saved: {error}"));
+                None
+            }
+        }
+    });
+    if let Some(photo) = &saved_photo {
+        recipe.image = photo.clone();
+    }
+
+    match db.import_recipe(&recipe, tags) {
+        Ok(id) => {
+            result.status = ImportStatus::Imported;
+            result.recipe_id = Some(id);
+        }
+        Err(error) => {
+            if let Some(photo) = &saved_photo {
+                let _ = delete_image(db, photo, app);
+            }
+            match error {
+                DBError::Duplicate(existing_id) => {
+                    result.status = ImportStatus::Duplicate;
+                    result.recipe_id = Some(existing_id);
+                }
+                error => {
+                    result.status = ImportStatus::Failed;
+                    result.messages.push(error.to_string());
+                }
+            }
+        }
+    }
+}
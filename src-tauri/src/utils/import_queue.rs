@@ -0,0 +1,182 @@
+use std::{thread, time::Duration};
+use tauri::{AppHandle, Manager};
+use url::Url;
+
+use crate::{
+    error::DBError,
+    types::{
+        client::ReqwestClient,
+        db::Database,
+        import::{ImportStatus, StepStatus, UrlImportReport},
+        import_job::{ImportJob, ImportJobEvent, ImportJobStatus},
+        recipe::DuplicatePolicy,
+    },
+    utils::url_import::import_recipe_from_url,
+};
+
+/// Event sent with an `ImportJobEvent` whenever a job changes status.
+pub const IMPORT_JOB_EVENT: &str = "import-job";
+/// Number of imports run at the same time, kept low to go easy on the sites being scraped
+const WORKERS: usize = 3;
+const MAX_ATTEMPTS: i32 = 3;
+/// Delay before a failed import is retried, multiplied by the number of attempts so far
+const RETRY_DELAY_SECONDS: i32 = 30;
+/// How often an idle worker checks the queue for new or due jobs
+const POLL_INTERVAL: Duration = Duration::from_secs(2);
+
+/// Queues URLs to be imported in the background, one per line of pasted text for instance.
+/// Blank entries are ignored, and entries that aren't web addresses are rejected.
+pub fn enqueue_urls(db: &Database, urls: &[String]) -> Result<Vec<ImportJob>, DBError> {
+    let urls: Vec<String> = urls
+        .iter()
+        .map(|url| url.trim())
+        .filter(|url| !url.is_empty())
+        .map(str::to_string)
+        .collect();
+    if let Some(invalid) = urls
+        .iter()
+        .find(|url| Url::parse(url).map_or(true, |url| !matches!(url.scheme(), "http" | "https")))
+    {
+        return Err(DBError::Validation(format!(
+            "\"{invalid}\" isn't a web address"
+        )));
+    }
+    db.enqueue_import_jobs(&urls)
+}
+
+/// Works through the import queue for as long as the app runs. Jobs left running when the
+/// app was last closed are queued again first.
+pub fn spawn_import_queue(app: AppHandle) {
+    if let Err(error) = app.state::<Database>().requeue_interrupted_import_jobs() {
+        println!("Error resuming the import queue: {error}");
+    }
+
+    for _ in 0..WORKERS {
+        let app = app.clone();
+        thread::spawn(move || loop {
+            match run_next_job(&app) {
+                Ok(true) => {}
+                Ok(false) => thread::sleep(POLL_INTERVAL),
+                Err(error) => {
+                    println!("Error running an import job: {error}");
+                    thread::sleep(POLL_INTERVAL);
+                }
+            }
+        });
+    }
+}
+
+/// Runs the next job that is due, returning whether there was one.
+fn run_next_job(app: &AppHandle) -> Result<bool, DBError> {
+    let db = app.state::<Database>();
+    let job = match db.claim_import_job()? {
+        Some(job) => job,
+        None => return Ok(false),
+    };
+    notify(app, &db, job.clone());
+
+    let report = tauri::async_runtime::block_on(import_recipe_from_url(
+        app.state::<ReqwestClient>(),
+        &db,
+        &job.url,
+        DuplicatePolicy::Reject,
+        app.clone(),
+    ));
+    let job = settle_job(&db, &job, &report)?;
+    notify(app, &db, job);
+    Ok(true)
+}
+
+/// Records how a job's import went. A failed import is queued again when it failed for a
+/// reason that may not last and has attempts left, and fails for good otherwise.
+fn settle_job(
+    db: &Database,
+    job: &ImportJob,
+    report: &UrlImportReport,
+) -> Result<ImportJob, DBError> {
+    match report.status {
+        ImportStatus::Imported => {
+            db.finish_import_job(&job.id, ImportJobStatus::Succeeded, report.recipe_id, None)
+        }
+        ImportStatus::Duplicate => {
+            db.finish_import_job(&job.id, ImportJobStatus::Duplicate, report.recipe_id, None)
+        }
+        ImportStatus::Failed => {
+            let failed_step = report
+                .steps
+                .iter()
+                .find(|outcome| outcome.status == StepStatus::Failed);
+            let error = failed_step.map_or_else(
+                || "The recipe couldn't be imported".to_string(),
+                |outcome| outcome.messages.join("; "),
+            );
+            let can_retry = failed_step.map_or(false, |outcome| outcome.retryable);
+            match can_retry && job.attempts < MAX_ATTEMPTS {
+                true => {
+                    db.requeue_import_job(&job.id, &error, &(RETRY_DELAY_SECONDS * job.attempts))
+                }
+                false => db.finish_import_job(&job.id, ImportJobStatus::Failed, None, Some(&error)),
+            }
+        }
+    }
+}
+
+fn notify(app: &AppHandle, db: &Database, job: ImportJob) {
+    let remaining = db.count_pending_import_jobs().unwrap_or_default();
+    let _ = app.emit_all(IMPORT_JOB_EVENT, ImportJobEvent { job, remaining });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::import::{StepOutcome, UrlImportStep};
+
+    fn failed_validation(message: &str, retryable: bool) -> UrlImportReport {
+        UrlImportReport {
+            steps: vec![StepOutcome {
+                step: UrlImportStep::Validate,
+                status: StepStatus::Failed,
+                messages: vec![message.to_string()],
+                retryable,
+            }],
+            ..Default::default()
+        }
+    }
+
+    fn claim(db: &Database, url: &str) -> ImportJob {
+        db.enqueue_import_jobs(&[url.to_string()]).unwrap();
+        db.claim_import_job().unwrap().unwrap()
+    }
+
+    #[test]
+    fn fails_pages_without_a_recipe_on_the_first_attempt() {
+        let db = Database::in_memory().unwrap();
+        let job = claim(&db, "https://example.com/about");
+
+        let report = failed_validation("No recipe was found on the page", false);
+        let job = settle_job(&db, &job, &report).unwrap();
+        assert_eq!(job.status, ImportJobStatus::Failed);
+        assert_eq!(job.attempts, 1);
+        assert_eq!(
+            job.error.as_deref(),
+            Some("No recipe was found on the page")
+        );
+    }
+
+    #[test]
+    fn retries_failures_that_may_not_last_until_out_of_attempts() {
+        let db = Database::in_memory().unwrap();
+        let job = claim(&db, "https://example.com/pancakes");
+
+        let report = failed_validation("Page responded with 503 Service Unavailable", true);
+        let job = settle_job(&db, &job, &report).unwrap();
+        assert_eq!(job.status, ImportJobStatus::Queued);
+
+        let job = ImportJob {
+            attempts: MAX_ATTEMPTS,
+            ..job
+        };
+        let job = settle_job(&db, &job, &report).unwrap();
+        assert_eq!(job.status, ImportJobStatus::Failed);
+    }
+}
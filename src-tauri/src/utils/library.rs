@@ -0,0 +1,351 @@
+use rusqlite::{Connection, OpenFlags};
+use sha2::{Digest, Sha256};
+use std::{
+    fs::{self, File},
+    io::{self, Read, Seek, Write},
+    path::{Path, PathBuf},
+};
+use tauri::AppHandle;
+use zip::{write::FileOptions, CompressionMethod, ZipArchive, ZipWriter};
+
+use crate::{
+    config::{get_or_create_app_dir, get_or_create_images_path},
+    error::DBError,
+    types::{
+        db::{image_paths, move_image_path, prepare_schema, Database, SCHEMA_VERSION},
+        library::{ArchiveEntry, LibraryImportReport, LibraryManifest, RestoreMode},
+    },
+    utils::image::index_images,
+};
+
+/// Layout version of the archives written by `export_library`
+pub const FORMAT_VERSION: u32 = 1;
+const MANIFEST_PATH: &str = "manifest.json";
+const DATABASE_PATH: &str = "recipes.db";
+const IMAGES_PREFIX: &str = "images/";
+
+/// Writes the whole library to a zip archive at `archive_path`: a snapshot of the database,
+/// the images it refers to and a manifest with their checksums.
+pub fn export_library(
+    db: &Database,
+    archive_path: &Path,
+    app: AppHandle,
+) -> Result<LibraryManifest, DBError> {
+    let app_dir = get_or_create_app_dir(app.clone())?;
+    let images_dir = get_or_create_images_path(app)?;
+    let snapshot_path = app_dir.join(format!("export-{}.db", uuid::Uuid::new_v4()));
+
+    let result = write_archive(db, &snapshot_path, &images_dir, archive_path);
+    let _ = fs::remove_file(&snapshot_path);
+    result
+}
+
+fn write_archive(
+    db: &Database,
+    snapshot_path: &Path,
+    images_dir: &Path,
+    archive_path: &Path,
+) -> Result<LibraryManifest, DBError> {
+    db.backup_to(snapshot_path)?;
+    let snapshot = Connection::open(snapshot_path)?;
+    let schema_version = snapshot.query_row("PRAGMA user_version", [], |row| row.get(0))?;
+    let recipe_count = snapshot.query_row(
+        "SELECT COUNT(*) FROM RecipeDetails WHERE deleted_at IS NULL",
+        [],
+        |row| row.get(0),
+    )?;
+    let created_at = snapshot.query_row("SELECT datetime('now')", [], |row| row.get(0))?;
+    let images = image_paths(&snapshot)?;
+    drop(snapshot);
+
+    let mut zip = ZipWriter::new(File::create(archive_path)?);
+    let options = FileOptions::default()
+        .compression_method(CompressionMethod::Deflated)
+        .large_file(true);
+
+    let mut files = vec![add_file(&mut zip, options, DATABASE_PATH, snapshot_path)?];
+    for image in images {
+        // Only images downloaded by the app are archived, remote ones are left as URLs
+        let path = Path::new(&image);
+        let name = match path.file_name() {
+            Some(name) if path.starts_with(images_dir) && path.is_file() => name.to_string_lossy(),
+            _ => continue,
+        };
+        let mut entry = add_file(&mut zip, options, &format!("{IMAGES_PREFIX}{name}"), path)?;
+        entry.source_path = Some(image.clone());
+        files.push(entry);
+    }
+
+    let manifest = LibraryManifest {
+        format_version: FORMAT_VERSION,
+        app_version: env!("CARGO_PKG_VERSION").to_string(),
+        schema_version,
+        created_at,
+        recipe_count,
+        files,
+    };
+    zip.start_file(MANIFEST_PATH, options)?;
+    serde_json::to_writer_pretty(&mut zip, &manifest)?;
+    zip.finish()?;
+
+    Ok(manifest)
+}
+
+fn add_file<W: Write + Seek>(
+    zip: &mut ZipWriter<W>,
+    options: FileOptions,
+    archive_path: &str,
+    path: &Path,
+) -> Result<ArchiveEntry, DBError> {
+    zip.start_file(archive_path, options)?;
+    let (sha256, size) = copy_hashed(&mut File::open(path)?, zip)?;
+    Ok(ArchiveEntry {
+        path: archive_path.to_string(),
+        sha256,
+        size,
+        source_path: None,
+    })
+}
+
+/// Copies `reader` into `writer`, returning the hex encoded SHA-256 of the copied bytes and
+/// their count.
+fn copy_hashed(reader: &mut impl Read, writer: &mut impl Write) -> io::Result<(String, u64)> {
+    let mut hasher = Sha256::new();
+    let mut buffer = [0; 64 * 1024];
+    let mut size = 0;
+    loop {
+        let read = reader.read(&mut buffer)?;
+        if read == 0 {
+            break;
+        }
+        hasher.update(&buffer[..read]);
+        writer.write_all(&buffer[..read])?;
+        size += read as u64;
+    }
+    Ok((format!("{:x}", hasher.finalize()), size))
+}
+
+/// Restores a library archive written by `export_library`. Every file is checked against the
+/// manifest before the library is touched, and older snapshots are migrated to the current
+/// schema. The restored images are then indexed by content.
+pub fn import_library(
+    db: &Database,
+    archive_path: &Path,
+    mode: RestoreMode,
+    app: AppHandle,
+) -> Result<LibraryImportReport, DBError> {
+    let app_dir = get_or_create_app_dir(app.clone())?;
+    let images_dir = get_or_create_images_path(app.clone())?;
+    let staging_dir = app_dir.join(format!("import-{}", uuid::Uuid::new_v4()));
+    fs::create_dir(&staging_dir)?;
+
+    let result = restore_archive(db, archive_path, mode, &staging_dir, &images_dir);
+    let _ = fs::remove_dir_all(&staging_dir);
+    let report = result?;
+    index_images(db, app)?;
+    Ok(report)
+}
+
+/// Reads the manifest of a library archive without extracting anything.
+pub fn read_manifest(archive_path: &Path) -> Result<LibraryManifest, DBError> {
+    let mut archive = ZipArchive::new(File::open(archive_path)?)?;
+    let manifest = serde_json::from_reader(archive.by_name(MANIFEST_PATH)?)?;
+    Ok(manifest)
+}
+
+/// Checks that a library archive can be restored: every file must match its checksum, and the
+/// database snapshot must open read-only, pass SQLite's integrity check and have a schema this
+/// version of the app knows.
+pub fn verify_archive(archive_path: &Path, app: AppHandle) -> Result<LibraryManifest, DBError> {
+    let app_dir = get_or_create_app_dir(app)?;
+    let staging_dir = app_dir.join(format!("verify-{}", uuid::Uuid::new_v4()));
+    fs::create_dir(&staging_dir)?;
+
+    let result = extract_archive(archive_path, &staging_dir);
+    let _ = fs::remove_dir_all(&staging_dir);
+    result
+}
+
+/// Extracts a library archive into `staging_dir` and verifies it, see `verify_archive`.
+fn extract_archive(archive_path: &Path, staging_dir: &Path) -> Result<LibraryManifest, DBError> {
+    let mut archive = ZipArchive::new(File::open(archive_path)?)?;
+    let manifest: LibraryManifest = serde_json::from_reader(archive.by_name(MANIFEST_PATH)?)?;
+    if manifest.format_version > FORMAT_VERSION {
+        return Err(DBError::CorruptArchive(format!(
+            "format version {} is newer than this version of the app supports",
+            manifest.format_version
+        )));
+    }
+    if !manifest
+        .files
+        .iter()
+        .any(|entry| entry.path == DATABASE_PATH)
+    {
+        return Err(DBError::CorruptArchive(
+            "the database snapshot is missing".to_string(),
+        ));
+    }
+
+    for entry in &manifest.files {
+        let staged_path = staged_path(staging_dir, entry)?;
+        // Reading a byte past the size the manifest gives is enough to tell a file is larger,
+        // without writing all of it to the disk
+        let mut file = archive
+            .by_name(&entry.path)?
+            .take(entry.size.saturating_add(1));
+        let (sha256, size) = copy_hashed(&mut file, &mut File::create(staged_path)?)?;
+        if size > entry.size {
+            return Err(DBError::CorruptArchive(format!(
+                "{} is larger than the manifest says",
+                entry.path
+            )));
+        }
+        if sha256 != entry.sha256 || size != entry.size {
+            return Err(DBError::CorruptArchive(format!(
+                "checksum of {} doesn't match",
+                entry.path
+            )));
+        }
+    }
+
+    let snapshot = Connection::open_with_flags(
+        staging_dir.join(DATABASE_PATH),
+        OpenFlags::SQLITE_OPEN_READ_ONLY,
+    )?;
+    let check: String = snapshot.query_row("PRAGMA quick_check", [], |row| row.get(0))?;
+    if check != "ok" {
+        return Err(DBError::CorruptArchive(format!(
+            "the database snapshot is damaged: {check}"
+        )));
+    }
+    let schema_version: usize = snapshot.query_row("PRAGMA user_version", [], |row| row.get(0))?;
+    if schema_version > SCHEMA_VERSION || manifest.schema_version as usize > SCHEMA_VERSION {
+        return Err(DBError::CorruptArchive(format!(
+            "the database snapshot is from a newer version of the app, at schema version {}",
+            schema_version.max(manifest.schema_version as usize)
+        )));
+    }
+
+    Ok(manifest)
+}
+
+fn restore_archive(
+    db: &Database,
+    archive_path: &Path,
+    mode: RestoreMode,
+    staging_dir: &Path,
+    images_dir: &Path,
+) -> Result<LibraryImportReport, DBError> {
+    let manifest = extract_archive(archive_path, staging_dir)?;
+
+    let snapshot_path = staging_dir.join(DATABASE_PATH);
+    let images: Vec<(&ArchiveEntry, PathBuf)> = manifest
+        .files
+        .iter()
+        .filter_map(|entry| {
+            let name = entry.path.strip_prefix(IMAGES_PREFIX)?;
+            Some((entry, images_dir.join(name)))
+        })
+        .collect();
+    {
+        let mut snapshot = Connection::open(&snapshot_path)?;
+        prepare_schema(&mut snapshot)?;
+        for (entry, image_path) in &images {
+            if let Some(source_path) = &entry.source_path {
+                move_image_path(&snapshot, source_path, &image_path.to_string_lossy())?;
+            }
+        }
+    }
+
+    let mut report = match mode {
+        RestoreMode::Replace => {
+            db.restore_from(&snapshot_path)?;
+            LibraryImportReport {
+                recipes_added: manifest.recipe_count,
+                ..Default::default()
+            }
+        }
+        RestoreMode::Merge => db.merge_from(&snapshot_path)?,
+    };
+
+    // Copied once the database is restored, so that a failed restore leaves no files behind
+    for (entry, image_path) in &images {
+        fs::copy(staging_dir.join(&entry.path), image_path)?;
+    }
+    report.images_restored = images.len() as i32;
+    Ok(report)
+}
+
+/// Where an archived file is extracted to. Paths are only accepted in the layout
+/// `export_library` writes, so an archive can't write outside of `staging_dir`.
+fn staged_path(staging_dir: &Path, entry: &ArchiveEntry) -> Result<PathBuf, DBError> {
+    let is_plain_name =
+        |name: &str| !name.is_empty() && name != ".." && !name.contains(['/', '\\']);
+    match entry.path.strip_prefix(IMAGES_PREFIX) {
+        _ if entry.path == DATABASE_PATH => Ok(staging_dir.join(DATABASE_PATH)),
+        Some(name) if is_plain_name(name) => {
+            let images_dir = staging_dir.join(IMAGES_PREFIX);
+            fs::create_dir_all(&images_dir)?;
+            Ok(images_dir.join(name))
+        }
+        _ => Err(DBError::CorruptArchive(format!(
+            "unexpected file {}",
+            entry.path
+        ))),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Writes an archive holding `files` and a manifest listing `entries` in a directory of
+    /// its own, returning the directory.
+    fn write_test_archive(files: &[(&str, &[u8])], entries: Vec<ArchiveEntry>) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("library-{}", uuid::Uuid::new_v4()));
+        fs::create_dir(&dir).unwrap();
+        let mut zip = ZipWriter::new(File::create(dir.join("library.zip")).unwrap());
+        for (path, content) in files {
+            zip.start_file(*path, FileOptions::default()).unwrap();
+            zip.write_all(content).unwrap();
+        }
+        let manifest = LibraryManifest {
+            format_version: FORMAT_VERSION,
+            files: entries,
+            ..Default::default()
+        };
+        zip.start_file(MANIFEST_PATH, FileOptions::default())
+            .unwrap();
+        serde_json::to_writer(&mut zip, &manifest).unwrap();
+        zip.finish().unwrap();
+        fs::create_dir(dir.join("staging")).unwrap();
+        dir
+    }
+
+    #[test]
+    fn stops_reading_files_larger_than_the_manifest_says() {
+        let content = vec![0; 1_000_000];
+        let dir = write_test_archive(
+            &[(DATABASE_PATH, &content)],
+            vec![ArchiveEntry {
+                path: DATABASE_PATH.to_string(),
+                sha256: String::new(),
+                size: 10,
+                source_path: None,
+            }],
+        );
+
+        let result = extract_archive(&dir.join("library.zip"), &dir.join("staging"));
+        let staged_size = fs::metadata(dir.join("staging").join(DATABASE_PATH))
+            .unwrap()
+            .len();
+        fs::remove_dir_all(&dir).unwrap();
+        match result {
+            Err(DBError::CorruptArchive(reason)) => {
+                assert_eq!(reason, "recipes.db is larger than the manifest says")
+            }
+            other => panic!("expected a corrupt archive, got {other:?}"),
+        }
+        assert_eq!(staged_size, 11);
+    }
+}
@@ -0,0 +1,332 @@
+use std::{fs, path::Path};
+
+use crate::{
+    error::DBError,
+    types::import::RecipeDraft,
+    utils::{parse::parse_servings, recipe_text::split_steps},
+};
+
+/// MealMaster's two letter unit codes and how they're written out.
+const UNITS: &[(&str, &str)] = &[
+    ("x", ""),
+    ("sm", "small"),
+    ("md", "medium"),
+    ("lg", "large"),
+    ("cn", "can"),
+    ("pk", "package"),
+    ("pn", "pinch"),
+    ("dr", "drop"),
+    ("ds", "dash"),
+    ("ct", "carton"),
+    ("bn", "bunch"),
+    ("sl", "slice"),
+    ("ea", ""),
+    ("t", "tsp"),
+    ("ts", "tsp"),
+    ("T", "tbsp"),
+    ("tb", "tbsp"),
+    ("fl", "fl oz"),
+    ("c", "cup"),
+    ("pt", "pint"),
+    ("qt", "quart"),
+    ("ga", "gallon"),
+    ("oz", "oz"),
+    ("lb", "lb"),
+    ("ml", "ml"),
+    ("cb", "cubic cm"),
+    ("cl", "cl"),
+    ("dl", "dl"),
+    ("l", "l"),
+    ("mg", "mg"),
+    ("cg", "cg"),
+    ("dg", "dg"),
+    ("g", "g"),
+    ("kg", "kg"),
+];
+
+/// Column the second ingredient starts at or after when ingredients are printed side by side.
+const SECOND_COLUMN: usize = 30;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Phase {
+    Header,
+    Ingredients,
+    Directions,
+}
+
+/// Reads the recipes of a MealMaster file as drafts. Older files are often Latin-1 rather
+/// than UTF-8, and are read as such.
+pub fn read_mealmaster(path: &Path) -> Result<Vec<RecipeDraft>, DBError> {
+    let bytes = fs::read(path)?;
+    let text = match String::from_utf8(bytes) {
+        Ok(text) => text,
+        Err(error) => error
+            .into_bytes()
+            .iter()
+            .map(|byte| *byte as char)
+            .collect(),
+    };
+    Ok(parse_mealmaster(&text))
+}
+
+fn is_recipe_start(line: &str) -> bool {
+    (line.starts_with("MMMMM") || line.starts_with("-----"))
+        && line.to_lowercase().contains("meal-master")
+}
+
+fn is_recipe_end(line: &str) -> bool {
+    line == "MMMMM" || line == "-----"
+}
+
+/// The title of a section separator such as `MMMMM-----Topping-----`, if the line is one.
+fn section_title(line: &str) -> Option<&str> {
+    let rest = line
+        .strip_prefix("MMMMM-")
+        .or_else(|| line.strip_prefix("-----"))?;
+    Some(rest.trim_matches('-').trim())
+}
+
+/// Parses every recipe in MealMaster text. Recipes start with a `Recipe via Meal-Master`
+/// line and end with `MMMMM` or `-----`.
+pub fn parse_mealmaster(text: &str) -> Vec<RecipeDraft> {
+    let mut drafts = Vec::new();
+    let mut recipe: Option<Vec<&str>> = None;
+
+    for line in text.lines() {
+        let trimmed = line.trim();
+        if is_recipe_start(trimmed) {
+            drafts.extend(recipe.replace(Vec::new()).map(|lines| parse_recipe(&lines)));
+        } else if is_recipe_end(trimmed) {
+            drafts.extend(recipe.take().map(|lines| parse_recipe(&lines)));
+        } else if let Some(lines) = &mut recipe {
+            lines.push(line.trim_end());
+        }
+    }
+    // A file cut short still has its last recipe
+    drafts.extend(recipe.map(|lines| parse_recipe(&lines)));
+    drafts
+}
+
+fn parse_recipe(lines: &[&str]) -> RecipeDraft {
+    let mut draft = RecipeDraft::default();
+    let mut ingredients: Vec<String> = Vec::new();
+    let mut directions = Vec::new();
+    let mut phase = Phase::Header;
+
+    for line in lines {
+        let trimmed = line.trim();
+        if phase == Phase::Header {
+            match trimmed.split_once(':') {
+                Some((key, value)) if is_header_field(key) => {
+                    read_header_field(key, value.trim(), &mut draft);
+                    continue;
+                }
+                _ if trimmed.is_empty() => continue,
+                _ => phase = Phase::Ingredients,
+            }
+        }
+
+        if phase == Phase::Ingredients {
+            if trimmed.is_empty() {
+                continue;
+            }
+            if let Some(title) = section_title(trimmed) {
+                if !title.is_empty() {
+                    ingredients.push(format!("{title}:"));
+                }
+                continue;
+            }
+
+            let chars: Vec<char> = line.chars().collect();
+            if is_ingredient(&chars) {
+                // The second column's quantity is right aligned like the first one's
+                let second = (SECOND_COLUMN..chars.len().saturating_sub(11)).find(|start| {
+                    chars[start - 1] == ' '
+                        && chars[start + 6] != ' '
+                        && is_ingredient(&chars[*start..])
+                });
+                let columns = match second {
+                    Some(start) => vec![&chars[..start], &chars[start..]],
+                    None => vec![&chars[..]],
+                };
+                for column in columns {
+                    add_ingredient(column, &mut ingredients);
+                }
+                continue;
+            }
+            phase = Phase::Directions;
+        }
+
+        match section_title(trimmed) {
+            Some(_) => directions.push(""),
+            None => directions.push(trimmed),
+        }
+    }
+
+    if draft.recipe.title.is_empty() {
+        draft.messages.push("Recipe has no title".to_string());
+    }
+    let steps = split_steps(&directions);
+    draft.recipe.ingredients = Some(ingredients).filter(|ingredients| !ingredients.is_empty());
+    draft.recipe.instructions = Some(steps).filter(|steps| !steps.is_empty());
+    draft
+}
+
+fn is_header_field(key: &str) -> bool {
+    matches!(
+        key.trim().to_lowercase().as_str(),
+        "title" | "categories" | "yield" | "servings"
+    )
+}
+
+fn read_header_field(key: &str, value: &str, draft: &mut RecipeDraft) {
+    match key.trim().to_lowercase().as_str() {
+        "title" => draft.recipe.title = value.to_string(),
+        "categories" => draft.categories.extend(
+            value
+                .split(',')
+                .map(str::trim)
+                .filter(|category| !category.is_empty() && !category.eq_ignore_ascii_case("none"))
+                .map(str::to_string),
+        ),
+        _ => match parse_servings(value) {
+            Some(servings) => draft.recipe.servings = servings,
+            None => draft
+                .messages
+                .push(format!("Yield \"{value}\" wasn't understood")),
+        },
+    }
+}
+
+/// Whether a line is laid out in MealMaster's ingredient columns: a quantity in the first
+/// seven, a unit code in the two after a space, then the ingredient after another space.
+fn is_ingredient(chars: &[char]) -> bool {
+    let column = |start: usize, end: usize| -> String {
+        chars
+            .iter()
+            .skip(start)
+            .take(end - start)
+            .collect::<String>()
+    };
+    let quantity = column(0, 7);
+    let unit = column(8, 10);
+
+    chars.len() > 11
+        && quantity
+            .chars()
+            .all(|c| c.is_ascii_digit() || " ./-".contains(c))
+        && chars[7] == ' '
+        && chars[10] == ' '
+        && (unit.trim().is_empty() || UNITS.iter().any(|(code, _)| *code == unit.trim()))
+        && !column(11, chars.len()).trim().is_empty()
+}
+
+/// Adds an ingredient from its columns. Text starting with `-` continues the ingredient
+/// before it.
+fn add_ingredient(chars: &[char], ingredients: &mut Vec<String>) {
+    let column = |start: usize, end: usize| -> String {
+        chars
+            .iter()
+            .skip(start)
+            .take(end.saturating_sub(start))
+            .collect::<String>()
+            .trim()
+            .to_string()
+    };
+    let quantity = column(0, 7);
+    let code = column(8, 10);
+    let text = column(11, chars.len());
+
+    if quantity.is_empty() && code.is_empty() && text.starts_with('-') {
+        if let Some(previous) = ingredients.last_mut() {
+            previous.push(' ');
+            previous.push_str(text.trim_start_matches('-').trim());
+            return;
+        }
+    }
+
+    let unit = UNITS
+        .iter()
+        .find(|(unit_code, _)| *unit_code == code)
+        .map_or("", |(_, unit)| *unit);
+    let ingredient = [quantity.as_str(), unit, text.as_str()]
+        .iter()
+        .filter(|part| !part.is_empty())
+        .copied()
+        .collect::<Vec<_>>()
+        .join(" ");
+    ingredients.push(ingredient);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MUFFINS: &str = "\
+MMMMM----- Recipe via Meal-Master (tm) v8.05
+
+      Title: Blueberry Muffins
+ Categories: Breads, Breakfast
+      Yield: 12 servings
+
+      2 c  All-purpose flour         1/2 t  Salt
+      1 T  Baking powder             3/4 c  Milk
+      1 lg Egg; beaten
+           -at room temperature
+MMMMM--------------------------TOPPING-------------------------------
+      2 T  Sugar
+
+  Heat the oven to 400F. Whisk the flour, baking powder and salt.
+  Stir in the milk and egg until just combined.
+
+  Fill the muffin cups and sprinkle with the sugar. Bake for 20 minutes.
+
+MMMMM
+";
+
+    #[test]
+    fn reads_a_recipe_with_two_ingredient_columns() {
+        let drafts = parse_mealmaster(MUFFINS);
+        assert_eq!(drafts.len(), 1);
+        let draft = &drafts[0];
+        assert_eq!(draft.recipe.title, "Blueberry Muffins");
+        assert_eq!(draft.recipe.servings, 12);
+        assert_eq!(draft.categories, vec!["Breads", "Breakfast"]);
+        assert_eq!(
+            draft.recipe.ingredients.as_deref(),
+            Some(
+                &[
+                    "2 cup All-purpose flour".to_string(),
+                    "1/2 tsp Salt".to_string(),
+                    "1 tbsp Baking powder".to_string(),
+                    "3/4 cup Milk".to_string(),
+                    "1 large Egg; beaten at room temperature".to_string(),
+                    "TOPPING:".to_string(),
+                    "2 tbsp Sugar".to_string(),
+                ][..]
+            )
+        );
+        assert_eq!(
+            draft
+                .recipe
+                .instructions
+                .as_ref()
+                .and_then(|steps| steps.last()),
+            Some(
+                &"Fill the muffin cups and sprinkle with the sugar. Bake for 20 minutes."
+                    .to_string()
+            )
+        );
+        assert!(draft.messages.is_empty());
+    }
+
+    #[test]
+    fn keeps_each_recipe_of_a_file() {
+        let text = format!("{MUFFINS}\n{}", MUFFINS.replace("Blueberry", "Raspberry"));
+        let titles: Vec<String> = parse_mealmaster(&text)
+            .into_iter()
+            .map(|draft| draft.recipe.title)
+            .collect();
+        assert_eq!(titles, vec!["Blueberry Muffins", "Raspberry Muffins"]);
+    }
+}
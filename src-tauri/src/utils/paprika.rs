@@ -0,0 +1,431 @@
+use base64::{engine::general_purpose::STANDARD, Engine as _};
+use flate2::{read::GzDecoder, write::GzEncoder, Compression};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::{
+    fs::{self, File},
+    io::{Read, Write},
+    path::Path,
+};
+use tauri::AppHandle;
+use url::Url;
+use zip::{write::FileOptions, ZipArchive, ZipWriter};
+
+use crate::{
+    error::DBError,
+    types::{
+        db::Database,
+        import::{ImportStatus, RecipeImportResult},
+        recipe::RecipeDetails,
+    },
+    utils::{
+        export::file_stem,
+        image::local_image_path,
+        import::save_imported_recipe,
+        parse::{parse_calories, parse_minutes, parse_servings},
+    },
+};
+
+/// A recipe as Paprika stores it, gzipped, in each file of a `.paprikarecipes` archive.
+#[derive(Default, Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+struct PaprikaRecipe {
+    #[serde(deserialize_with = "null_as_default")]
+    uid: String,
+    #[serde(deserialize_with = "null_as_default")]
+    name: String,
+    /// One ingredient per line
+    #[serde(deserialize_with = "null_as_default")]
+    ingredients: String,
+    /// One step per line
+    #[serde(deserialize_with = "null_as_default")]
+    directions: String,
+    #[serde(deserialize_with = "null_as_default")]
+    description: String,
+    #[serde(deserialize_with = "null_as_default")]
+    notes: String,
+    #[serde(deserialize_with = "null_as_default")]
+    nutritional_info: String,
+    #[serde(deserialize_with = "null_as_default")]
+    servings: String,
+    #[serde(deserialize_with = "null_as_default")]
+    prep_time: String,
+    #[serde(deserialize_with = "null_as_default")]
+    cook_time: String,
+    #[serde(deserialize_with = "null_as_default")]
+    total_time: String,
+    #[serde(deserialize_with = "null_as_default")]
+    difficulty: String,
+    #[serde(deserialize_with = "null_as_default")]
+    source: String,
+    #[serde(deserialize_with = "null_as_default")]
+    source_url: String,
+    image_url: Option<String>,
+    /// File name of the photo
+    photo: Option<String>,
+    /// Base64 encoded photo
+    photo_data: Option<String>,
+    photo_hash: Option<String>,
+    #[serde(deserialize_with = "null_as_default")]
+    categories: Vec<String>,
+    #[serde(deserialize_with = "null_as_default")]
+    rating: i32,
+    #[serde(deserialize_with = "null_as_default")]
+    created: String,
+    #[serde(deserialize_with = "null_as_default")]
+    hash: String,
+}
+
+/// Reads `null` as the field's default. Paprika writes `null` for some of the fields it
+/// leaves empty, which `#[serde(default)]` only covers when they are missing.
+fn null_as_default<'de, D, T>(deserializer: D) -> Result<T, D::Error>
+where
+    D: serde::Deserializer<'de>,
+    T: Default + Deserialize<'de>,
+{
+    Ok(Option::<T>::deserialize(deserializer)?.unwrap_or_default())
+}
+
+/// Largest recipe read from an archive once decompressed, leaving room for a photo as large
+/// as any that is imported, base64 encoded within the recipe
+const MAX_RECIPE_BYTES: u64 = 32_000_000;
+
+fn lines(text: &str) -> Option<Vec<String>> {
+    let lines: Vec<String> = text
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty())
+        .map(str::to_string)
+        .collect();
+    match lines.is_empty() {
+        true => None,
+        false => Some(lines),
+    }
+}
+
+fn non_empty(text: &str) -> Option<String> {
+    let text = text.trim();
+    match text.is_empty() {
+        true => None,
+        false => Some(text.to_string()),
+    }
+}
+
+/// Imports every recipe of a Paprika `.paprikarecipes` archive, reporting for each recipe
+/// whether it was imported and what couldn't be carried over.
+pub fn import_paprika(
+    db: &Database,
+    archive_path: &Path,
+    app: AppHandle,
+) -> Result<Vec<RecipeImportResult>, DBError> {
+    let mut archive = ZipArchive::new(File::open(archive_path)?)?;
+    let mut results = Vec::new();
+
+    for index in 0..archive.len() {
+        let mut file = archive.by_index(index)?;
+        if file.is_dir() {
+            continue;
+        }
+        let file_name = file.name().to_string();
+
+        results.push(match read_recipe(&mut file) {
+            Ok(paprika) => import_recipe(db, paprika, &file_name, app.clone()),
+            Err(error) => RecipeImportResult {
+                title: file_name,
+                status: ImportStatus::Failed,
+                recipe_id: None,
+                messages: vec![format!("Not a Paprika recipe: {error}")],
+            },
+        });
+    }
+
+    Ok(results)
+}
+
+/// Reads a recipe from a gzipped file of an archive. Only so much is decompressed, as the
+/// archive may not come from Paprika.
+fn read_recipe(file: impl Read) -> Result<PaprikaRecipe, String> {
+    let mut json = Vec::new();
+    GzDecoder::new(file)
+        .take(MAX_RECIPE_BYTES + 1)
+        .read_to_end(&mut json)
+        .map_err(|error| error.to_string())?;
+    if json.len() as u64 > MAX_RECIPE_BYTES {
+        return Err(format!("larger than {} MB", MAX_RECIPE_BYTES / 1_000_000));
+    }
+    serde_json::from_slice(&json).map_err(|error| error.to_string())
+}
+
+fn import_recipe(
+    db: &Database,
+    paprika: PaprikaRecipe,
+    file_name: &str,
+    app: AppHandle,
+) -> RecipeImportResult {
+    let mut result = RecipeImportResult {
+        title: non_empty(&paprika.name).unwrap_or_else(|| file_name.to_string()),
+        status: ImportStatus::Failed,
+        recipe_id: None,
+        messages: Vec::new(),
+    };
+    if non_empty(&paprika.name).is_none() {
+        result.messages.push("Recipe has no name".to_string());
+        return result;
+    }
+
+    let recipe = to_recipe(&paprika, &mut result.messages);
+    let mut photo = None;
+    if let Some(photo_data) = &paprika.photo_data {
+        match STANDARD.decode(photo_data.trim()) {
+            Ok(bytes) => photo = Some(bytes),
+            Err(_) => result
+                .messages
+                .push("Photo couldn't be decoded".to_string()),
+        }
+    }
+
+    save_imported_recipe(
+        db,
+        recipe,
+        &paprika.categories,
+        photo.as_deref(),
+        app,
+        &mut result,
+    );
+    result
+}
+
+/// Maps the fields of a Paprika recipe onto a recipe, noting in `messages` what couldn't be
+/// mapped. The photo is left to the caller.
+fn to_recipe(paprika: &PaprikaRecipe, messages: &mut Vec<String>) -> RecipeDetails {
+    let servings = match non_empty(&paprika.servings) {
+        Some(servings) => parse_servings(&servings).unwrap_or_else(|| {
+            messages.push(format!("Servings \"{servings}\" weren't understood"));
+            0
+        }),
+        None => 0,
+    };
+
+    let time = match non_empty(&paprika.total_time) {
+        Some(total_time) => parse_minutes(&total_time),
+        None => match (
+            parse_minutes(&paprika.prep_time),
+            parse_minutes(&paprika.cook_time),
+        ) {
+            (None, None) => None,
+            (prep, cook) => Some(prep.unwrap_or(0) + cook.unwrap_or(0)),
+        },
+    };
+    let has_time = [&paprika.total_time, &paprika.prep_time, &paprika.cook_time]
+        .iter()
+        .any(|time| non_empty(time).is_some());
+    if time.is_none() && has_time {
+        messages.push("Cooking time wasn't understood".to_string());
+    }
+
+    let calories = match non_empty(&paprika.nutritional_info) {
+        Some(nutrition) => {
+            let calories = parse_calories(&nutrition);
+            messages.push(match calories {
+                Some(_) => "Nutrition other than calories wasn't imported".to_string(),
+                None => "Nutritional info wasn't imported".to_string(),
+            });
+            calories.unwrap_or(0)
+        }
+        None => 0,
+    };
+
+    for (field, value) in [
+        ("Description", &paprika.description),
+        ("Notes", &paprika.notes),
+        ("Difficulty", &paprika.difficulty),
+    ] {
+        if non_empty(value).is_some() {
+            messages.push(format!("{field} wasn't imported"));
+        }
+    }
+    if paprika.rating > 0 {
+        messages.push(format!("Rating of {} wasn't imported", paprika.rating));
+    }
+
+    let source = non_empty(&paprika.source).or_else(|| {
+        Url::parse(&paprika.source_url)
+            .ok()
+            .and_then(|url| url.host_str().map(str::to_string))
+    });
+
+    RecipeDetails {
+        image: paprika.image_url.clone().unwrap_or_default(),
+        url: non_empty(&paprika.source_url),
+        servings,
+        time: time.unwrap_or(0),
+        calories,
+        source,
+        ingredients: lines(&paprika.ingredients),
+        instructions: lines(&paprika.directions),
+        title: paprika.name.trim().to_string(),
+        date_added: non_empty(&paprika.created),
+        ..Default::default()
+    }
+}
+
+/// Writes recipes to a `.paprikarecipes` archive Paprika can import, with their tags as
+/// categories and downloaded images embedded as photos.
+pub fn export_paprika(
+    db: &Database,
+    recipes: &[RecipeDetails],
+    archive_path: &Path,
+    app: AppHandle,
+) -> Result<(), DBError> {
+    let mut zip = ZipWriter::new(File::create(archive_path)?);
+
+    for recipe in recipes {
+        let tags = db.get_tags_for_recipe(&recipe.id.unwrap_or_default())?;
+        let mut paprika = PaprikaRecipe {
+            uid: uuid::Uuid::new_v4().to_string().to_uppercase(),
+            name: recipe.title.clone(),
+            ingredients: recipe.ingredients.clone().unwrap_or_default().join("\n"),
+            directions: recipe.instructions.clone().unwrap_or_default().join("\n"),
+            source: recipe.source.clone().unwrap_or_default(),
+            source_url: recipe.url.clone().unwrap_or_default(),
+            categories: tags.into_iter().map(|tag| tag.name).collect(),
+            created: recipe.date_added.clone().unwrap_or_default(),
+            ..Default::default()
+        };
+        if recipe.servings > 0 {
+            paprika.servings = recipe.servings.to_string();
+        }
+        if recipe.time > 0 {
+            paprika.total_time = format!("{} mins", recipe.time);
+        }
+        if recipe.calories > 0 {
+            paprika.nutritional_info = format!("Calories: {}", recipe.calories);
+        }
+
+        match local_image_path(&recipe.image, app.clone())? {
+            Some(image_path) => {
+                let bytes = fs::read(&image_path)?;
+                paprika.photo = image_path
+                    .file_name()
+                    .map(|name| name.to_string_lossy().into_owned());
+                paprika.photo_hash = Some(format!("{:x}", Sha256::digest(&bytes)));
+                paprika.photo_data = Some(STANDARD.encode(bytes));
+            }
+            None if !recipe.image.is_empty() => paprika.image_url = Some(recipe.image.clone()),
+            None => {}
+        }
+        // Paprika uses the hash to tell whether a recipe changed
+        paprika.hash = format!("{:x}", Sha256::digest(serde_json::to_vec(&paprika)?));
+
+        let mut gzipped = GzEncoder::new(Vec::new(), Compression::default());
+        serde_json::to_writer(&mut gzipped, &paprika)?;
+        zip.start_file(
+            format!("{}.paprikarecipe", file_stem(recipe)),
+            FileOptions::default(),
+        )?;
+        zip.write_all(&gzipped.finish()?)?;
+    }
+
+    zip.finish()?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn gzip(bytes: &[u8]) -> Vec<u8> {
+        let mut gzipped = GzEncoder::new(Vec::new(), Compression::default());
+        gzipped.write_all(bytes).unwrap();
+        gzipped.finish().unwrap()
+    }
+
+    #[test]
+    fn reads_a_recipe_from_a_gzipped_entry() {
+        let json = r#"{
+            "uid": "8F2C1A6E-3B7D-4E0A-9C55-1D2E3F4A5B6C",
+            "name": "Banana Bread ",
+            "ingredients": "3 ripe bananas\n\n1/3 cup melted butter\n1 1/2 cups flour",
+            "directions": "Mash the bananas.\nStir in the butter and flour.\nBake for an hour.",
+            "notes": "Freezes well",
+            "nutritional_info": "Calories: 196 per slice",
+            "servings": "Makes 8 slices",
+            "prep_time": "10 mins",
+            "cook_time": "1 hr",
+            "total_time": "",
+            "source": "",
+            "source_url": "https://www.example.com/banana-bread",
+            "photo_data": null,
+            "categories": ["Baking", "Breakfast"],
+            "rating": 4,
+            "created": "2021-03-14 09:26:53"
+        }"#;
+        let paprika = read_recipe(gzip(json.as_bytes()).as_slice()).unwrap();
+        assert_eq!(paprika.categories, vec!["Baking", "Breakfast"]);
+
+        let mut messages = Vec::new();
+        let recipe = to_recipe(&paprika, &mut messages);
+        assert_eq!(recipe.title, "Banana Bread");
+        assert_eq!(recipe.servings, 8);
+        assert_eq!(recipe.time, 70);
+        assert_eq!(recipe.calories, 196);
+        assert_eq!(recipe.source.as_deref(), Some("www.example.com"));
+        assert_eq!(recipe.ingredients.map(|lines| lines.len()), Some(3));
+        assert_eq!(
+            recipe.instructions.unwrap().last().map(String::as_str),
+            Some("Bake for an hour.")
+        );
+        assert_eq!(recipe.date_added.as_deref(), Some("2021-03-14 09:26:53"));
+        assert_eq!(
+            messages,
+            vec![
+                "Nutrition other than calories wasn't imported",
+                "Notes wasn't imported",
+                "Rating of 4 wasn't imported",
+            ]
+        );
+    }
+
+    #[test]
+    fn reads_null_fields_as_empty() {
+        let json = r#"{
+            "uid": "0B6E2C1F-8A4D-4F3B-B2E9-7C1D5A6F8E90",
+            "name": "Plain Rice",
+            "ingredients": "1 cup rice\n2 cups water",
+            "directions": null,
+            "description": null,
+            "notes": null,
+            "nutritional_info": null,
+            "servings": null,
+            "difficulty": null,
+            "source": null,
+            "source_url": null,
+            "image_url": null,
+            "categories": null,
+            "rating": null
+        }"#;
+        let paprika = read_recipe(gzip(json.as_bytes()).as_slice()).unwrap();
+        assert_eq!(paprika.notes, "");
+        assert!(paprika.categories.is_empty());
+
+        let mut messages = Vec::new();
+        let recipe = to_recipe(&paprika, &mut messages);
+        assert_eq!(recipe.title, "Plain Rice");
+        assert_eq!(recipe.ingredients.map(|lines| lines.len()), Some(2));
+        assert_eq!(recipe.instructions, None);
+        assert_eq!(recipe.url, None);
+        assert!(messages.is_empty());
+    }
+
+    #[test]
+    fn rejects_entries_that_arent_paprika_recipes() {
+        assert!(read_recipe(&b"{\"name\": \"not gzipped\"}"[..]).is_err());
+        assert!(read_recipe(gzip(b"<html></html>").as_slice()).is_err());
+
+        let oversized = vec![b' '; MAX_RECIPE_BYTES as usize + 1];
+        assert_eq!(
+            read_recipe(gzip(&oversized).as_slice()).unwrap_err(),
+            "larger than 32 MB"
+        );
+    }
+}
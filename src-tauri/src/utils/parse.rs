@@ -0,0 +1,305 @@
+/// A number found in text, with the byte offsets of where it starts and ends.
+struct Number {
+    start: usize,
+    value: f64,
+    end: usize,
+}
+
+/// Numbers in `text`. Fractions such as `1/2` and `1 1/2` are read as their value.
+fn numbers(text: &str) -> Vec<Number> {
+    let bytes = text.as_bytes();
+    let digits_end = |mut index: usize| {
+        while index < bytes.len() && bytes[index].is_ascii_digit() {
+            index += 1;
+        }
+        index
+    };
+    // The value and end of a fraction starting at `start`, if there is one
+    let fraction = |start: usize| -> Option<(f64, usize)> {
+        let slash = digits_end(start);
+        if slash == start || bytes.get(slash) != Some(&b'/') {
+            return None;
+        }
+        let end = digits_end(slash + 1);
+        let numerator: f64 = text[start..slash].parse().ok()?;
+        let denominator: f64 = text.get(slash + 1..end)?.parse().ok()?;
+        match denominator > 0.0 {
+            true => Some((numerator / denominator, end)),
+            false => None,
+        }
+    };
+
+    let mut numbers = Vec::new();
+    let mut index = 0;
+    while index < bytes.len() {
+        if !bytes[index].is_ascii_digit() {
+            index += 1;
+            continue;
+        }
+
+        let start = index;
+        let (mut value, mut end) = match fraction(start) {
+            Some(fraction) => fraction,
+            None => {
+                let mut end = digits_end(start);
+                if bytes.get(end) == Some(&b'.')
+                    && bytes.get(end + 1).map_or(false, u8::is_ascii_digit)
+                {
+                    end = digits_end(end + 1);
+                }
+                (text[start..end].parse().unwrap_or(0.0), end)
+            }
+        };
+        if bytes.get(end) == Some(&b' ') {
+            if let Some((fraction, fraction_end)) = fraction(end + 1) {
+                value += fraction;
+                end = fraction_end;
+            }
+        }
+
+        numbers.push(Number { start, value, end });
+        index = end;
+    }
+    numbers
+}
+
+/// Reads the number of servings from a yield such as `4`, `Serves 4-6` or `Makes 12 cookies`,
+/// taking the first number.
+pub fn parse_servings(text: &str) -> Option<i32> {
+    numbers(text)
+        .first()
+        .map(|number| number.value.round() as i32)
+        .filter(|servings| *servings > 0)
+}
+
+/// Reads a duration in minutes from an ISO 8601 duration such as `PT1H30M`, or from text such
+/// as `1 hr 30 mins`, `1 1/2 hours` or `45`. A number without a unit is read as minutes.
+pub fn parse_minutes(text: &str) -> Option<i32> {
+    let text = text.trim().to_lowercase();
+    let minutes = match text.strip_prefix('p') {
+        Some(duration) => parse_iso_duration(duration)?,
+        None => numbers(&text)
+            .iter()
+            .map(|number| {
+                let unit = text[number.end..].trim_start();
+                let per_unit = match unit.chars().next() {
+                    Some('d') => 24.0 * 60.0,
+                    Some('h') => 60.0,
+                    Some('s') if !unit.starts_with("serv") => 1.0 / 60.0,
+                    _ => 1.0,
+                };
+                number.value * per_unit
+            })
+            .sum(),
+    };
+
+    match minutes.round() as i32 {
+        0 => None,
+        minutes => Some(minutes),
+    }
+}
+
+/// Minutes in the part of an ISO 8601 duration after its `P`.
+fn parse_iso_duration(duration: &str) -> Option<f64> {
+    let mut minutes = 0.0;
+    let mut number = String::new();
+    let mut in_time = false;
+    for c in duration.chars() {
+        match c {
+            '0'..='9' | '.' => number.push(c),
+            't' => in_time = true,
+            unit => {
+                let value: f64 = number.parse().ok()?;
+                number.clear();
+                minutes += value
+                    * match (unit, in_time) {
+                        ('d', false) => 24.0 * 60.0,
+                        ('h', true) => 60.0,
+                        ('m', true) => 1.0,
+                        ('s', true) => 1.0 / 60.0,
+                        _ => return None,
+                    };
+            }
+        }
+    }
+    Some(minutes)
+}
+
+/// Reads a calorie count from nutrition text such as `Calories: 350` or `350 kcal`.
+pub fn parse_calories(text: &str) -> Option<i32> {
+    let text = text.to_lowercase();
+    let label = text.find("calorie").or_else(|| text.find("kcal"))?;
+    let numbers = numbers(&text);
+
+    // `350 calories` has its number right before the label, `Calories: 350` after it
+    let number = numbers
+        .iter()
+        .rev()
+        .find(|number| number.end <= label && text[number.end..label].trim().is_empty())
+        .or_else(|| numbers.iter().find(|number| number.start > label))?;
+    Some(number.value.round() as i32)
+}
+
+/// Units an ingredient's quantity may be given in, in their singular form.
+const UNITS: &[&str] = &[
+    "cup",
+    "c",
+    "tablespoon",
+    "tbsp",
+    "tbs",
+    "tb",
+    "teaspoon",
+    "tsp",
+    "ts",
+    "gram",
+    "g",
+    "kilogram",
+    "kg",
+    "milligram",
+    "mg",
+    "liter",
+    "litre",
+    "l",
+    "milliliter",
+    "millilitre",
+    "ml",
+    "cl",
+    "dl",
+    "ounce",
+    "oz",
+    "fl oz",
+    "pound",
+    "lb",
+    "pint",
+    "pt",
+    "quart",
+    "qt",
+    "gallon",
+    "gal",
+    "pinch",
+    "dash",
+    "handful",
+    "clove",
+    "can",
+    "tin",
+    "jar",
+    "package",
+    "pkg",
+    "packet",
+    "stick",
+    "slice",
+    "sprig",
+    "bunch",
+    "head",
+    "piece",
+    "drop",
+];
+
+/// An ingredient line split into its parts.
+#[derive(Default, Debug, Clone, PartialEq)]
+pub struct IngredientParts {
+    /// The quantity as written, such as `2`, `1/2` or `2-3`
+    pub quantity: Option<String>,
+    /// The quantity as a number, the lower bound for ranges
+    pub amount: Option<f64>,
+    pub unit: Option<String>,
+    pub name: String,
+    /// Preparation following the name, such as `finely chopped` in `1 onion, finely chopped`
+    pub note: Option<String>,
+}
+
+/// Splits an ingredient line such as `1 1/2 cups flour, sifted` into its quantity, unit, name
+/// and preparation note. Lines without a leading quantity are all name.
+pub fn parse_ingredient(line: &str) -> IngredientParts {
+    let line = line.trim();
+    let numbers = numbers(line);
+    let mut parts = IngredientParts::default();
+
+    let mut rest = line;
+    if let Some(first) = numbers.first().filter(|number| number.start == 0) {
+        // A range such as `2-3` or `2 to 3` reads as a single quantity
+        let end = numbers
+            .get(1)
+            .filter(|second| {
+                let between = line[first.end..second.start].trim();
+                between == "-" || between == "to"
+            })
+            .map_or(first.end, |second| second.end);
+        parts.quantity = Some(line[..end].to_string());
+        parts.amount = Some(first.value);
+        rest = line[end..].trim_start();
+
+        // Units are ASCII, so they're matched without lowercasing `rest`, which could change
+        // the length of the text after them
+        let unit = UNITS
+            .iter()
+            .filter_map(|unit| {
+                let after = strip_prefix_ignore_case(rest, unit)?;
+                let after = strip_prefix_ignore_case(after, "es")
+                    .or_else(|| strip_prefix_ignore_case(after, "s"))
+                    .filter(|_| unit.len() > 1)
+                    .unwrap_or(after);
+                let after = after.strip_prefix('.').unwrap_or(after);
+                match after.chars().next().map_or(true, |c| !c.is_alphanumeric()) {
+                    true => Some(rest.len() - after.len()),
+                    false => None,
+                }
+            })
+            .max();
+        if let Some(unit_end) = unit {
+            parts.unit = Some(rest[..unit_end].trim_end_matches('.').to_string());
+            rest = rest[unit_end..].trim_start();
+            rest = rest.strip_prefix("of ").unwrap_or(rest);
+        }
+    }
+
+    let (name, note) = match rest.find(',') {
+        Some(comma) => (&rest[..comma], Some(rest[comma + 1..].trim())),
+        None => (rest, None),
+    };
+    parts.name = name.trim().to_string();
+    parts.note = note.filter(|note| !note.is_empty()).map(str::to_string);
+    parts
+}
+
+/// Strips an ASCII `prefix` from the start of `text`, ignoring case.
+fn strip_prefix_ignore_case<'a>(text: &'a str, prefix: &str) -> Option<&'a str> {
+    text.get(..prefix.len())
+        .filter(|start| start.eq_ignore_ascii_case(prefix))
+        .map(|_| &text[prefix.len()..])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_quantity_unit_name_and_note() {
+        let parts = parse_ingredient("1 1/2 Cups flour, sifted");
+        assert_eq!(parts.quantity.as_deref(), Some("1 1/2"));
+        assert_eq!(parts.amount, Some(1.5));
+        assert_eq!(parts.unit.as_deref(), Some("Cups"));
+        assert_eq!(parts.name, "flour");
+        assert_eq!(parts.note.as_deref(), Some("sifted"));
+    }
+
+    #[test]
+    fn keeps_non_ascii_text_after_the_unit() {
+        let parts = parse_ingredient("1 c İİİ");
+        assert_eq!(parts.unit.as_deref(), Some("c"));
+        assert_eq!(parts.name, "İİİ");
+
+        let parts = parse_ingredient("2 tbsp crème fraîche");
+        assert_eq!(parts.unit.as_deref(), Some("tbsp"));
+        assert_eq!(parts.name, "crème fraîche");
+    }
+
+    #[test]
+    fn leaves_a_word_starting_like_a_unit_in_the_name() {
+        let parts = parse_ingredient("2 cloves garlic");
+        assert_eq!(parts.unit.as_deref(), Some("cloves"));
+        let parts = parse_ingredient("3 large eggs");
+        assert_eq!(parts.unit, None);
+        assert_eq!(parts.name, "large eggs");
+    }
+}
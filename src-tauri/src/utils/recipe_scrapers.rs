@@ -1,6 +1,8 @@
 use pyo3::prelude::*;
 
-pub fn scrape_recipe_from_url(url: &str) -> PyResult<String> {
+/// Scrapes a recipe page with recipe-scrapers, returning its data as JSON. Sites it has no
+/// scraper for are only read from their schema.org markup in `wild_mode`.
+pub fn scrape_recipe_from_url(url: &str, wild_mode: bool) -> PyResult<String> {
     Python::with_gil(|py| {
         let get_recipe_data = PyModule::from_code(
             py,
@@ -8,8 +10,8 @@ pub fn scrape_recipe_from_url(url: &str) -> PyResult<String> {
 import json
 from recipe_scrapers import scrape_me
 
-def scrape_url(url):
-    results = scrape_me(url)
+def scrape_url(url, wild_mode):
+    results = scrape_me(url, wild_mode=wild_mode)
     json_results = results.to_json()
     json_string = json.dumps(json_results)
     return json_string
@@ -21,7 +23,7 @@ def scrape_url(url):
         // pass arguments as rust tuple
         let recipe_result: String = get_recipe_data
             .getattr("scrape_url")?
-            .call1((url,))?
+            .call1((url, wild_mode))?
             .extract()?;
 
         Ok(recipe_result)
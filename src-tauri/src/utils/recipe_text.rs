@@ -0,0 +1,357 @@
+use url::Url;
+
+use crate::{
+    types::{import::RecipeDraft, recipe::RecipeDetails},
+    utils::parse::{parse_calories, parse_minutes, parse_servings},
+};
+
+/// Characters an ingredient line may start with besides digits.
+const FRACTIONS: &str = "¼½¾⅓⅔⅛⅜⅝⅞";
+
+/// Lines ingredients and steps are commonly written in lists with.
+const BULLETS: &[&str] = &["- ", "* ", "• ", "· ", "– "];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Section {
+    Ingredients,
+    Instructions,
+    Notes,
+}
+
+/// The section a heading such as `Ingredients:` or `## Method` starts, if the line is one.
+fn section_heading(line: &str) -> Option<Section> {
+    let heading = line
+        .trim_matches(|c: char| "#*=_-:".contains(c) || c.is_whitespace())
+        .to_lowercase();
+    match heading.as_str() {
+        "ingredients" | "ingredient list" | "you will need" | "you'll need" | "what you need" => {
+            Some(Section::Ingredients)
+        }
+        "instructions" | "directions" | "method" | "preparation" | "steps" | "procedure"
+        | "how to make it" => Some(Section::Instructions),
+        "notes" | "note" | "tips" | "cook's notes" => Some(Section::Notes),
+        _ => None,
+    }
+}
+
+/// Removes a list bullet from the start of a line.
+pub fn strip_bullet(line: &str) -> &str {
+    BULLETS
+        .iter()
+        .find_map(|bullet| line.strip_prefix(bullet))
+        .unwrap_or(line)
+        .trim_start()
+}
+
+/// Removes a step number such as `1.`, `2)` or `Step 3:` from the start of a line, returning
+/// the rest if the line has one.
+pub fn strip_step_number(line: &str) -> Option<&str> {
+    let (line, has_step) = match line.get(..4) {
+        Some(word) if word.eq_ignore_ascii_case("step") => (line[4..].trim_start(), true),
+        _ => (line, false),
+    };
+    let digits = line
+        .find(|c: char| !c.is_ascii_digit())
+        .unwrap_or(line.len());
+    if digits == 0 {
+        return None;
+    }
+
+    let rest = &line[digits..];
+    let rest = match rest.chars().next() {
+        Some('.' | ')' | ':') => &rest[1..],
+        _ if has_step => rest,
+        _ => return None,
+    };
+    match rest.chars().next() {
+        None => Some(rest),
+        Some(c) if c.is_whitespace() => Some(rest.trim_start()),
+        _ => None,
+    }
+}
+
+fn ends_sentence(text: &str) -> bool {
+    text.trim_end().ends_with(['.', '!', '?', ':', ')'])
+}
+
+/// Groups the lines of a method into steps. Numbered lines and blank lines start a new step,
+/// as does a line after one ending a sentence. Other lines continue the step before them, as
+/// in text wrapped to a fixed width.
+pub fn split_steps(lines: &[&str]) -> Vec<String> {
+    let mut steps: Vec<String> = Vec::new();
+    let mut current: Option<String> = None;
+
+    for line in lines {
+        let line = line.trim();
+        if line.is_empty() {
+            steps.extend(current.take());
+            continue;
+        }
+
+        match strip_step_number(line) {
+            Some(rest) => {
+                steps.extend(current.take());
+                current = Some(rest.to_string());
+            }
+            None => match &mut current {
+                Some(step) if !ends_sentence(step) => {
+                    step.push(' ');
+                    step.push_str(line);
+                }
+                _ => {
+                    steps.extend(current.take());
+                    current = Some(strip_bullet(line).to_string());
+                }
+            },
+        }
+    }
+    steps.extend(current);
+    steps.retain(|step| !step.is_empty());
+    steps
+}
+
+/// Whether a line starts the way ingredients are listed, with a quantity or a bullet.
+fn starts_like_ingredient(line: &str) -> bool {
+    let starts_with_quantity = line
+        .chars()
+        .next()
+        .map_or(false, |c| c.is_ascii_digit() || FRACTIONS.contains(c));
+    (starts_with_quantity || strip_bullet(line).len() < line.len())
+        && strip_step_number(line).is_none()
+}
+
+/// Whether a line reads like an ingredient, such as `2 cups flour`, `- salt` or `Salt and
+/// pepper`, rather than a step or a sentence of prose.
+fn is_ingredient_line(line: &str) -> bool {
+    let words = line.split_whitespace().count();
+    let limit = match starts_like_ingredient(line) {
+        true => 12,
+        false => 5,
+    };
+    words <= limit && !ends_sentence(line) && strip_step_number(line).is_none()
+}
+
+/// Reads a detail such as `Serves 4` or `Prep time: 10 minutes` into the recipe, returning
+/// whether the line was one.
+fn read_detail(
+    line: &str,
+    recipe: &mut RecipeDetails,
+    times: &mut (Option<i32>, Option<i32>),
+) -> bool {
+    let (key, value) = match line.split_once(':') {
+        Some((key, value)) => (key.trim().to_lowercase(), value.trim()),
+        None => {
+            let lowercase = line.to_lowercase();
+            let is_yield = ["serves ", "makes ", "yield "]
+                .iter()
+                .any(|prefix| lowercase.starts_with(prefix));
+            match is_yield && line.split_whitespace().count() <= 4 {
+                true => ("servings".to_string(), line),
+                false => return false,
+            }
+        }
+    };
+
+    match key.as_str() {
+        "servings" | "serves" | "yield" | "yields" | "makes" => {
+            recipe.servings = parse_servings(value).unwrap_or(recipe.servings)
+        }
+        "time" | "total time" | "ready in" => {
+            recipe.time = parse_minutes(value).unwrap_or(recipe.time)
+        }
+        "prep time" | "prep" | "preparation time" => times.0 = parse_minutes(value),
+        "cook time" | "cooking time" | "cook" => times.1 = parse_minutes(value),
+        "calories" | "nutrition" => {
+            recipe.calories =
+                parse_calories(&format!("calories: {value}")).unwrap_or(recipe.calories)
+        }
+        "source" | "from" | "url" => match Url::parse(value) {
+            Ok(url) => {
+                recipe.url = Some(value.to_string());
+                recipe.source = url.host_str().map(str::to_string);
+            }
+            Err(_) => recipe.source = Some(value.to_string()),
+        },
+        _ => return false,
+    }
+    true
+}
+
+/// Drafts a recipe from pasted text, such as a recipe emailed around as plain text. The title
+/// is taken from the first line, ingredients and steps from their headings when the text has
+/// them, or else from how the lines read.
+pub fn parse_recipe_text(text: &str) -> RecipeDraft {
+    let mut draft = RecipeDraft::default();
+    let recipe = &mut draft.recipe;
+    let mut times = (None, None);
+
+    let lines: Vec<&str> = text.lines().map(str::trim).collect();
+    let mut ingredients = Vec::new();
+    let mut method = Vec::new();
+    let mut skipped = Vec::new();
+    let mut section = None;
+
+    for line in lines {
+        if let Some(heading) = section_heading(line) {
+            section = Some(heading);
+            continue;
+        }
+        if line.is_empty() {
+            if section == Some(Section::Instructions) {
+                method.push(line);
+            }
+            continue;
+        }
+        if section != Some(Section::Instructions) && read_detail(line, recipe, &mut times) {
+            continue;
+        }
+        if Url::parse(line).map_or(false, |url| url.has_host()) && recipe.url.is_none() {
+            recipe.url = Some(line.to_string());
+            recipe.source = Url::parse(line)
+                .ok()
+                .and_then(|url| url.host_str().map(str::to_string));
+            continue;
+        }
+
+        match section {
+            Some(Section::Ingredients) => ingredients.push(strip_bullet(line).to_string()),
+            Some(Section::Instructions) => method.push(line),
+            Some(Section::Notes) => skipped.push(line),
+            // Before any heading, the first line is the title, then ingredient lines start the
+            // ingredients and anything reading like a step starts the method
+            None if recipe.title.is_empty()
+                && ingredients.is_empty()
+                && !starts_like_ingredient(line) =>
+            {
+                let title = line.trim_start_matches('#').trim();
+                let title = ["title:", "recipe:"]
+                    .iter()
+                    .find_map(|prefix| {
+                        title
+                            .get(..prefix.len())
+                            .filter(|start| start.eq_ignore_ascii_case(prefix))
+                            .map(|_| title[prefix.len()..].trim())
+                    })
+                    .unwrap_or(title);
+                recipe.title = title.to_string();
+            }
+            None if is_ingredient_line(line) && method.is_empty() => {
+                ingredients.push(strip_bullet(line).to_string())
+            }
+            None if ingredients.is_empty() && strip_step_number(line).is_none() => {
+                skipped.push(line)
+            }
+            None => {
+                section = Some(Section::Instructions);
+                method.push(line);
+            }
+        }
+    }
+
+    if recipe.time == 0 && times != (None, None) {
+        recipe.time = times.0.unwrap_or(0) + times.1.unwrap_or(0);
+    }
+    let steps = split_steps(&method);
+    if recipe.title.is_empty() {
+        draft.messages.push("No title was found".to_string());
+    }
+    if ingredients.is_empty() {
+        draft.messages.push("No ingredients were found".to_string());
+    }
+    if steps.is_empty() {
+        draft.messages.push("No steps were found".to_string());
+    }
+    if !skipped.is_empty() {
+        draft
+            .messages
+            .push(format!("Left out: {}", skipped.join(" ")));
+    }
+
+    let recipe = &mut draft.recipe;
+    recipe.ingredients = Some(ingredients).filter(|ingredients| !ingredients.is_empty());
+    recipe.instructions = Some(steps).filter(|steps| !steps.is_empty());
+    draft
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reads_a_recipe_with_headings() {
+        let draft = parse_recipe_text(
+            "Lemon Garlic Chicken
+Serves 4
+Prep time: 10 minutes
+Cook time: 25 minutes
+
+Ingredients:
+- 4 chicken thighs
+- 2 tbsp olive oil
+• 3 cloves garlic, minced
+* Juice of 1 lemon
+
+Method:
+1. Heat the oven to 200C.
+2) Rub the chicken with the oil, garlic
+   and lemon juice.
+Step 3: Roast for 25 minutes.
+
+Notes:
+Goes well with rice.",
+        );
+        let recipe = &draft.recipe;
+        assert_eq!(recipe.title, "Lemon Garlic Chicken");
+        assert_eq!(recipe.servings, 4);
+        assert_eq!(recipe.time, 35);
+        assert_eq!(
+            recipe.ingredients.as_deref(),
+            Some(
+                &[
+                    "4 chicken thighs".to_string(),
+                    "2 tbsp olive oil".to_string(),
+                    "3 cloves garlic, minced".to_string(),
+                    "Juice of 1 lemon".to_string(),
+                ][..]
+            )
+        );
+        assert_eq!(
+            recipe.instructions.as_deref(),
+            Some(
+                &[
+                    "Heat the oven to 200C.".to_string(),
+                    "Rub the chicken with the oil, garlic and lemon juice.".to_string(),
+                    "Roast for 25 minutes.".to_string(),
+                ][..]
+            )
+        );
+        assert_eq!(draft.messages, vec!["Left out: Goes well with rice."]);
+    }
+
+    #[test]
+    fn reads_a_recipe_without_headings() {
+        let draft = parse_recipe_text(
+            "Simple Pancakes
+https://www.example.com/pancakes
+1 cup flour
+1 cup milk
+1 egg
+Whisk everything together until smooth.
+Cook on a hot pan until golden on both sides.",
+        );
+        let recipe = &draft.recipe;
+        assert_eq!(recipe.title, "Simple Pancakes");
+        assert_eq!(recipe.source.as_deref(), Some("www.example.com"));
+        assert_eq!(recipe.ingredients.as_ref().map(Vec::len), Some(3));
+        assert_eq!(recipe.instructions.as_ref().map(Vec::len), Some(2));
+        assert!(draft.messages.is_empty());
+    }
+
+    #[test]
+    fn strips_step_numbers_only() {
+        assert_eq!(strip_step_number("12. Serve"), Some("Serve"));
+        assert_eq!(strip_step_number("step 2 Serve"), Some("Serve"));
+        assert_eq!(strip_step_number("2 eggs"), None);
+        assert_eq!(strip_step_number("1.5 cups milk"), None);
+    }
+}
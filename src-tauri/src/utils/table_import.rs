@@ -0,0 +1,543 @@
+use serde_json::Value;
+use std::{collections::HashMap, fs, path::Path};
+use url::Url;
+
+use crate::{
+    error::DBError,
+    types::{
+        db::Database,
+        import::{
+            FieldMapping, ImportStatus, ListSplit, RecipeField, TableImportReport, TableRowResult,
+        },
+        recipe::RecipeDetails,
+    },
+    utils::{
+        parse::{parse_calories, parse_minutes, parse_servings},
+        recipe_text::{strip_bullet, strip_step_number},
+    },
+};
+
+/// The value of a column in one row of a table.
+#[derive(Debug, Clone, PartialEq)]
+enum Cell {
+    Text(String),
+    /// A JSON array, already split into items
+    List(Vec<String>),
+}
+
+impl Cell {
+    fn text(&self) -> String {
+        match self {
+            Cell::Text(text) => text.trim().to_string(),
+            Cell::List(items) => items.join("\n"),
+        }
+    }
+
+    fn items(&self, split: ListSplit) -> Vec<String> {
+        match self {
+            Cell::Text(text) => split_list(text, split),
+            Cell::List(items) => items
+                .iter()
+                .map(|item| item.trim().to_string())
+                .filter(|item| !item.is_empty())
+                .collect(),
+        }
+    }
+
+    fn is_empty(&self) -> bool {
+        match self {
+            Cell::Text(text) => text.trim().is_empty(),
+            Cell::List(items) => items.is_empty(),
+        }
+    }
+}
+
+/// A table's column names, in the order they first appear, and its rows.
+type Table = (Vec<String>, Vec<HashMap<String, Cell>>);
+
+/// Reads the column names of a CSV, TSV or JSON file, to map them to recipe fields.
+pub fn read_table_columns(path: &Path) -> Result<Vec<String>, DBError> {
+    Ok(read_table(path)?.0)
+}
+
+/// Reads a CSV or TSV file with a header row, or a JSON file holding an array of objects,
+/// either on its own or under a `recipes` key. Nested JSON objects are flattened into columns
+/// named by their keys joined with `.`.
+fn read_table(path: &Path) -> Result<Table, DBError> {
+    let extension = path
+        .extension()
+        .map(|extension| extension.to_string_lossy().to_lowercase());
+    match extension.as_deref() {
+        Some("csv") => read_csv(path, b','),
+        Some("tsv") | Some("tab") => read_csv(path, b'\t'),
+        Some("json") => read_json(path),
+        _ => Err(DBError::Validation(
+            "only CSV, TSV and JSON files can be imported".to_owned(),
+        )),
+    }
+}
+
+fn read_csv(path: &Path, delimiter: u8) -> Result<Table, DBError> {
+    let mut reader = csv::ReaderBuilder::new()
+        .delimiter(delimiter)
+        .flexible(true)
+        .from_path(path)?;
+    let columns: Vec<String> = reader
+        .headers()?
+        .iter()
+        .map(|header| header.trim().to_string())
+        .collect();
+
+    let mut rows = Vec::new();
+    for record in reader.records() {
+        let record = record?;
+        let row: HashMap<String, Cell> = columns
+            .iter()
+            .zip(record.iter())
+            .map(|(column, value)| (column.clone(), Cell::Text(value.to_string())))
+            .collect();
+        if row.values().any(|cell| !cell.is_empty()) {
+            rows.push(row);
+        }
+    }
+    Ok((columns, rows))
+}
+
+fn read_json(path: &Path) -> Result<Table, DBError> {
+    let json: Value = serde_json::from_str(&fs::read_to_string(path)?)?;
+    let objects = match json {
+        Value::Array(objects) => objects,
+        Value::Object(mut object) => match object.remove("recipes") {
+            Some(Value::Array(objects)) => objects,
+            _ => vec![Value::Object(object)],
+        },
+        _ => Vec::new(),
+    };
+
+    let mut columns = Vec::new();
+    let mut rows = Vec::new();
+    for object in objects {
+        let mut row = HashMap::new();
+        flatten("", object, &mut row);
+        for column in row.keys() {
+            if !columns.contains(column) {
+                columns.push(column.clone());
+            }
+        }
+        rows.push(row);
+    }
+    Ok((columns, rows))
+}
+
+fn flatten(prefix: &str, value: Value, row: &mut HashMap<String, Cell>) {
+    let cell = match value {
+        Value::Object(object) => {
+            for (key, value) in object {
+                let column = match prefix {
+                    "" => key,
+                    prefix => format!("{prefix}.{key}"),
+                };
+                flatten(&column, value, row);
+            }
+            return;
+        }
+        Value::Array(items) => Cell::List(items.iter().filter_map(json_text).collect()),
+        value => Cell::Text(json_text(&value).unwrap_or_default()),
+    };
+    row.insert(prefix.to_string(), cell);
+}
+
+/// Text of a JSON value in a list. Objects, such as schema.org `HowToStep`s, are read by their
+/// `text` or `name`.
+fn json_text(value: &Value) -> Option<String> {
+    match value {
+        Value::Null => None,
+        Value::String(text) => Some(text.clone()),
+        Value::Object(object) => object
+            .get("text")
+            .or_else(|| object.get("name"))
+            .and_then(json_text),
+        value => Some(value.to_string()),
+    }
+}
+
+fn split_list(text: &str, split: ListSplit) -> Vec<String> {
+    let items: Vec<String> = match split {
+        ListSplit::Lines => text.lines().map(str::to_string).collect(),
+        ListSplit::Semicolons => text.split(';').map(str::to_string).collect(),
+        ListSplit::Commas => text.split(',').map(str::to_string).collect(),
+        ListSplit::Pipes => text.split('|').map(str::to_string).collect(),
+        ListSplit::Numbered => {
+            let mut items = vec![String::new()];
+            for word in text.split_whitespace() {
+                match strip_step_number(word) {
+                    Some("") => items.push(String::new()),
+                    _ => {
+                        if let Some(item) = items.last_mut() {
+                            if !item.is_empty() {
+                                item.push(' ');
+                            }
+                            item.push_str(word);
+                        }
+                    }
+                }
+            }
+            items
+        }
+    };
+
+    items
+        .iter()
+        .map(|item| {
+            let item = item.trim();
+            strip_bullet(strip_step_number(item).unwrap_or(item)).to_string()
+        })
+        .filter(|item| !item.is_empty())
+        .collect()
+}
+
+/// Reads a date such as `2023-04-01`, `2023-04-01 12:30` or `2023-04-01T12:30:00Z` into the
+/// format SQLite's `CURRENT_TIMESTAMP` writes. Fractions of a second and time zones are
+/// dropped.
+fn parse_date(text: &str) -> Option<String> {
+    let date = text.get(..10)?;
+    let is_date = date.char_indices().all(|(index, c)| match index {
+        4 | 7 => c == '-',
+        _ => c.is_ascii_digit(),
+    });
+    if !is_date {
+        return None;
+    }
+
+    let time = match &text[10..] {
+        "" => return Some(format!("{date} 00:00:00")),
+        rest => rest.strip_prefix(['T', ' '])?,
+    };
+    let is_time = |time: &str| {
+        time.char_indices().all(|(index, c)| match index {
+            2 | 5 => c == ':',
+            _ => c.is_ascii_digit(),
+        })
+    };
+    let (time, zone) = match (time.get(..8), time.get(..5)) {
+        (Some(seconds), _) if is_time(seconds) => (seconds.to_string(), &time[8..]),
+        (_, Some(minutes)) if is_time(minutes) => (format!("{minutes}:00"), &time[5..]),
+        _ => return None,
+    };
+    match zone.is_empty() || zone.starts_with(['.', 'Z', '+', '-']) {
+        true => Some(format!("{date} {time}")),
+        false => None,
+    }
+}
+
+/// Reads a recipe and its tags from a row, along with the reasons the row is invalid.
+fn to_recipe(
+    row: &HashMap<String, Cell>,
+    mappings: &[FieldMapping],
+) -> (RecipeDetails, Vec<String>, Vec<String>) {
+    let mut recipe = RecipeDetails::default();
+    let mut tags = Vec::new();
+    let mut errors = Vec::new();
+
+    for mapping in mappings {
+        let cell = match row.get(&mapping.column) {
+            Some(cell) if !cell.is_empty() => cell,
+            _ => continue,
+        };
+        let text = cell.text();
+        let default_split = match mapping.field {
+            RecipeField::Tags => ListSplit::Commas,
+            _ => ListSplit::Lines,
+        };
+        let items = || cell.items(mapping.split.unwrap_or(default_split));
+
+        match mapping.field {
+            RecipeField::Title => recipe.title = text,
+            RecipeField::Url => match Url::parse(&text) {
+                Ok(_) => recipe.url = Some(text),
+                Err(_) => errors.push(format!("\"{text}\" isn't a URL")),
+            },
+            RecipeField::Image => recipe.image = text,
+            RecipeField::Source => recipe.source = Some(text),
+            RecipeField::Servings => match parse_servings(&text) {
+                Some(servings) => recipe.servings = servings,
+                None => errors.push(format!("Servings \"{text}\" aren't a number")),
+            },
+            RecipeField::Time => match parse_minutes(&text) {
+                Some(time) => recipe.time = time,
+                None => errors.push(format!("Time \"{text}\" isn't a duration")),
+            },
+            RecipeField::Calories => match parse_calories(&format!("calories: {text}")) {
+                Some(calories) => recipe.calories = calories,
+                None => errors.push(format!("Calories \"{text}\" aren't a number")),
+            },
+            RecipeField::Ingredients => recipe
+                .ingredients
+                .get_or_insert_with(Vec::new)
+                .extend(items()),
+            RecipeField::Instructions => recipe
+                .instructions
+                .get_or_insert_with(Vec::new)
+                .extend(items()),
+            RecipeField::Tags => tags.extend(items()),
+            RecipeField::DateAdded => match parse_date(&text) {
+                Some(date) => recipe.date_added = Some(date),
+                None => errors.push(format!("Date added \"{text}\" isn't a date")),
+            },
+        }
+    }
+
+    if recipe.title.is_empty() {
+        errors.push("Title is missing".to_string());
+    }
+    if recipe.source.is_none() {
+        recipe.source = recipe
+            .url
+            .as_deref()
+            .and_then(|url| Url::parse(url).ok())
+            .and_then(|url| url.host_str().map(str::to_string));
+    }
+    (recipe, tags, errors)
+}
+
+/// Imports the recipes of a CSV, TSV or JSON file, filling recipe fields from columns as
+/// `mappings` says. Every row is validated first, then all valid rows are saved in a single
+/// transaction. Nothing is saved in a dry run, or when a row is invalid unless `skip_invalid`
+/// is set, so the report can be reviewed before importing.
+pub fn import_table(
+    db: &Database,
+    path: &Path,
+    mappings: &[FieldMapping],
+    dry_run: bool,
+    skip_invalid: bool,
+) -> Result<TableImportReport, DBError> {
+    if !mappings
+        .iter()
+        .any(|mapping| mapping.field == RecipeField::Title)
+    {
+        return Err(DBError::Validation(
+            "a column must be mapped to the title".to_owned(),
+        ));
+    }
+    let (columns, rows) = read_table(path)?;
+    if let Some(missing) = mappings
+        .iter()
+        .find(|mapping| !columns.contains(&mapping.column))
+    {
+        return Err(DBError::Validation(format!(
+            "column \"{}\" isn't in the file",
+            missing.column
+        )));
+    }
+
+    let mut report = TableImportReport::default();
+    for (index, row) in rows.iter().enumerate() {
+        let (recipe, tags, errors) = to_recipe(row, mappings);
+        let status = match errors.is_empty() {
+            true => ImportStatus::Imported,
+            false => ImportStatus::Failed,
+        };
+        report.rows.push(TableRowResult {
+            row: index as u32 + 1,
+            recipe,
+            tags,
+            status,
+            recipe_id: None,
+            errors,
+        });
+    }
+
+    let mut valid_rows: Vec<&mut TableRowResult> = report
+        .rows
+        .iter_mut()
+        .filter(|row| row.status == ImportStatus::Imported)
+        .collect();
+    let has_invalid_rows = valid_rows.len() < rows.len();
+
+    if dry_run || has_invalid_rows && !skip_invalid {
+        for row in valid_rows {
+            if let Some(existing_id) = db.find_duplicate_recipe(&row.recipe)? {
+                row.status = ImportStatus::Duplicate;
+                row.recipe_id = Some(existing_id);
+            }
+        }
+        return Ok(report);
+    }
+
+    let batch: Vec<(RecipeDetails, Vec<String>)> = valid_rows
+        .iter()
+        .map(|row| (row.recipe.clone(), row.tags.clone()))
+        .collect();
+    let outcomes = db.import_recipes(&batch)?;
+    for (row, (status, id)) in valid_rows.iter_mut().zip(outcomes) {
+        row.status = status;
+        row.recipe_id = Some(id);
+    }
+    report.committed = true;
+    Ok(report)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reads_dates_with_and_without_a_time() {
+        assert_eq!(
+            parse_date("2023-04-01").as_deref(),
+            Some("2023-04-01 00:00:00")
+        );
+        assert_eq!(
+            parse_date("2023-04-01 12:30").as_deref(),
+            Some("2023-04-01 12:30:00")
+        );
+        assert_eq!(
+            parse_date("2023-04-01T12:30:15.250+02:00").as_deref(),
+            Some("2023-04-01 12:30:15")
+        );
+    }
+
+    #[test]
+    fn rejects_text_that_isnt_a_date() {
+        assert_eq!(parse_date("2023-04-01 garbage!"), None);
+        assert_eq!(parse_date("2023-04-01T12:3x:00"), None);
+        assert_eq!(parse_date("2023-04-01 12:30:15 tomorrow"), None);
+        assert_eq!(parse_date("04/01/2023"), None);
+    }
+
+    /// Writes `content` to a file of the temporary directory with the given extension, to be
+    /// read like an imported file.
+    fn table_file(content: &str, extension: &str) -> std::path::PathBuf {
+        let path = std::env::temp_dir().join(format!("{}.{extension}", uuid::Uuid::new_v4()));
+        fs::write(&path, content).unwrap();
+        path
+    }
+
+    fn mapping(column: &str, field: RecipeField, split: Option<ListSplit>) -> FieldMapping {
+        FieldMapping {
+            column: column.to_string(),
+            field,
+            split,
+        }
+    }
+
+    #[test]
+    fn reads_recipes_from_csv() {
+        let path = table_file(
+            "Name,Link,Serves,Ingredients,Method,Labels,Added
+Shakshuka,https://www.example.com/shakshuka,4,\"1 onion
+6 eggs
+1 can tomatoes\",\"1. Soften the onion. 2. Add the tomatoes. 3) Crack in the eggs.\",\"Brunch, Eggs\",2022-11-05 08:15
+,,,,,,
+Toast,not a link,lots,,,,
+",
+            "csv",
+        );
+        let (columns, rows) = read_table(&path).unwrap();
+        fs::remove_file(&path).unwrap();
+        assert_eq!(
+            columns,
+            vec![
+                "Name",
+                "Link",
+                "Serves",
+                "Ingredients",
+                "Method",
+                "Labels",
+                "Added"
+            ]
+        );
+        assert_eq!(rows.len(), 2);
+
+        let mappings = [
+            mapping("Name", RecipeField::Title, None),
+            mapping("Link", RecipeField::Url, None),
+            mapping("Serves", RecipeField::Servings, None),
+            mapping("Ingredients", RecipeField::Ingredients, None),
+            mapping(
+                "Method",
+                RecipeField::Instructions,
+                Some(ListSplit::Numbered),
+            ),
+            mapping("Labels", RecipeField::Tags, None),
+            mapping("Added", RecipeField::DateAdded, None),
+        ];
+        let (recipe, tags, errors) = to_recipe(&rows[0], &mappings);
+        assert!(errors.is_empty());
+        assert_eq!(recipe.title, "Shakshuka");
+        assert_eq!(recipe.source.as_deref(), Some("www.example.com"));
+        assert_eq!(recipe.servings, 4);
+        assert_eq!(recipe.ingredients.map(|lines| lines.len()), Some(3));
+        assert_eq!(
+            recipe.instructions,
+            Some(vec![
+                "Soften the onion.".to_string(),
+                "Add the tomatoes.".to_string(),
+                "Crack in the eggs.".to_string(),
+            ])
+        );
+        assert_eq!(tags, vec!["Brunch", "Eggs"]);
+        assert_eq!(recipe.date_added.as_deref(), Some("2022-11-05 08:15:00"));
+
+        let (_, _, errors) = to_recipe(&rows[1], &mappings);
+        assert_eq!(
+            errors,
+            vec![
+                "\"not a link\" isn't a URL",
+                "Servings \"lots\" aren't a number",
+            ]
+        );
+    }
+
+    #[test]
+    fn flattens_nested_json() {
+        let path = table_file(
+            r#"{
+                "recipes": [
+                    {
+                        "title": "Miso Soup",
+                        "details": { "servings": 2, "time": { "total": "PT15M" } },
+                        "ingredients": ["4 cups dashi", "3 tbsp miso", null],
+                        "steps": [
+                            { "@type": "HowToStep", "text": "Warm the dashi." },
+                            { "@type": "HowToStep", "text": "Whisk in the miso." }
+                        ]
+                    },
+                    { "title": "Onigiri", "details": { "servings": 6 } }
+                ]
+            }"#,
+            "json",
+        );
+        let (columns, rows) = read_table(&path).unwrap();
+        fs::remove_file(&path).unwrap();
+        assert!(columns.contains(&"details.time.total".to_string()));
+        assert_eq!(rows.len(), 2);
+
+        let mappings = [
+            mapping("title", RecipeField::Title, None),
+            mapping("details.servings", RecipeField::Servings, None),
+            mapping("details.time.total", RecipeField::Time, None),
+            mapping("ingredients", RecipeField::Ingredients, None),
+            mapping("steps", RecipeField::Instructions, None),
+        ];
+        let (recipe, _, errors) = to_recipe(&rows[0], &mappings);
+        assert!(errors.is_empty());
+        assert_eq!(recipe.servings, 2);
+        assert_eq!(recipe.time, 15);
+        assert_eq!(
+            recipe.ingredients,
+            Some(vec!["4 cups dashi".to_string(), "3 tbsp miso".to_string()])
+        );
+        assert_eq!(
+            recipe.instructions,
+            Some(vec![
+                "Warm the dashi.".to_string(),
+                "Whisk in the miso.".to_string(),
+            ])
+        );
+
+        let (recipe, _, errors) = to_recipe(&rows[1], &mappings);
+        assert!(errors.is_empty());
+        assert_eq!((recipe.servings, recipe.time), (6, 0));
+    }
+}
@@ -0,0 +1,422 @@
+use tauri::{AppHandle, State};
+use url::Url;
+
+use crate::{
+    error::{DBError, RequestError},
+    types::{
+        client::ReqwestClient,
+        db::Database,
+        import::{ImportStatus, StepOutcome, StepStatus, UrlImportReport, UrlImportStep},
+        recipe::{DuplicatePolicy, RecipeData, RecipeDetails},
+        response::{check_if_scrapable, scrapable_domains},
+    },
+    utils::{
+        canonical_url::canonical_link,
+        image::{delete_image, download_image},
+        json::has_recipe,
+        parse::{parse_calories, parse_servings},
+        recipe_scrapers::scrape_recipe_from_url,
+        request::build_request,
+    },
+};
+
+const STEPS: [UrlImportStep; 5] = [
+    UrlImportStep::Validate,
+    UrlImportStep::Scrape,
+    UrlImportStep::Map,
+    UrlImportStep::DownloadImage,
+    UrlImportStep::Save,
+];
+
+/// What validating a recipe page found out about it.
+struct Page {
+    /// Whether recipe-scrapers has a scraper for the site, rather than reading its schema.org
+    /// markup
+    has_scraper: bool,
+    canonical_url: Option<String>,
+}
+
+/// Why a step failed, and whether trying again later could get past it.
+struct StepFailure {
+    message: String,
+    /// Set for network errors, timeouts and server errors, which may not last
+    retryable: bool,
+}
+
+impl StepFailure {
+    fn permanent(message: String) -> Self {
+        StepFailure {
+            message,
+            retryable: false,
+        }
+    }
+
+    fn temporary(message: String) -> Self {
+        StepFailure {
+            message,
+            retryable: true,
+        }
+    }
+}
+
+fn record(
+    report: &mut UrlImportReport,
+    step: UrlImportStep,
+    status: StepStatus,
+    messages: Vec<String>,
+) {
+    report.steps.push(StepOutcome {
+        step,
+        status,
+        messages,
+        retryable: false,
+    });
+}
+
+fn record_failure(report: &mut UrlImportReport, step: UrlImportStep, failure: StepFailure) {
+    report.steps.push(StepOutcome {
+        step,
+        status: StepStatus::Failed,
+        messages: vec![failure.message],
+        retryable: failure.retryable,
+    });
+}
+
+/// Records the steps after the last one recorded as skipped, so that the report lists them
+/// all.
+fn skip_remaining(report: &mut UrlImportReport, reason: &str) {
+    for step in STEPS.iter().skip(report.steps.len()) {
+        record(report, *step, StepStatus::Skipped, vec![reason.to_string()]);
+    }
+}
+
+/// Loads the page to check it has a recipe, either one recipe-scrapers has a scraper for or
+/// one described by schema.org markup.
+async fn validate_page(client: State<'_, ReqwestClient>, url: &str) -> Result<Page, StepFailure> {
+    let not_a_web_address = || StepFailure::permanent(format!("\"{url}\" isn't a web address"));
+    let parsed_url = Url::parse(url).map_err(|_| not_a_web_address())?;
+    if !matches!(parsed_url.scheme(), "http" | "https") {
+        return Err(not_a_web_address());
+    }
+
+    let request = build_request(url, None, client)
+        .await
+        .map_err(|error| StepFailure::permanent(error.to_string()))?;
+    let response = request.send().await.map_err(|error| {
+        // Errors raised by the cache rather than on the way to the site won't go away
+        let is_network_error = match &error {
+            reqwest_middleware::Error::Reqwest(error) => {
+                error.is_timeout() || error.is_connect() || error.is_request()
+            }
+            reqwest_middleware::Error::Middleware(_) => false,
+        };
+        let message = format!(
+            "Page couldn't be loaded: {}",
+            RequestError::Middleware(error)
+        );
+        match is_network_error {
+            true => StepFailure::temporary(message),
+            false => StepFailure::permanent(message),
+        }
+    })?;
+    let status = response.status();
+    if !status.is_success() {
+        let message = format!("Page responded with {status}");
+        return Err(match status.is_server_error() {
+            true => StepFailure::temporary(message),
+            false => StepFailure::permanent(message),
+        });
+    }
+    let body = response.text().await.map_err(|error| {
+        let is_network_error = error.is_timeout() || error.is_body();
+        let message = format!("Page couldn't be read: {}", RequestError::Reqwest(error));
+        match is_network_error {
+            true => StepFailure::temporary(message),
+            false => StepFailure::permanent(message),
+        }
+    })?;
+
+    let has_scraper = check_if_scrapable(url, &scrapable_domains()).unwrap_or(false);
+    if !has_scraper && !has_recipe(body.clone()).unwrap_or(false) {
+        return Err(StepFailure::permanent(
+            "No recipe was found on the page".to_string(),
+        ));
+    }
+    Ok(Page {
+        has_scraper,
+        canonical_url: canonical_link(&body, url),
+    })
+}
+
+fn scrape(url: &str, page: &Page) -> Result<RecipeData, String> {
+    let json = scrape_recipe_from_url(url, !page.has_scraper)
+        .map_err(|error| RequestError::PyO3(error).to_string())?;
+    serde_json::from_str(&json).map_err(|error| RequestError::Serde(error).to_string())
+}
+
+/// Reads scraped data into a recipe, noting in `messages` the details the page was missing.
+fn recipe_from_data(
+    url: &str,
+    data: &RecipeData,
+    canonical_url: Option<String>,
+    messages: &mut Vec<String>,
+) -> Result<RecipeDetails, String> {
+    let title = data
+        .title
+        .as_deref()
+        .map(str::trim)
+        .filter(|title| !title.is_empty())
+        .ok_or("Page has no recipe title")?;
+
+    let servings = data.yields.as_deref().and_then(parse_servings);
+    let time = match data.total_time.filter(|time| *time > 0.0) {
+        Some(total_time) => Some(total_time),
+        None => match (data.prep_time, data.cook_time) {
+            (None, None) => None,
+            (prep_time, cook_time) => Some(prep_time.unwrap_or(0.0) + cook_time.unwrap_or(0.0)),
+        },
+    };
+    let calories = data
+        .nutrients
+        .as_ref()
+        .and_then(|nutrients| nutrients.get("calories"))
+        .and_then(|calories| parse_calories(&format!("calories: {calories}")));
+
+    let instructions = match &data.instructions_list {
+        Some(steps) if !steps.is_empty() => Some(steps.clone()),
+        _ => data.instructions.as_ref().map(|instructions| {
+            instructions
+                .lines()
+                .map(str::trim)
+                .filter(|step| !step.is_empty())
+                .map(str::to_string)
+                .collect()
+        }),
+    };
+
+    let missing = [
+        (servings.is_none(), "No servings were found on the page"),
+        (time.is_none(), "No cooking time was found on the page"),
+        (calories.is_none(), "No calories were found on the page"),
+        (data.image.is_none(), "No image was found on the page"),
+        (
+            data.ingredients.is_none(),
+            "No ingredients were found on the page",
+        ),
+        (
+            instructions.is_none(),
+            "No instructions were found on the page",
+        ),
+    ];
+    for (is_missing, message) in missing {
+        if is_missing {
+            messages.push(message.to_string());
+        }
+    }
+
+    Ok(RecipeDetails {
+        image: data.image.clone().unwrap_or_default(),
+        url: Some(url.to_string()),
+        servings: servings.unwrap_or(0),
+        time: time.map_or(0, |time| time.round() as i32),
+        calories: calories.unwrap_or(0),
+        source: data.host.clone().or_else(|| data.site_name.clone()),
+        ingredients: data.ingredients.clone(),
+        instructions,
+        title: title.to_string(),
+        canonical_url: canonical_url.or_else(|| data.canonical_url.clone()),
+        ..Default::default()
+    })
+}
+
+/// Saves the recipe on the page at `url`: validates the page, scrapes it, reads the scraped
+/// data into a recipe, downloads its image and saves it. A recipe that is already saved is
+/// merged into the saved one or left alone, depending on `on_duplicate`. Failures are
+/// reported as the outcome of the step they happened in rather than returned as errors.
+pub async fn import_recipe_from_url(
+    client: State<'_, ReqwestClient>,
+    db: &Database,
+    url: &str,
+    on_duplicate: DuplicatePolicy,
+    app: AppHandle,
+) -> UrlImportReport {
+    let mut report = UrlImportReport::default();
+    let url = url.trim();
+
+    let page = match validate_page(client.clone(), url).await {
+        Ok(page) => page,
+        Err(failure) => {
+            record_failure(&mut report, UrlImportStep::Validate, failure);
+            skip_remaining(&mut report, "The page couldn't be validated");
+            return report;
+        }
+    };
+    record(
+        &mut report,
+        UrlImportStep::Validate,
+        StepStatus::Succeeded,
+        Vec::new(),
+    );
+
+    let data = match scrape(url, &page) {
+        Ok(data) => data,
+        Err(message) => {
+            record(
+                &mut report,
+                UrlImportStep::Scrape,
+                StepStatus::Failed,
+                vec![message],
+            );
+            skip_remaining(&mut report, "The page couldn't be scraped");
+            return report;
+        }
+    };
+    record(
+        &mut report,
+        UrlImportStep::Scrape,
+        StepStatus::Succeeded,
+        Vec::new(),
+    );
+
+    let mut messages = Vec::new();
+    let recipe = match recipe_from_data(url, &data, page.canonical_url, &mut messages) {
+        Ok(recipe) => recipe,
+        Err(message) => {
+            record(
+                &mut report,
+                UrlImportStep::Map,
+                StepStatus::Failed,
+                vec![message],
+            );
+            skip_remaining(&mut report, "No recipe could be read from the page");
+            return report;
+        }
+    };
+    record(
+        &mut report,
+        UrlImportStep::Map,
+        StepStatus::Succeeded,
+        messages,
+    );
+    report.recipe = Some(recipe.clone());
+
+    match db.find_duplicate_recipe(&recipe) {
+        Ok(Some(existing_id)) => {
+            report.status = ImportStatus::Duplicate;
+            report.recipe_id = Some(existing_id);
+            record(
+                &mut report,
+                UrlImportStep::DownloadImage,
+                StepStatus::Skipped,
+                vec!["The recipe is already saved".to_string()],
+            );
+            match on_duplicate {
+                DuplicatePolicy::Reject => {
+                    skip_remaining(&mut report, "The recipe is already saved")
+                }
+                DuplicatePolicy::Merge => match db.merge_recipe(&existing_id, &recipe, None) {
+                    Ok(_) => record(
+                        &mut report,
+                        UrlImportStep::Save,
+                        StepStatus::Succeeded,
+                        vec!["Merged into the saved recipe".to_string()],
+                    ),
+                    Err(error) => record(
+                        &mut report,
+                        UrlImportStep::Save,
+                        StepStatus::Failed,
+                        vec![error.to_string()],
+                    ),
+                },
+            }
+            return report;
+        }
+        Ok(None) => {}
+        Err(error) => {
+            record(
+                &mut report,
+                UrlImportStep::DownloadImage,
+                StepStatus::Skipped,
+                vec!["The library couldn't be checked for the recipe".to_string()],
+            );
+            record(
+                &mut report,
+                UrlImportStep::Save,
+                StepStatus::Failed,
+                vec![error.to_string()],
+            );
+            return report;
+        }
+    }
+
+    // A missing image doesn't stop the recipe from being saved with its remote image
+    let downloaded_image = match &recipe.image {
+        image if image.is_empty() => {
+            record(
+                &mut report,
+                UrlImportStep::DownloadImage,
+                StepStatus::Skipped,
+                vec!["The page has no image".to_string()],
+            );
+            None
+        }
+        image => match download_image(client, db, image, &[], app.clone()).await {
+            Ok(Some(path)) => {
+                record(
+                    &mut report,
+                    UrlImportStep::DownloadImage,
+                    StepStatus::Succeeded,
+                    Vec::new(),
+                );
+                Some(path)
+            }
+            Ok(None) => {
+                record(
+                    &mut report,
+                    UrlImportStep::DownloadImage,
+                    StepStatus::Failed,
+                    vec!["The image couldn't be downloaded".to_string()],
+                );
+                None
+            }
+            Err(error) => {
+                record(
+                    &mut report,
+                    UrlImportStep::DownloadImage,
+                    StepStatus::Failed,
+                    vec![error.to_string()],
+                );
+                None
+            }
+        },
+    };
+
+    match db.add_recipe(&recipe, downloaded_image.clone(), None) {
+        Ok(id) => {
+            report.status = ImportStatus::Imported;
+            report.recipe_id = Some(id);
+            record(
+                &mut report,
+                UrlImportStep::Save,
+                StepStatus::Succeeded,
+                Vec::new(),
+            );
+        }
+        Err(error) => {
+            if let Some(image) = &downloaded_image {
+                let _ = delete_image(db, image, app);
+            }
+            if let DBError::Duplicate(existing_id) = error {
+                report.status = ImportStatus::Duplicate;
+                report.recipe_id = Some(existing_id);
+            }
+            record(
+                &mut report,
+                UrlImportStep::Save,
+                StepStatus::Failed,
+                vec![error.to_string()],
+            );
+        }
+    }
+    report
+}